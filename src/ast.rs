@@ -1,8 +1,15 @@
 // Author: Dustin Pilgrim
 // License: MIT
 
+use std::fmt;
+use std::ops::Index;
+
+use base64::Engine;
+use base64::engine::general_purpose::STANDARD as BASE64;
 use regex::Regex;
 
+use crate::{RuneError, RuneErrorCode};
+
 #[derive(Debug, Clone)]
 pub enum Condition {
     Equals(String, Value),
@@ -45,16 +52,31 @@ pub struct IfBlock {
 
 #[derive(Debug, Clone)]
 pub enum Value {
+    /// Text; a `$` in it is a plain character, never an interpolation.
     String(String),
-    Number(f64),
+    /// An integer literal such as `8080`.
+    Int(i64),
+    /// A literal with a decimal point, such as `0.5` or `30.0`.
+    Float(f64),
     Bool(bool),
     Regex(Regex),
+    /// Binary data from a `b64"..."` literal.
+    Bytes(Vec<u8>),
+    /// A `t"..."` timestamp literal, keeping the offset it was written with.
+    #[cfg(feature = "chrono")]
+    DateTime(chrono::DateTime<chrono::FixedOffset>),
     Array(Vec<Value>),
 
     /// Object blocks now contain items (assignments + if-blocks)
     Object(Vec<ObjectItem>),
 
     Reference(Vec<String>),
+    /// A `$namespace.path` reference such as `$runtime.port`, `$file.secret` or a
+    /// provider's `$vault.key`, resolved through its namespace even where a config key
+    /// has the same name. `$env` and `$sys` are expanded while parsing instead.
+    Namespaced(Vec<String>),
+    /// A string with `$` references left for `get` time, e.g. `"v$var.version"`: its
+    /// literal text as `String` parts between `Namespaced` ones.
     Interpolated(Vec<Value>),
 
     /// Inline/value conditional: `x = if cond a else b`
@@ -67,12 +89,19 @@ impl PartialEq for Value {
     fn eq(&self, other: &Self) -> bool {
         match (self, other) {
             (Value::String(a), Value::String(b)) => a == b,
-            (Value::Number(a), Value::Number(b)) => a == b,
+            (Value::Int(a), Value::Int(b)) => a == b,
+            (Value::Float(a), Value::Float(b)) => a == b,
+            // Compare numerically so `if ratio = 1` still matches `ratio 1.0`.
+            (Value::Int(a), Value::Float(b)) | (Value::Float(b), Value::Int(a)) => *a as f64 == *b,
             (Value::Bool(a), Value::Bool(b)) => a == b,
             (Value::Regex(a), Value::Regex(b)) => a.as_str() == b.as_str(),
+            (Value::Bytes(a), Value::Bytes(b)) => a == b,
+            #[cfg(feature = "chrono")]
+            (Value::DateTime(a), Value::DateTime(b)) => a == b,
             (Value::Array(a), Value::Array(b)) => a == b,
             (Value::Object(a), Value::Object(b)) => a == b,
             (Value::Reference(a), Value::Reference(b)) => a == b,
+            (Value::Namespaced(a), Value::Namespaced(b)) => a == b,
             (Value::Interpolated(a), Value::Interpolated(b)) => a == b,
             (Value::Conditional(a), Value::Conditional(b)) => a == b,
             (Value::Null, Value::Null) => true,
@@ -90,6 +119,86 @@ impl Value {
         }
     }
 
+    pub fn as_str(&self) -> Option<&str> {
+        if let Value::String(s) = self {
+            Some(s)
+        } else {
+            None
+        }
+    }
+
+    /// Alias for [`as_str`](Self::as_str).
+    pub fn as_string(&self) -> Option<&str> {
+        self.as_str()
+    }
+
+    /// Either numeric variant as an `f64`.
+    pub fn as_f64(&self) -> Option<f64> {
+        match self {
+            Value::Int(n) => Some(*n as f64),
+            Value::Float(n) => Some(*n),
+            _ => None,
+        }
+    }
+
+    /// Alias for [`as_f64`](Self::as_f64).
+    pub fn as_number(&self) -> Option<f64> {
+        self.as_f64()
+    }
+
+    /// The number as an `i64`, only when it is integral and fits (no silent truncation).
+    pub fn as_i64(&self) -> Option<i64> {
+        // `i64::MAX as f64` rounds up to 2^63, which itself is out of range.
+        const UPPER: f64 = 9_223_372_036_854_775_808.0;
+        match self {
+            Value::Int(n) => Some(*n),
+            Value::Float(n) if n.fract() == 0.0 && *n >= -UPPER && *n < UPPER => Some(*n as i64),
+            _ => None,
+        }
+    }
+
+    /// The number as a `u64`, only when it is integral, non-negative, and fits.
+    pub fn as_u64(&self) -> Option<u64> {
+        const UPPER: f64 = 18_446_744_073_709_551_616.0;
+        match self {
+            Value::Int(n) => u64::try_from(*n).ok(),
+            Value::Float(n) if n.fract() == 0.0 && *n >= 0.0 && *n < UPPER => Some(*n as u64),
+            _ => None,
+        }
+    }
+
+    pub fn as_bool(&self) -> Option<bool> {
+        if let Value::Bool(b) = self {
+            Some(*b)
+        } else {
+            None
+        }
+    }
+
+    pub fn as_bytes(&self) -> Option<&[u8]> {
+        if let Value::Bytes(bytes) = self {
+            Some(bytes)
+        } else {
+            None
+        }
+    }
+
+    pub fn as_array(&self) -> Option<&[Value]> {
+        if let Value::Array(values) = self {
+            Some(values)
+        } else {
+            None
+        }
+    }
+
+    pub fn as_reference(&self) -> Option<&[String]> {
+        if let Value::Reference(path) = self {
+            Some(path)
+        } else {
+            None
+        }
+    }
+
     pub fn as_regex(&self) -> Option<&Regex> {
         if let Value::Regex(r) = self {
             Some(r)
@@ -98,6 +207,173 @@ impl Value {
         }
     }
 
+    pub fn is_string(&self) -> bool {
+        matches!(self, Value::String(_))
+    }
+
+    /// True for both `Int` and `Float`.
+    pub fn is_number(&self) -> bool {
+        matches!(self, Value::Int(_) | Value::Float(_))
+    }
+
+    pub fn is_int(&self) -> bool {
+        matches!(self, Value::Int(_))
+    }
+
+    pub fn is_float(&self) -> bool {
+        matches!(self, Value::Float(_))
+    }
+
+    pub fn is_bool(&self) -> bool {
+        matches!(self, Value::Bool(_))
+    }
+
+    pub fn is_bytes(&self) -> bool {
+        matches!(self, Value::Bytes(_))
+    }
+
+    pub fn is_array(&self) -> bool {
+        matches!(self, Value::Array(_))
+    }
+
+    pub fn is_object(&self) -> bool {
+        matches!(self, Value::Object(_))
+    }
+
+    /// True for both `Reference` and `Namespaced`.
+    pub fn is_reference(&self) -> bool {
+        matches!(self, Value::Reference(_) | Value::Namespaced(_))
+    }
+
+    pub fn is_null(&self) -> bool {
+        matches!(self, Value::Null)
+    }
+
+    /// The variant's name for messages, e.g. `"string"` or `"int"`; these match the
+    /// type names schemas use.
+    pub fn type_name(&self) -> &'static str {
+        match self {
+            Value::String(_) => "string",
+            Value::Int(_) => "int",
+            Value::Float(_) => "float",
+            Value::Bool(_) => "bool",
+            Value::Regex(_) => "regex",
+            Value::Bytes(_) => "bytes",
+            #[cfg(feature = "chrono")]
+            Value::DateTime(_) => "datetime",
+            Value::Array(_) => "array",
+            Value::Object(_) => "object",
+            Value::Reference(_) | Value::Namespaced(_) => "reference",
+            Value::Interpolated(_) => "interpolated",
+            Value::Conditional(_) => "conditional",
+            Value::Null => "null",
+        }
+    }
+
+    /// The object entry named `key`, or `None` for a missing key or a non-object.
+    /// Unlike `value["key"]`, a miss is not confused with an explicit `null`.
+    pub fn get(&self, key: &str) -> Option<&Value> {
+        match self {
+            Value::Object(items) => items.iter().find_map(|item| match item {
+                ObjectItem::Assign(k, v) if k == key => Some(v),
+                _ => None,
+            }),
+            _ => None,
+        }
+    }
+
+    /// The array element at `index`, or `None` when out of bounds or not an array.
+    pub fn get_index(&self, index: usize) -> Option<&Value> {
+        match self {
+            Value::Array(values) => values.get(index),
+            _ => None,
+        }
+    }
+
+    /// Look up a nested value by dotted path, e.g. `"servers.0.host"`.
+    ///
+    /// Segments index object keys; numeric segments index arrays. Quoted segments
+    /// (`a."b.c"`) address keys containing dots. An empty path returns `self`.
+    pub fn get_path(&self, path: &str) -> Option<&Value> {
+        if path.is_empty() {
+            return Some(self);
+        }
+
+        let mut current = self;
+        for segment in crate::utils::split_path(path) {
+            current = current.child(&segment)?;
+        }
+        Some(current)
+    }
+
+    /// One step of a path: the object entry named `segment`, or the array element at
+    /// `segment` when it is a number.
+    pub(crate) fn child(&self, segment: &str) -> Option<&Value> {
+        match self {
+            Value::Array(_) => self.get_index(segment.parse().ok()?),
+            _ => self.get(segment),
+        }
+    }
+
+    /// Deep-merge `other` over `self`, replacing arrays. See `merge_with`.
+    pub fn merge(&self, other: &Value) -> Value {
+        self.merge_with(other, MergeStrategy::Replace)
+    }
+
+    /// Deep-merge `other` over `self` and return the result.
+    ///
+    /// Objects merge key by key recursively; for any other pair `other` wins, except
+    /// arrays under `MergeStrategy::Concat`, which are appended. If-blocks from `other`
+    /// are appended as-is since they can't be matched by key.
+    pub fn merge_with(&self, other: &Value, strategy: MergeStrategy) -> Value {
+        match (self, other) {
+            (Value::Object(base), Value::Object(overrides)) => {
+                let mut items = base.clone();
+                merge_object_items(&mut items, overrides, strategy);
+                Value::Object(items)
+            }
+            (Value::Array(base), Value::Array(extra)) if strategy == MergeStrategy::Concat => {
+                Value::Array(base.iter().chain(extra).cloned().collect())
+            }
+            _ => other.clone(),
+        }
+    }
+
+    /// Equality that ignores key order inside objects. Arrays stay ordered, and
+    /// if-blocks are compared in the order they appear.
+    pub fn eq_unordered(&self, other: &Value) -> bool {
+        match (self, other) {
+            (Value::Object(a), Value::Object(b)) => {
+                fn assigns(items: &[ObjectItem]) -> usize {
+                    items
+                        .iter()
+                        .filter(|item| matches!(item, ObjectItem::Assign(..)))
+                        .count()
+                }
+                fn if_blocks(items: &[ObjectItem]) -> Vec<&ObjectItem> {
+                    items
+                        .iter()
+                        .filter(|item| matches!(item, ObjectItem::IfBlock(_)))
+                        .collect()
+                }
+
+                assigns(a) == assigns(b)
+                    && if_blocks(a) == if_blocks(b)
+                    && a.iter().all(|item| match item {
+                        ObjectItem::Assign(key, value) => b.iter().any(|other| match other {
+                            ObjectItem::Assign(k, v) => k == key && value.eq_unordered(v),
+                            ObjectItem::IfBlock(_) => false,
+                        }),
+                        ObjectItem::IfBlock(_) => true,
+                    })
+            }
+            (Value::Array(a), Value::Array(b)) => {
+                a.len() == b.len() && a.iter().zip(b).all(|(x, y)| x.eq_unordered(y))
+            }
+            _ => self == other,
+        }
+    }
+
     pub fn matches(&self, text: &str) -> bool {
         match self {
             Value::Regex(r) => r.is_match(text),
@@ -107,9 +383,1303 @@ impl Value {
     }
 }
 
-#[derive(Debug, Clone, PartialEq)]
+/// Which top-level list an entry recorded in `Document::order` lives in.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum EntryKind {
+    Global,
+    Item,
+}
+
+/// How `Value::merge_with` combines two arrays found at the same key.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum MergeStrategy {
+    /// The overriding array replaces the base array.
+    #[default]
+    Replace,
+    /// The overriding array is appended to the base array.
+    Concat,
+}
+
+/// Merge `overrides` into `target` in place, keeping `target`'s key order.
+pub(crate) fn merge_object_items(
+    target: &mut Vec<ObjectItem>,
+    overrides: &[ObjectItem],
+    strategy: MergeStrategy,
+) {
+    for override_item in overrides {
+        let ObjectItem::Assign(override_key, override_value) = override_item else {
+            target.push(override_item.clone());
+            continue;
+        };
+
+        let Some(ObjectItem::Assign(_, target_value)) = target
+            .iter_mut()
+            .find(|item| matches!(item, ObjectItem::Assign(key, _) if key == override_key))
+        else {
+            target.push(override_item.clone());
+            continue;
+        };
+
+        *target_value = target_value.merge_with(override_value, strategy);
+    }
+}
+
+/// Merge `(key, value)` pairs by key, deep-merging values that exist on both sides.
+pub(crate) fn merge_named_values(target: &mut Vec<(String, Value)>, overrides: &[(String, Value)]) {
+    for (override_key, override_value) in overrides {
+        let Some((_, target_value)) = target.iter_mut().find(|(key, _)| key == override_key) else {
+            target.push((override_key.clone(), override_value.clone()));
+            continue;
+        };
+
+        *target_value = target_value.merge(override_value);
+    }
+}
+
+/// Shared sentinel returned by `Index` lookups that miss.
+static NULL: Value = Value::Null;
+
+/// `value["key"]` indexes an object by key.
+///
+/// Like `serde_json`, a missing key (or indexing a non-object) returns `Value::Null`
+/// rather than panicking, so chains like `value["server"]["port"]` stay cheap to write.
+/// Use `Value::get` or `Value::get_path` when you need to tell "missing" apart from an
+/// explicit `null`.
+impl Index<&str> for Value {
+    type Output = Value;
+
+    fn index(&self, key: &str) -> &Value {
+        self.get(key).unwrap_or(&NULL)
+    }
+}
+
+/// `value[0]` indexes an array; out-of-bounds (or a non-array) returns `Value::Null`.
+impl Index<usize> for Value {
+    type Output = Value;
+
+    fn index(&self, index: usize) -> &Value {
+        self.get_index(index).unwrap_or(&NULL)
+    }
+}
+
+macro_rules! impl_from_int {
+    ($($ty:ty),*) => {
+        $(
+            impl From<$ty> for Value {
+                fn from(n: $ty) -> Self {
+                    Value::Int(i64::from(n))
+                }
+            }
+        )*
+    };
+}
+
+impl_from_int!(i8, i16, i32, i64, u8, u16, u32);
+
+/// Falls back to `Value::Float` above `i64::MAX`.
+impl From<u64> for Value {
+    fn from(n: u64) -> Self {
+        i64::try_from(n).map_or(Value::Float(n as f64), Value::Int)
+    }
+}
+
+/// Falls back to `Value::Float` above `i64::MAX`.
+impl From<usize> for Value {
+    fn from(n: usize) -> Self {
+        i64::try_from(n).map_or(Value::Float(n as f64), Value::Int)
+    }
+}
+
+impl From<f32> for Value {
+    fn from(n: f32) -> Self {
+        Value::Float(f64::from(n))
+    }
+}
+
+impl From<f64> for Value {
+    fn from(n: f64) -> Self {
+        Value::Float(n)
+    }
+}
+
+impl From<bool> for Value {
+    fn from(b: bool) -> Self {
+        Value::Bool(b)
+    }
+}
+
+impl From<&str> for Value {
+    fn from(s: &str) -> Self {
+        Value::String(s.to_string())
+    }
+}
+
+impl From<String> for Value {
+    fn from(s: String) -> Self {
+        Value::String(s)
+    }
+}
+
+impl<T: Into<Value>> From<Vec<T>> for Value {
+    fn from(items: Vec<T>) -> Self {
+        Value::Array(items.into_iter().map(Into::into).collect())
+    }
+}
+
+/// `None` becomes `Value::Null`.
+impl<T: Into<Value>> From<Option<T>> for Value {
+    fn from(opt: Option<T>) -> Self {
+        opt.map_or(Value::Null, Into::into)
+    }
+}
+
+/// Collecting `(key, value)` pairs gives an object with the keys in iteration order:
+/// `[("port", 8080)].into_iter().collect::<Value>()`.
+impl<K: Into<String>, V: Into<Value>> FromIterator<(K, V)> for Value {
+    fn from_iter<I: IntoIterator<Item = (K, V)>>(pairs: I) -> Self {
+        Value::Object(
+            pairs
+                .into_iter()
+                .map(|(key, value)| ObjectItem::Assign(key.into(), value.into()))
+                .collect(),
+        )
+    }
+}
+
+/// Renders RUNE source rather than the `Debug` form.
+///
+/// Objects have no key of their own, so an object renders as its body: one item per
+/// line, with nested blocks written as `key:` ... `end`. Wrap it in a parent block
+/// (`root:` ... `end`) to get something the parser accepts at top level. Objects inside
+/// arrays are written inline as `{ key value }`, which cannot hold `if` blocks.
+///
+/// The alternate form, `{:#}`, writes non-empty arrays one element per line.
+impl fmt::Display for Value {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write_value(f, self, 0)
+    }
+}
+
+impl fmt::Display for Condition {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Condition::Equals(path, value) => write!(f, "{} = {}", path, value),
+            Condition::NotEquals(path, value) => write!(f, "{} != {}", path, value),
+            Condition::Exists(path) => write!(f, "{}", path),
+            Condition::NotExists(path) => write!(f, "not {}", path),
+        }
+    }
+}
+
+fn write_value(f: &mut fmt::Formatter<'_>, value: &Value, indent: usize) -> fmt::Result {
+    match value {
+        Value::String(s) => write_quoted(f, s),
+        Value::Int(n) => write!(f, "{}", n),
+        // Keep a decimal point so the literal parses back as a float.
+        Value::Float(n) if n.is_finite() && n.fract() == 0.0 && n.abs() < 1e16 => {
+            write!(f, "{:.1}", n)
+        }
+        Value::Float(n) => write!(f, "{}", n),
+        Value::Bool(b) => write!(f, "{}", b),
+        Value::Regex(r) => write!(f, "r\"{}\"", r.as_str()),
+        Value::Bytes(bytes) => write!(f, "b64\"{}\"", BASE64.encode(bytes)),
+        #[cfg(feature = "chrono")]
+        Value::DateTime(timestamp) => write!(f, "t\"{}\"", timestamp.to_rfc3339()),
+        Value::Array(values) => write_array(f, values, indent, f.alternate()),
+        Value::Object(items) => write_object_items(f, items, indent),
+        Value::Reference(path) => write_path(f, path),
+        Value::Namespaced(path) => {
+            f.write_str("$")?;
+            write_path(f, path)
+        }
+        Value::Interpolated(parts) => {
+            f.write_str("\"")?;
+            for part in parts {
+                match part {
+                    Value::String(s) => write_escaped(f, s)?,
+                    Value::Namespaced(path) => write!(f, "${}", path.join("."))?,
+                    other => write_escaped(f, &other.to_string())?,
+                }
+            }
+            f.write_str("\"")
+        }
+        Value::Conditional(cond) => {
+            write!(f, "if {} ", cond.condition)?;
+            write_value(f, &cond.then_value, indent)?;
+            if let Some(else_value) = &cond.else_value {
+                f.write_str(" else ")?;
+                write_value(f, else_value, indent)?;
+            }
+            Ok(())
+        }
+        Value::Null => f.write_str("null"),
+    }
+}
+
+/// Write an array on one line, or one element per line when `multiline` is set.
+fn write_array(
+    f: &mut fmt::Formatter<'_>,
+    values: &[Value],
+    indent: usize,
+    multiline: bool,
+) -> fmt::Result {
+    if multiline && !values.is_empty() {
+        let pad = "  ".repeat(indent);
+        f.write_str("[\n")?;
+        for v in values {
+            write!(f, "{}  ", pad)?;
+            write_element(f, v, indent + 1)?;
+            f.write_str("\n")?;
+        }
+        return write!(f, "{}]", pad);
+    }
+
+    f.write_str("[")?;
+    for (index, v) in values.iter().enumerate() {
+        if index > 0 {
+            f.write_str(", ")?;
+        }
+        write_element(f, v, indent)?;
+    }
+    f.write_str("]")
+}
+
+/// Write an array element, where a block cannot go: objects are written inline as
+/// `{ key value }`, keeping everything in them on one line.
+fn write_element(f: &mut fmt::Formatter<'_>, value: &Value, indent: usize) -> fmt::Result {
+    let Value::Object(items) = value else {
+        return write_value(f, value, indent);
+    };
+    f.write_str("{")?;
+    for item in items {
+        // Inline objects have no `if` syntax.
+        if let ObjectItem::Assign(key, v) = item {
+            f.write_str(" ")?;
+            write_key(f, key)?;
+            f.write_str(" ")?;
+            match v {
+                Value::Array(values) => write_array(f, values, indent, false)?,
+                other => write_element(f, other, indent)?,
+            }
+        }
+    }
+    f.write_str(if items.is_empty() { "}" } else { " }" })
+}
+
+/// Write object items one per line at `indent` levels (two spaces each).
+pub(crate) fn write_object_items(
+    f: &mut fmt::Formatter<'_>,
+    items: &[ObjectItem],
+    indent: usize,
+) -> fmt::Result {
+    let pad = "  ".repeat(indent);
+    for (index, item) in items.iter().enumerate() {
+        if index > 0 {
+            f.write_str("\n")?;
+        }
+        match item {
+            ObjectItem::Assign(key, Value::Object(children)) => {
+                f.write_str(&pad)?;
+                write_key(f, key)?;
+                f.write_str(":\n")?;
+                if !children.is_empty() {
+                    write_object_items(f, children, indent + 1)?;
+                    f.write_str("\n")?;
+                }
+                write!(f, "{}end", pad)?;
+            }
+            ObjectItem::Assign(key, value) => {
+                f.write_str(&pad)?;
+                write_key(f, key)?;
+                f.write_str(" ")?;
+                write_value(f, value, indent)?;
+            }
+            ObjectItem::IfBlock(block) => {
+                writeln!(f, "{}if {}:", pad, block.condition)?;
+                if !block.then_items.is_empty() {
+                    write_object_items(f, &block.then_items, indent + 1)?;
+                    f.write_str("\n")?;
+                }
+                if let Some(else_items) = &block.else_items {
+                    writeln!(f, "{}else:", pad)?;
+                    if !else_items.is_empty() {
+                        write_object_items(f, else_items, indent + 1)?;
+                        f.write_str("\n")?;
+                    }
+                }
+                write!(f, "{}endif", pad)?;
+            }
+        }
+    }
+    Ok(())
+}
+
+/// Write a reference path as dotted keys.
+fn write_path(f: &mut fmt::Formatter<'_>, path: &[String]) -> fmt::Result {
+    for (index, segment) in path.iter().enumerate() {
+        if index > 0 {
+            f.write_str(".")?;
+        }
+        write_key(f, segment)?;
+    }
+    Ok(())
+}
+
+/// Write a key bare when the lexer would read it back as an identifier, quoted otherwise.
+/// `const` is quoted too, since a bare top-level `const:` opens the constants block.
+pub(crate) fn write_key(f: &mut fmt::Formatter<'_>, key: &str) -> fmt::Result {
+    let is_keyword = matches!(
+        key,
+        "const"
+            | "true"
+            | "false"
+            | "end"
+            | "endif"
+            | "gather"
+            | "as"
+            | "if"
+            | "else"
+            | "elseif"
+            | "else-if"
+            | "null"
+            | "None"
+    );
+    let mut chars = key.chars();
+    let is_ident = chars.next().is_some_and(|c| c.is_alphabetic())
+        && chars.all(|c| c.is_alphanumeric() || c == '_' || c == '-');
+
+    if is_ident && !is_keyword {
+        f.write_str(key)
+    } else {
+        write_quoted(f, key)
+    }
+}
+
+/// Write a double-quoted string, escaping what the lexer unescapes. Every `$` is
+/// written `\$`, since a plain string never interpolates.
+pub(crate) fn write_quoted(f: &mut fmt::Formatter<'_>, s: &str) -> fmt::Result {
+    f.write_str("\"")?;
+    write_escaped(f, s)?;
+    f.write_str("\"")
+}
+
+fn write_escaped(f: &mut fmt::Formatter<'_>, s: &str) -> fmt::Result {
+    for ch in s.chars() {
+        match ch {
+            '\\' => f.write_str("\\\\")?,
+            '$' => f.write_str("\\$")?,
+            '"' => f.write_str("\\\"")?,
+            '\n' => f.write_str("\\n")?,
+            '\t' => f.write_str("\\t")?,
+            '\r' => f.write_str("\\r")?,
+            other => write!(f, "{}", other)?,
+        }
+    }
+    Ok(())
+}
+
+/// The text of an interpolated string as written, each reference as `$path`.
+pub(crate) fn interpolated_text(parts: &[Value]) -> String {
+    parts
+        .iter()
+        .map(|part| match part {
+            Value::String(s) => s.clone(),
+            Value::Namespaced(path) => format!("${}", path.join(".")),
+            other => other.to_string(),
+        })
+        .collect()
+}
+
+#[derive(Debug, Clone, PartialEq, Default)]
 pub struct Document {
     pub items: Vec<(String, Value)>,
     pub metadata: Vec<(String, Value)>,
     pub globals: Vec<(String, Value)>,
+
+    /// Entries of the top-level `const:` block, referenced as `$const.NAME`.
+    /// They are kept apart from globals so they never collide with keys or import aliases.
+    pub constants: Vec<(String, Value)>,
+
+    /// Original source order of top-level entries, as indices into `globals`/`items`.
+    /// Globals and blocks are stored separately, so this is what lets tooling
+    /// reproduce a `global / block / global` layout.
+    pub order: Vec<(EntryKind, usize)>,
+}
+
+impl Document {
+    /// Iterate top-level globals and items in their original source order.
+    ///
+    /// Entries that were added without an `order` record (e.g. merged in from a
+    /// gathered file) are yielded afterwards, globals first.
+    pub fn entries(&self) -> Vec<(EntryKind, &str, &Value)> {
+        let mut seen_globals = vec![false; self.globals.len()];
+        let mut seen_items = vec![false; self.items.len()];
+        let mut out = Vec::with_capacity(self.globals.len() + self.items.len());
+
+        for (kind, index) in &self.order {
+            let (list, seen) = match kind {
+                EntryKind::Global => (&self.globals, &mut seen_globals),
+                EntryKind::Item => (&self.items, &mut seen_items),
+            };
+            if let Some((k, v)) = list.get(*index)
+                && !seen[*index]
+            {
+                seen[*index] = true;
+                out.push((*kind, k.as_str(), v));
+            }
+        }
+
+        for (index, (k, v)) in self.globals.iter().enumerate() {
+            if !seen_globals[index] {
+                out.push((EntryKind::Global, k.as_str(), v));
+            }
+        }
+        for (index, (k, v)) in self.items.iter().enumerate() {
+            if !seen_items[index] {
+                out.push((EntryKind::Item, k.as_str(), v));
+            }
+        }
+
+        out
+    }
+
+    /// Deep-merge `other` over `self`, with `other` winning on conflicts.
+    ///
+    /// Metadata merges by key. Globals and items are merged as one top-level namespace
+    /// in source order: a key keeps its original position, takes the kind it has in
+    /// `other` (so a block can replace a global and vice versa), and keys new to
+    /// `other` are appended. Arrays are replaced; see `merge_with`.
+    pub fn merge(&self, other: &Document) -> Document {
+        self.merge_with(other, MergeStrategy::Replace)
+    }
+
+    /// Like `merge`, combining arrays at the same key as `strategy` says.
+    pub fn merge_with(&self, other: &Document, strategy: MergeStrategy) -> Document {
+        let mut metadata = self.metadata.clone();
+        merge_named_values(&mut metadata, &other.metadata);
+        let mut constants = self.constants.clone();
+        merge_named_values(&mut constants, &other.constants);
+
+        let mut entries: Vec<(EntryKind, String, Value)> = self
+            .entries()
+            .into_iter()
+            .map(|(kind, key, value)| (kind, key.to_string(), value.clone()))
+            .collect();
+        for (kind, key, value) in other.entries() {
+            match entries.iter_mut().find(|(_, k, _)| k == key) {
+                Some(entry) => {
+                    entry.0 = kind;
+                    entry.2 = entry.2.merge_with(value, strategy);
+                }
+                None => entries.push((kind, key.to_string(), value.clone())),
+            }
+        }
+
+        let mut merged = Document {
+            metadata,
+            constants,
+            ..Document::default()
+        };
+        for (kind, key, value) in entries {
+            let list = match kind {
+                EntryKind::Global => &mut merged.globals,
+                EntryKind::Item => &mut merged.items,
+            };
+            merged.order.push((kind, list.len()));
+            list.push((key, value));
+        }
+        merged
+    }
+
+    /// Convert a JSON document, e.g. to migrate a config with `to_rune_string`.
+    ///
+    /// The keys of the top-level object become top-level entries: objects become
+    /// blocks and everything else globals, as the parser stores them. Keys come in the
+    /// order `serde_json` keeps them, which is sorted unless its `preserve_order`
+    /// feature is enabled. Objects
+    /// map to `Value::Object`, arrays to `Value::Array`, whole numbers to `Value::Int`
+    /// (falling back to `Value::Float` beyond `i64`), other numbers to `Value::Float`
+    /// and `null` to `Value::Null`. Strings are literal: a `$` in one is written `\$`
+    /// as RUNE rather than starting an interpolation.
+    ///
+    /// # Errors
+    /// Returns a `TypeError` if `value` is not an object.
+    ///
+    /// # Examples
+    /// ```
+    /// use rune_cfg::{Document, Value};
+    ///
+    /// let doc = Document::from_json_str(r#"{"name": "demo", "app": {"port": 8080}}"#).unwrap();
+    /// assert_eq!(doc.globals[0], ("name".to_string(), Value::from("demo")));
+    /// assert_eq!(doc.items[0].1.get_path("port"), Some(&Value::Int(8080)));
+    /// ```
+    pub fn from_json(value: &serde_json::Value) -> Result<Document, RuneError> {
+        let serde_json::Value::Object(entries) = value else {
+            return Err(RuneError::TypeError {
+                message: format!("Expected a JSON object at the top level, got {}", value),
+                line: 0,
+                column: 0,
+                hint: Some("Wrap the values in an object so each has a key".into()),
+                code: Some(RuneErrorCode::ExpectedObject.code()),
+            });
+        };
+
+        let mut doc = Document::default();
+        for (key, json) in entries {
+            let value = value_from_json(json);
+            let (kind, list) = if matches!(value, Value::Object(_)) {
+                (EntryKind::Item, &mut doc.items)
+            } else {
+                (EntryKind::Global, &mut doc.globals)
+            };
+            doc.order.push((kind, list.len()));
+            list.push((key.clone(), value));
+        }
+        Ok(doc)
+    }
+
+    /// Like `from_json`, parsing `text` first.
+    ///
+    /// # Errors
+    /// Returns a `SyntaxError` with the position of invalid JSON, or a `TypeError` as
+    /// `from_json` does.
+    pub fn from_json_str(text: &str) -> Result<Document, RuneError> {
+        let value: serde_json::Value =
+            serde_json::from_str(text).map_err(|e| RuneError::SyntaxError {
+                message: format!("Invalid JSON: {}", e),
+                line: e.line(),
+                column: e.column(),
+                span: None,
+                hint: Some("Check the JSON syntax near this position".into()),
+                code: Some(RuneErrorCode::InvalidJson.code()),
+            })?;
+        Document::from_json(&value)
+    }
+
+    /// Every leaf value across globals and items with its dotted path, in source order.
+    ///
+    /// Objects are descended into rather than yielded; arrays count as leaves. Items
+    /// inside `if` blocks are skipped since they depend on resolution. Keys that
+    /// contain a dot are quoted, so each path works with `Value::get_path`.
+    pub fn iter_flat(&self) -> impl Iterator<Item = (String, &Value)> {
+        self.flatten_entries(false).into_iter()
+    }
+
+    /// Like `iter_flat`, but also yields each object before its children.
+    pub fn iter_flat_with_objects(&self) -> impl Iterator<Item = (String, &Value)> {
+        self.flatten_entries(true).into_iter()
+    }
+
+    fn flatten_entries(&self, include_objects: bool) -> Vec<(String, &Value)> {
+        let mut out = Vec::new();
+        for (_, key, value) in self.entries() {
+            flatten_value(
+                crate::utils::join_path("", key),
+                value,
+                include_objects,
+                &mut out,
+            );
+        }
+        out
+    }
+}
+
+fn value_from_json(json: &serde_json::Value) -> Value {
+    match json {
+        serde_json::Value::Null => Value::Null,
+        serde_json::Value::Bool(b) => Value::Bool(*b),
+        serde_json::Value::Number(n) => match (n.as_i64(), n.as_f64()) {
+            (Some(i), _) => Value::Int(i),
+            (None, Some(f)) => Value::Float(f),
+            (None, None) => Value::Null,
+        },
+        // JSON has no interpolation, so each `$` in it is literal.
+        serde_json::Value::String(s) => Value::String(s.clone()),
+        serde_json::Value::Array(values) => {
+            Value::Array(values.iter().map(value_from_json).collect())
+        }
+        serde_json::Value::Object(entries) => Value::Object(
+            entries
+                .iter()
+                .map(|(k, v)| ObjectItem::Assign(k.clone(), value_from_json(v)))
+                .collect(),
+        ),
+    }
+}
+
+fn flatten_value<'a>(
+    path: String,
+    value: &'a Value,
+    include_objects: bool,
+    out: &mut Vec<(String, &'a Value)>,
+) {
+    let Value::Object(items) = value else {
+        out.push((path, value));
+        return;
+    };
+
+    if include_objects {
+        out.push((path.clone(), value));
+    }
+    for item in items {
+        if let ObjectItem::Assign(key, child) = item {
+            let child_path = crate::utils::join_path(&path, key);
+            flatten_value(child_path, child, include_objects, out);
+        }
+    }
+}
+
+/// Renders the whole document as RUNE source: metadata first, then globals and
+/// blocks in source order. The output parses back to an equal document. As for
+/// `Value`, `{:#}` writes arrays one element per line.
+impl fmt::Display for Document {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        for (key, value) in &self.metadata {
+            f.write_str("@")?;
+            write_key(f, key)?;
+            match value {
+                Value::Object(items) => {
+                    f.write_str(":\n")?;
+                    if !items.is_empty() {
+                        write_object_items(f, items, 1)?;
+                        f.write_str("\n")?;
+                    }
+                    f.write_str("end\n")?;
+                }
+                other => {
+                    f.write_str(" ")?;
+                    write_value(f, other, 0)?;
+                    f.write_str("\n")?;
+                }
+            }
+        }
+
+        if !self.constants.is_empty() {
+            let items: Vec<ObjectItem> = self
+                .constants
+                .iter()
+                .map(|(k, v)| ObjectItem::Assign(k.clone(), v.clone()))
+                .collect();
+            f.write_str("const:\n")?;
+            write_object_items(f, &items, 1)?;
+            f.write_str("\nend\n")?;
+        }
+
+        for (_, key, value) in self.entries() {
+            let item = ObjectItem::Assign(key.to_string(), value.clone());
+            write_object_items(f, std::slice::from_ref(&item), 0)?;
+            f.write_str("\n")?;
+        }
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn accessors_match_their_variant() {
+        let string = Value::String("rune".into());
+        let number = Value::Int(8080);
+        let boolean = Value::Bool(true);
+        let array = Value::Array(vec![Value::Int(1)]);
+        let reference = Value::Reference(vec!["app".into(), "name".into()]);
+
+        assert_eq!(string.as_str(), Some("rune"));
+        assert_eq!(number.as_f64(), Some(8080.0));
+        assert_eq!(boolean.as_bool(), Some(true));
+        assert_eq!(array.as_array(), Some(&[Value::Int(1)][..]));
+        assert_eq!(
+            reference.as_reference(),
+            Some(&["app".to_string(), "name".to_string()][..])
+        );
+
+        assert_eq!(number.as_str(), None);
+        assert_eq!(string.as_f64(), None);
+        assert_eq!(Value::Null.as_bool(), None);
+        assert_eq!(boolean.as_array(), None);
+        assert_eq!(string.as_reference(), None);
+    }
+
+    #[test]
+    fn as_string_and_as_number_alias_as_str_and_as_f64() {
+        assert_eq!(Value::String("rune".into()).as_string(), Some("rune"));
+        assert_eq!(Value::Int(2).as_number(), Some(2.0));
+        assert_eq!(Value::Float(0.5).as_number(), Some(0.5));
+
+        assert_eq!(Value::Int(2).as_string(), None);
+        assert_eq!(Value::String("2".into()).as_number(), None);
+    }
+
+    #[test]
+    fn remaining_accessors_match_their_variant() {
+        let object = Value::Object(vec![ObjectItem::Assign("a".into(), Value::Int(1))]);
+        let bytes = Value::Bytes(vec![1, 2]);
+        let regex = Value::Regex(Regex::new("^a$").unwrap());
+
+        assert_eq!(object.as_object().map(Vec::len), Some(1));
+        assert_eq!(bytes.as_bytes(), Some(&[1u8, 2][..]));
+        assert_eq!(regex.as_regex().map(Regex::as_str), Some("^a$"));
+
+        assert!(bytes.as_object().is_none());
+        assert_eq!(object.as_bytes(), None);
+        assert!(object.as_regex().is_none());
+        assert_eq!(regex.as_str(), None);
+        assert!(Value::Null.is_null());
+        assert!(!Value::String("null".into()).is_null());
+    }
+
+    #[test]
+    fn type_name_names_each_variant() {
+        let names = [
+            (Value::String("s".into()), "string"),
+            (Value::Int(1), "int"),
+            (Value::Float(1.5), "float"),
+            (Value::Bool(true), "bool"),
+            (Value::Regex(Regex::new("a").unwrap()), "regex"),
+            (Value::Bytes(vec![]), "bytes"),
+            (Value::Array(vec![]), "array"),
+            (Value::Object(vec![]), "object"),
+            (Value::Reference(vec!["a".into()]), "reference"),
+            (
+                Value::Namespaced(vec!["runtime".into(), "a".into()]),
+                "reference",
+            ),
+            (Value::Interpolated(vec![]), "interpolated"),
+            (Value::Null, "null"),
+        ];
+        for (value, name) in names {
+            assert_eq!(value.type_name(), name);
+        }
+    }
+
+    #[test]
+    fn integer_accessors_reject_fractional_and_out_of_range() {
+        assert_eq!(Value::Int(8080).as_i64(), Some(8080));
+        assert_eq!(Value::Int(-3).as_u64(), None);
+        assert_eq!(Value::Float(8080.0).as_i64(), Some(8080));
+        assert_eq!(Value::Float(8080.0).as_u64(), Some(8080));
+        assert_eq!(Value::Float(1.5).as_i64(), None);
+        assert_eq!(Value::Float(1.5).as_u64(), None);
+        assert_eq!(Value::Float(-3.0).as_i64(), Some(-3));
+        assert_eq!(Value::Float(-3.0).as_u64(), None);
+        assert_eq!(Value::Float(1e20).as_i64(), None);
+        assert_eq!(Value::String("1".into()).as_i64(), None);
+    }
+
+    #[test]
+    fn display_renders_rune_that_parses_back() {
+        let value = Value::Object(vec![
+            ObjectItem::Assign("name".into(), Value::String("say \"hi\"\n".into())),
+            ObjectItem::Assign("port".into(), Value::Int(8080)),
+            ObjectItem::Assign("ratio".into(), Value::Float(0.5)),
+            ObjectItem::Assign(
+                "hosts".into(),
+                Value::Array(vec![Value::String("a".into()), Value::String("b".into())]),
+            ),
+            ObjectItem::Assign(
+                "server".into(),
+                Value::Object(vec![
+                    ObjectItem::Assign("debug".into(), Value::Bool(false)),
+                    ObjectItem::Assign("owner".into(), Value::Reference(vec!["name".into()])),
+                    ObjectItem::Assign("nothing".into(), Value::Null),
+                ]),
+            ),
+            ObjectItem::Assign("weird.key".into(), Value::Bool(true)),
+        ]);
+
+        let rendered = value.to_string();
+        assert!(rendered.contains("port 8080\n"));
+        assert!(rendered.contains("server:\n  debug false\n"));
+
+        let source = format!("root:\n{}\nend\n", rendered);
+        let mut parser = crate::parser::Parser::new(&source).expect("lexer");
+        let doc = parser.parse_document().expect("rendered RUNE should parse");
+        assert_eq!(doc.items[0].1, value);
+    }
+
+    #[test]
+    fn get_path_walks_objects_and_arrays() {
+        let value = Value::Object(vec![ObjectItem::Assign(
+            "app".into(),
+            Value::Object(vec![ObjectItem::Assign(
+                "servers".into(),
+                Value::Array(vec![
+                    Value::Object(vec![ObjectItem::Assign(
+                        "host".into(),
+                        Value::String("a.example.com".into()),
+                    )]),
+                    Value::Object(vec![ObjectItem::Assign(
+                        "host".into(),
+                        Value::String("b.example.com".into()),
+                    )]),
+                ]),
+            )]),
+        )]);
+
+        assert_eq!(
+            value.get_path("app.servers.1.host"),
+            Some(&Value::String("b.example.com".into()))
+        );
+        assert_eq!(
+            value
+                .get_path("app.servers")
+                .and_then(Value::as_array)
+                .map(<[Value]>::len),
+            Some(2)
+        );
+        assert_eq!(value.get_path("app.servers.2.host"), None);
+        assert_eq!(value.get_path("app.missing"), None);
+        assert_eq!(value.get_path("app.servers.x"), None);
+        assert_eq!(value.get_path(""), Some(&value));
+    }
+
+    #[test]
+    fn index_chains_and_misses_return_null() {
+        let value = Value::Object(vec![ObjectItem::Assign(
+            "server".into(),
+            Value::Object(vec![
+                ObjectItem::Assign("port".into(), Value::Int(8080)),
+                ObjectItem::Assign(
+                    "hosts".into(),
+                    Value::Array(vec![Value::String("a".into())]),
+                ),
+            ]),
+        )]);
+
+        assert_eq!(value["server"]["port"], Value::Int(8080));
+        assert_eq!(value["server"]["hosts"][0], Value::String("a".into()));
+        assert_eq!(value["server"]["hosts"][5], Value::Null);
+        assert_eq!(value["missing"]["deeper"], Value::Null);
+        assert_eq!(value["server"]["port"][0], Value::Null);
+    }
+
+    #[test]
+    fn get_and_get_index_tell_missing_from_null() {
+        let value = Value::Object(vec![ObjectItem::Assign(
+            "server".into(),
+            Value::Object(vec![
+                ObjectItem::Assign(
+                    "hosts".into(),
+                    Value::Array(vec![Value::String("a".into()), Value::Null]),
+                ),
+                ObjectItem::Assign("proxy".into(), Value::Null),
+            ]),
+        )]);
+
+        let hosts = value.get("server").and_then(|s| s.get("hosts")).unwrap();
+        assert_eq!(hosts.get_index(0), Some(&Value::String("a".into())));
+        assert_eq!(hosts.get_index(1), Some(&Value::Null));
+        assert_eq!(hosts.get_index(2), None);
+        assert_eq!(value["server"]["hosts"][2], Value::Null);
+
+        assert_eq!(value["server"].get("proxy"), Some(&Value::Null));
+        assert_eq!(value["server"].get("missing"), None);
+        // Each only looks into its own kind of container.
+        assert_eq!(hosts.get("0"), None);
+        assert_eq!(value.get_index(0), None);
+    }
+
+    #[test]
+    fn exactly_one_predicate_holds_per_variant() {
+        let values = [
+            Value::String("s".into()),
+            Value::Int(1),
+            Value::Bool(true),
+            Value::Array(vec![]),
+            Value::Object(vec![]),
+            Value::Reference(vec!["a".into()]),
+            Value::Null,
+        ];
+
+        for value in &values {
+            let holds = [
+                value.is_string(),
+                value.is_number(),
+                value.is_bool(),
+                value.is_array(),
+                value.is_object(),
+                value.is_reference(),
+                value.is_null(),
+            ];
+            assert_eq!(
+                holds.iter().filter(|h| **h).count(),
+                1,
+                "{:?} should match exactly one predicate",
+                value
+            );
+        }
+    }
+
+    #[test]
+    fn merge_deep_merges_objects_and_overrides_scalars() {
+        let base = Value::Object(vec![
+            ObjectItem::Assign(
+                "server".into(),
+                Value::Object(vec![
+                    ObjectItem::Assign("host".into(), Value::String("localhost".into())),
+                    ObjectItem::Assign("port".into(), Value::Int(80)),
+                ]),
+            ),
+            ObjectItem::Assign("debug".into(), Value::Bool(false)),
+        ]);
+        let overrides = Value::Object(vec![
+            ObjectItem::Assign(
+                "server".into(),
+                Value::Object(vec![ObjectItem::Assign("port".into(), Value::Int(8080))]),
+            ),
+            ObjectItem::Assign("debug".into(), Value::Bool(true)),
+            ObjectItem::Assign("name".into(), Value::String("app".into())),
+        ]);
+
+        let merged = base.merge(&overrides);
+        assert_eq!(merged["server"]["host"], Value::String("localhost".into()));
+        assert_eq!(merged["server"]["port"], Value::Int(8080));
+        assert_eq!(merged["debug"], Value::Bool(true));
+        assert_eq!(merged["name"], Value::String("app".into()));
+    }
+
+    #[test]
+    fn merge_array_strategies() {
+        let base = Value::Object(vec![ObjectItem::Assign(
+            "tags".into(),
+            Value::Array(vec![Value::String("a".into())]),
+        )]);
+        let overrides = Value::Object(vec![ObjectItem::Assign(
+            "tags".into(),
+            Value::Array(vec![Value::String("b".into())]),
+        )]);
+
+        assert_eq!(
+            base.merge(&overrides)["tags"],
+            Value::Array(vec![Value::String("b".into())])
+        );
+        assert_eq!(
+            base.merge_with(&overrides, MergeStrategy::Concat)["tags"],
+            Value::Array(vec![Value::String("a".into()), Value::String("b".into())])
+        );
+    }
+
+    #[test]
+    fn from_builds_the_same_value_as_explicit_variants() {
+        let built = Value::Object(vec![
+            ObjectItem::Assign("name".into(), Value::from("app")),
+            ObjectItem::Assign("port".into(), Value::from(8080)),
+            ObjectItem::Assign("debug".into(), Value::from(true)),
+            ObjectItem::Assign("hosts".into(), Value::from(vec!["a", "b"])),
+            ObjectItem::Assign("owner".into(), Value::from(None::<String>)),
+        ]);
+
+        let explicit = Value::Object(vec![
+            ObjectItem::Assign("name".into(), Value::String("app".into())),
+            ObjectItem::Assign("port".into(), Value::Int(8080)),
+            ObjectItem::Assign("debug".into(), Value::Bool(true)),
+            ObjectItem::Assign(
+                "hosts".into(),
+                Value::Array(vec![Value::String("a".into()), Value::String("b".into())]),
+            ),
+            ObjectItem::Assign("owner".into(), Value::Null),
+        ]);
+
+        assert_eq!(built, explicit);
+        assert_eq!(
+            Value::from(vec![vec![1u8], vec![2u8]]),
+            Value::Array(vec![
+                Value::Array(vec![Value::Int(1)]),
+                Value::Array(vec![Value::Int(2)]),
+            ])
+        );
+    }
+
+    #[test]
+    fn collecting_pairs_builds_nested_objects() {
+        let tls: Value = [
+            ("enabled", Value::from(true)),
+            ("cert", "/etc/app.pem".into()),
+        ]
+        .into_iter()
+        .collect();
+        let server: Value = [
+            ("host", Value::from("localhost")),
+            ("port", 8080.into()),
+            ("tags", vec!["a", "b"].into()),
+            ("tls", tls),
+        ]
+        .into_iter()
+        .collect();
+
+        assert_eq!(server.get_path("port"), Some(&Value::Int(8080)));
+        assert_eq!(server.get_path("tags.1"), Some(&Value::from("b")));
+        assert_eq!(server.get_path("tls.enabled"), Some(&Value::Bool(true)));
+        assert_eq!(
+            server,
+            Value::Object(vec![
+                ObjectItem::Assign("host".into(), Value::String("localhost".into())),
+                ObjectItem::Assign("port".into(), Value::Int(8080)),
+                ObjectItem::Assign(
+                    "tags".into(),
+                    Value::Array(vec![Value::String("a".into()), Value::String("b".into())]),
+                ),
+                ObjectItem::Assign(
+                    "tls".into(),
+                    Value::Object(vec![
+                        ObjectItem::Assign("enabled".into(), Value::Bool(true)),
+                        ObjectItem::Assign("cert".into(), Value::String("/etc/app.pem".into())),
+                    ]),
+                ),
+            ])
+        );
+
+        let ports: Value = vec![("http", 80u16), ("https", 443)].into_iter().collect();
+        assert_eq!(ports["https"], Value::Int(443));
+    }
+
+    #[test]
+    fn int_and_float_round_trip_through_display() {
+        let value = Value::Object(vec![
+            ObjectItem::Assign("port".into(), Value::Int(8080)),
+            ObjectItem::Assign("timeout".into(), Value::Float(30.0)),
+        ]);
+
+        let source = format!("root:\n{}\nend\n", value);
+        assert!(source.contains("timeout 30.0"));
+
+        let mut parser = crate::parser::Parser::new(&source).expect("lexer");
+        let doc = parser.parse_document().expect("rendered RUNE should parse");
+        assert!(matches!(doc.items[0].1["port"], Value::Int(8080)));
+        assert!(matches!(doc.items[0].1["timeout"], Value::Float(n) if n == 30.0));
+
+        // Equality is numeric across the two variants.
+        assert_eq!(Value::Int(1), Value::Float(1.0));
+    }
+
+    #[test]
+    fn iter_flat_yields_every_leaf_path() {
+        let source = r#"
+name "RuneApp"
+app:
+  port 8080
+  server:
+    host "localhost"
+    "east.1" true
+  end
+  tags ["a", "b"]
+end
+"#;
+        let mut parser = crate::parser::Parser::new(source).expect("lexer");
+        let doc = parser.parse_document().expect("parse");
+
+        let flat: Vec<(String, &Value)> = doc.iter_flat().collect();
+        assert_eq!(
+            flat,
+            vec![
+                ("name".to_string(), &Value::String("RuneApp".into())),
+                ("app.port".to_string(), &Value::Int(8080)),
+                (
+                    "app.server.host".to_string(),
+                    &Value::String("localhost".into())
+                ),
+                ("app.server.\"east.1\"".to_string(), &Value::Bool(true)),
+                ("app.tags".to_string(), &Value::from(vec!["a", "b"])),
+            ]
+        );
+        for (path, value) in &flat {
+            let (head, rest) = path.split_once('.').unwrap_or((path, ""));
+            let root = doc
+                .entries()
+                .into_iter()
+                .find(|(_, key, _)| *key == head)
+                .map(|(_, _, v)| v)
+                .unwrap();
+            let found = if rest.is_empty() {
+                Some(root)
+            } else {
+                root.get_path(rest)
+            };
+            assert_eq!(found, Some(*value), "{} should resolve", path);
+        }
+
+        let with_objects: Vec<String> = doc.iter_flat_with_objects().map(|(p, _)| p).collect();
+        assert_eq!(
+            with_objects,
+            vec![
+                "name",
+                "app",
+                "app.port",
+                "app.server",
+                "app.server.host",
+                "app.server.\"east.1\"",
+                "app.tags",
+            ]
+        );
+    }
+
+    #[test]
+    fn eq_unordered_ignores_key_order_only() {
+        let a = Value::Object(vec![
+            ObjectItem::Assign("name".into(), Value::from("app")),
+            ObjectItem::Assign(
+                "server".into(),
+                Value::Object(vec![
+                    ObjectItem::Assign("host".into(), Value::from("localhost")),
+                    ObjectItem::Assign("port".into(), Value::from(8080)),
+                ]),
+            ),
+        ]);
+        let reordered = Value::Object(vec![
+            ObjectItem::Assign(
+                "server".into(),
+                Value::Object(vec![
+                    ObjectItem::Assign("port".into(), Value::from(8080)),
+                    ObjectItem::Assign("host".into(), Value::from("localhost")),
+                ]),
+            ),
+            ObjectItem::Assign("name".into(), Value::from("app")),
+        ]);
+        assert_ne!(a, reordered);
+        assert!(a.eq_unordered(&reordered));
+
+        let different_value = Value::Object(vec![
+            ObjectItem::Assign("name".into(), Value::from("app")),
+            ObjectItem::Assign(
+                "server".into(),
+                Value::Object(vec![
+                    ObjectItem::Assign("host".into(), Value::from("localhost")),
+                    ObjectItem::Assign("port".into(), Value::from(9090)),
+                ]),
+            ),
+        ]);
+        assert!(!a.eq_unordered(&different_value));
+
+        let missing_key =
+            Value::Object(vec![ObjectItem::Assign("name".into(), Value::from("app"))]);
+        assert!(!a.eq_unordered(&missing_key));
+        assert!(!missing_key.eq_unordered(&a));
+
+        // Arrays keep their order.
+        assert!(!Value::from(vec![1, 2]).eq_unordered(&Value::from(vec![2, 1])));
+    }
+
+    #[test]
+    fn document_merge_overrides_and_preserves_keys() {
+        let parse = |source: &str| {
+            let mut parser = crate::parser::Parser::new(source).expect("lexer");
+            parser.parse_document().expect("parse")
+        };
+        let base = parse(
+            r#"
+@version "1"
+@author "base"
+name "RuneApp"
+app:
+  port 8080
+  host "localhost"
+end
+debug false
+"#,
+        );
+        let overrides = parse(
+            r#"
+@version "2"
+debug true
+app:
+  port 9090
+end
+region "eu"
+"#,
+        );
+
+        let merged = base.merge(&overrides);
+
+        assert_eq!(
+            merged.metadata,
+            vec![
+                ("version".to_string(), Value::from("2")),
+                ("author".to_string(), Value::from("base")),
+            ]
+        );
+        let keys: Vec<(EntryKind, &str)> = merged
+            .entries()
+            .into_iter()
+            .map(|(kind, key, _)| (kind, key))
+            .collect();
+        assert_eq!(
+            keys,
+            vec![
+                (EntryKind::Global, "name"),
+                (EntryKind::Item, "app"),
+                (EntryKind::Global, "debug"),
+                (EntryKind::Global, "region"),
+            ]
+        );
+        assert_eq!(merged.items[0].1["port"], Value::Int(9090));
+        assert_eq!(merged.items[0].1["host"], Value::from("localhost"));
+        assert_eq!(merged.globals[1].1, Value::Bool(true));
+    }
+
+    #[test]
+    fn from_json_maps_objects_to_blocks_and_scalars_to_globals() {
+        let doc = Document::from_json_str(
+            r#"{
+                "name": "demo",
+                "ratio": 0.5,
+                "app": {
+                    "port": 8080,
+                    "tags": ["a", "b"],
+                    "tls": { "enabled": true, "cert": null }
+                }
+            }"#,
+        )
+        .unwrap();
+
+        assert_eq!(
+            doc.globals,
+            vec![
+                ("name".to_string(), Value::String("demo".into())),
+                ("ratio".to_string(), Value::Float(0.5)),
+            ]
+        );
+        assert_eq!(doc.items.len(), 1);
+        let app = &doc.items[0].1;
+        assert_eq!(app.get_path("port"), Some(&Value::Int(8080)));
+        assert_eq!(
+            app.get_path("tags"),
+            Some(&Value::Array(vec!["a".into(), "b".into()]))
+        );
+        assert_eq!(app.get_path("tls.enabled"), Some(&Value::Bool(true)));
+        assert_eq!(app.get_path("tls.cert"), Some(&Value::Null));
+
+        // The result writes out as RUNE that parses back to the same document.
+        let source = format!("{:#}", doc);
+        let mut parser = crate::parser::Parser::new(&source).unwrap();
+        assert_eq!(parser.parse_document().unwrap(), doc);
+
+        assert!(matches!(
+            Document::from_json_str("[1, 2]"),
+            Err(RuneError::TypeError {
+                code: Some(410),
+                ..
+            })
+        ));
+        assert!(matches!(
+            Document::from_json_str("{\n  \"a\": }"),
+            Err(RuneError::SyntaxError { line: 2, .. })
+        ));
+    }
+
+    #[test]
+    fn from_json_keeps_dollar_signs_literal() {
+        let doc = Document::from_json_str(r#"{"v": "literal $env.HOME here"}"#).unwrap();
+        assert_eq!(doc.globals[0].1.as_str(), Some("literal $env.HOME here"));
+
+        let source = format!("{}", doc);
+        assert_eq!(source, "v \"literal \\$env.HOME here\"\n");
+        let config = crate::RuneConfig::from_str(&source).unwrap();
+        assert_eq!(config.get::<String>("v").unwrap(), "literal $env.HOME here");
+        assert_eq!(
+            crate::export::export_document_to_json(&doc).unwrap(),
+            crate::export::export_document_to_json(config.document().unwrap()).unwrap()
+        );
+    }
 }
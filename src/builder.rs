@@ -0,0 +1,127 @@
+// Author: Dustin Pilgrim
+// License: MIT
+
+//! Fluent construction of [`Document`]s from Rust code.
+//!
+//! ```
+//! use rune_cfg::DocumentBuilder;
+//!
+//! let doc = DocumentBuilder::new()
+//!     .global("name", "RuneApp")
+//!     .block("app", |b| b.set("port", 8080).block("tls", |t| t.set("enabled", true)))
+//!     .build();
+//!
+//! assert!(doc.to_string().contains("port 8080"));
+//! ```
+
+use crate::ast::{Document, EntryKind, ObjectItem, Value};
+
+/// Builds a [`Document`], recording entries in the order they are added.
+#[derive(Debug, Clone, Default)]
+pub struct DocumentBuilder {
+    doc: Document,
+}
+
+impl DocumentBuilder {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Add an `@key value` metadata entry.
+    pub fn metadata(mut self, key: impl Into<String>, value: impl Into<Value>) -> Self {
+        self.doc.metadata.push((key.into(), value.into()));
+        self
+    }
+
+    /// Add a top-level `key value` global.
+    pub fn global(mut self, key: impl Into<String>, value: impl Into<Value>) -> Self {
+        self.doc
+            .order
+            .push((EntryKind::Global, self.doc.globals.len()));
+        self.doc.globals.push((key.into(), value.into()));
+        self
+    }
+
+    /// Add a top-level `key:` ... `end` block whose body is filled in by `build`.
+    pub fn block(
+        mut self,
+        key: impl Into<String>,
+        build: impl FnOnce(ObjectBuilder) -> ObjectBuilder,
+    ) -> Self {
+        let body = build(ObjectBuilder::new()).build();
+        self.doc.order.push((EntryKind::Item, self.doc.items.len()));
+        self.doc.items.push((key.into(), body));
+        self
+    }
+
+    pub fn build(self) -> Document {
+        self.doc
+    }
+}
+
+/// Builds the body of an object block.
+#[derive(Debug, Clone, Default)]
+pub struct ObjectBuilder {
+    items: Vec<ObjectItem>,
+}
+
+impl ObjectBuilder {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Set `key` to `value`.
+    pub fn set(mut self, key: impl Into<String>, value: impl Into<Value>) -> Self {
+        self.items
+            .push(ObjectItem::Assign(key.into(), value.into()));
+        self
+    }
+
+    /// Add a nested `key:` ... `end` block.
+    pub fn block(
+        self,
+        key: impl Into<String>,
+        build: impl FnOnce(ObjectBuilder) -> ObjectBuilder,
+    ) -> Self {
+        let body = build(ObjectBuilder::new()).build();
+        self.set(key, body)
+    }
+
+    /// The finished `Value::Object`.
+    pub fn build(self) -> Value {
+        Value::Object(self.items)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::parser::Parser;
+
+    #[test]
+    fn built_document_serializes_and_reparses_equal() {
+        let doc = DocumentBuilder::new()
+            .metadata("description", "generated")
+            .global("name", "RuneApp")
+            .block("app", |b| {
+                b.set("port", 8080)
+                    .set("hosts", vec!["a", "b"])
+                    .block("tls", |t| t.set("enabled", true))
+            })
+            .global("debug", false)
+            .build();
+
+        let source = doc.to_string();
+        assert_eq!(
+            source,
+            "@description \"generated\"\n\
+             name \"RuneApp\"\n\
+             app:\n  port 8080\n  hosts [\"a\", \"b\"]\n  tls:\n    enabled true\n  end\nend\n\
+             debug false\n"
+        );
+
+        let mut parser = Parser::new(&source).expect("lexer");
+        let reparsed = parser.parse_document().expect("built RUNE should parse");
+        assert_eq!(reparsed, doc);
+    }
+}
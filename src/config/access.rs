@@ -46,9 +46,7 @@ impl RuneConfig {
     {
         match self.get_value_flexible(path) {
             Ok(value) => Ok(Some(T::try_from(value)?)),
-            Err(RuneError::SyntaxError {
-                code: Some(304), ..
-            }) => Ok(None),
+            Err(e) if e.error_code() == Some(RuneErrorCode::PathNotFound) => Ok(None),
             Err(e) => Err(e),
         }
     }
@@ -69,14 +67,50 @@ impl RuneConfig {
         self.get(path).unwrap_or(default)
     }
 
+    /// Deserialize the resolved value at `path` into `T`; an empty path is the whole
+    /// config, whose globals and top-level blocks become the fields of `T`.
+    ///
+    /// References, conditionals and `$` values are resolved first, so `T` only sees
+    /// plain values. Paths are matched like `get`, and type errors carry the line of
+    /// the key.
+    ///
+    /// # Examples
+    /// ```
+    /// # use rune_cfg::RuneConfig;
+    /// #[derive(serde::Deserialize)]
+    /// struct Server {
+    ///     host: String,
+    ///     port: u16,
+    /// }
+    ///
+    /// #[derive(serde::Deserialize)]
+    /// struct Config {
+    ///     server: Server,
+    /// }
+    ///
+    /// let config = RuneConfig::from_str("server:\n  host \"localhost\"\n  port 8080\nend\n").unwrap();
+    /// let parsed: Config = config.deserialize("").unwrap();
+    /// assert_eq!(parsed.server.port, 8080);
+    ///
+    /// let server: Server = config.deserialize("server").unwrap();
+    /// assert_eq!(server.host, "localhost");
+    /// ```
+    #[cfg(feature = "serde")]
+    pub fn deserialize<T: serde::de::DeserializeOwned>(&self, path: &str) -> Result<T, RuneError> {
+        let value = self.get_value_flexible(path)?;
+        crate::de::from_value(value)
+            .map_err(|e| enhance_error_with_line_info(e, path, &self.raw_content))
+    }
+
     /// Internal method that tries both snake_case and kebab-case variants.
     ///
     /// Allows flexible key access: `monitor_media` and `monitor-media` both work.
     fn get_value_flexible(&self, path: &str) -> Result<Value, RuneError> {
         // Fast path: exact
-        if let Ok(v) = self.get_value(path) {
-            return Ok(v);
-        }
+        let exact_err = match self.get_value(path) {
+            Ok(v) => return Ok(v),
+            Err(e) => e,
+        };
 
         // Root path special case handled by get_value("") already
         if path.trim().is_empty() {
@@ -84,7 +118,7 @@ impl RuneConfig {
         }
 
         // Try segment-by-segment variants: for each segment, try {original, snake, kebab}
-        let segs: Vec<&str> = path.split('.').collect();
+        let segs = crate::utils::split_path(path);
 
         fn variants(seg: &str) -> Vec<String> {
             let mut out = Vec::new();
@@ -109,33 +143,42 @@ impl RuneConfig {
         // DFS over combinations, stop on first that resolves
         fn dfs(
             cfg: &RuneConfig,
-            segs: &[&str],
+            path: &str,
+            segs: &[String],
             i: usize,
             cur: &mut Vec<String>,
         ) -> Result<Value, RuneError> {
             if i == segs.len() {
-                let candidate = cur.join(".");
-                return cfg.get_value(&candidate);
+                return cfg.get_value_at(path, cur);
             }
 
-            for v in variants(segs[i]) {
+            for v in variants(&segs[i]) {
                 cur.push(v);
-                if let Ok(val) = dfs(cfg, segs, i + 1, cur) {
-                    return Ok(val);
+                // Only a missing path means "try the next spelling"; resolution
+                // errors (unset $env, reference cycles, ...) are reported as-is.
+                match dfs(cfg, path, segs, i + 1, cur) {
+                    Ok(val) => return Ok(val),
+                    Err(e) if e.error_code() == Some(RuneErrorCode::PathNotFound) => {}
+                    Err(e) => return Err(e),
                 }
                 cur.pop();
             }
 
             Err(RuneError::SyntaxError {
-                message: format!("Path '{}' not found in configuration", segs.join(".")),
+                message: format!("Path '{}' not found in configuration", path),
                 line: 0,
                 column: 0,
+                span: None,
                 hint: Some("Check that the path exists in your config file".into()),
-                code: Some(304),
+                code: Some(RuneErrorCode::PathNotFound.code()),
             })
         }
 
-        dfs(self, &segs, 0, &mut Vec::new())
+        // No spelling matched: report the exact path's error, which says why it missed.
+        match dfs(self, path, &segs, 0, &mut Vec::new()) {
+            Err(e) if e.error_code() == Some(RuneErrorCode::PathNotFound) => Err(exact_err),
+            other => other,
+        }
     }
 
     /// Get a raw `Value` from the configuration.
@@ -155,6 +198,101 @@ impl RuneConfig {
     /// # }
     /// ```
     pub fn get_value(&self, path: &str) -> Result<Value, RuneError> {
+        if path.trim().is_empty() {
+            return self.get_value_at(path, &[]);
+        }
+        self.get_value_at(path, &crate::utils::split_path(path))
+    }
+
+    /// Resolve the value at already-split path segments; `path` is only used for errors.
+    fn get_value_at(&self, path: &str, segs: &[String]) -> Result<Value, RuneError> {
+        let lazy_root;
+        let resolved_root = match self.resolution_mode {
+            ResolutionMode::Eager => self
+                .resolved
+                .get_or_try_init(|| self.resolve_entries(None, None))?,
+            ResolutionMode::Lazy => {
+                lazy_root = self.resolve_entries(segs.first().map(String::as_str), None)?;
+                &lazy_root
+            }
+        };
+
+        self.lookup_resolved(resolved_root, path, segs)
+    }
+
+    /// Find `segs` in a resolved root; `path` is only used for errors.
+    pub(super) fn lookup_resolved(
+        &self,
+        resolved_root: &Value,
+        path: &str,
+        segs: &[String],
+    ) -> Result<Value, RuneError> {
+        // Root lookup: return fully resolved root
+        if segs.is_empty() {
+            return Ok(resolved_root.clone());
+        }
+
+        // Now traverse the resolved Value tree to find the requested path. Numeric
+        // segments index arrays, so `servers.0.host` works.
+        let mut cur = resolved_root;
+        for (i, seg) in segs.iter().enumerate() {
+            if let (Value::Array(values), Ok(index)) = (cur, seg.parse::<usize>())
+                && index >= values.len()
+            {
+                return Err(RuneError::SyntaxError {
+                    message: format!(
+                        "Index {} out of range for '{}' in path '{}'",
+                        index,
+                        segs[..i].join("."),
+                        path
+                    ),
+                    line: 0,
+                    column: 0,
+                    span: None,
+                    hint: Some(format!("The array has {} element(s)", values.len())),
+                    code: Some(RuneErrorCode::PathNotFound.code()),
+                });
+            }
+
+            cur = cur.child(seg).ok_or_else(|| {
+                let (line, snippet) = helpers::find_config_line(path, &self.raw_content);
+                if line > 0 {
+                    RuneError::SyntaxError {
+                        message: format!(
+                            "Path '{}' not found in configuration (near line {})",
+                            path, line
+                        ),
+                        line,
+                        column: 0,
+                        span: None,
+                        hint: Some(format!("Check around: {}", snippet)),
+                        code: Some(RuneErrorCode::PathNotFound.code()),
+                    }
+                } else {
+                    RuneError::SyntaxError {
+                        message: format!("Path '{}' not found in configuration", path),
+                        line: 0,
+                        column: 0,
+                        span: None,
+                        hint: Some("Check that the path exists in your config file".into()),
+                        code: Some(RuneErrorCode::PathNotFound.code()),
+                    }
+                }
+            })?;
+        }
+        Ok(cur.clone())
+    }
+
+    /// Resolve the main document into one object of globals and items: all of them, or
+    /// only the top-level entry named `only`. Entries reached through references are
+    /// resolved as needed either way.
+    ///
+    /// `prefetched` holds values awaited from async providers, if any.
+    pub(super) fn resolve_entries(
+        &self,
+        only: Option<&str>,
+        prefetched: Option<&helpers::Prefetched>,
+    ) -> Result<Value, RuneError> {
         use crate::ast::ObjectItem;
 
         let main_doc =
@@ -164,17 +302,21 @@ impl RuneConfig {
                     message: "No main document loaded".into(),
                     line: 0,
                     column: 0,
+                    span: None,
                     hint: None,
-                    code: Some(305),
+                    code: Some(RuneErrorCode::NoMainDocument.code()),
                 })?;
 
         // Build a temporary parser and inject imports (same as before).
-        let mut temp_parser = parser::Parser::new("").map_err(|_| RuneError::SyntaxError {
-            message: "Failed to create temporary parser".into(),
-            line: 0,
-            column: 0,
-            hint: None,
-            code: Some(303),
+        let mut temp_parser = parser::Parser::with_context("", &self.context).map_err(|_| {
+            RuneError::SyntaxError {
+                message: "Failed to create temporary parser".into(),
+                line: 0,
+                column: 0,
+                span: None,
+                hint: None,
+                code: Some(RuneErrorCode::ParserUnavailable.code()),
+            }
         })?;
 
         for (alias, doc) in &self.documents {
@@ -188,66 +330,24 @@ impl RuneConfig {
         // and resolve_reference() cannot see those until we resolve/flatten the object.
         let mut root_items: Vec<ObjectItem> = Vec::new();
 
-        for (k, v) in &main_doc.globals {
-            root_items.push(ObjectItem::Assign(k.clone(), v.clone()));
-        }
-        for (k, v) in &main_doc.items {
-            root_items.push(ObjectItem::Assign(k.clone(), v.clone()));
-        }
-
-        // Resolve + flatten everything (references, $env/$sys, inline if, and block if/endif).
-        let resolved_root =
-            helpers::resolve_value_recursively(&Value::Object(root_items), &temp_parser, main_doc)?;
-
-        // Root lookup: return fully resolved root
-        if path.trim().is_empty() {
-            return Ok(resolved_root);
-        }
-
-        // Now traverse the resolved Value tree to find the requested path.
-        fn lookup_path(v: &Value, segs: &[&str]) -> Option<Value> {
-            use crate::ast::ObjectItem;
-
-            let mut cur = v;
-            for seg in segs {
-                match cur {
-                    Value::Object(items) => {
-                        let next = items.iter().find_map(|it| match it {
-                            ObjectItem::Assign(k, val) if k == seg => Some(val),
-                            _ => None,
-                        })?;
-                        cur = next;
-                    }
-                    _ => return None,
-                }
+        for (k, v) in main_doc.globals.iter().chain(&main_doc.items) {
+            if only.is_none_or(|key| key == k) {
+                root_items.push(ObjectItem::Assign(k.clone(), v.clone()));
             }
-            Some(cur.clone())
         }
 
-        let segs: Vec<&str> = path.split('.').collect();
-        lookup_path(&resolved_root, &segs).ok_or_else(|| {
-            let (line, snippet) = helpers::find_config_line(path, &self.raw_content);
-            if line > 0 {
-                RuneError::SyntaxError {
-                    message: format!(
-                        "Path '{}' not found in configuration (near line {})",
-                        path, line
-                    ),
-                    line,
-                    column: 0,
-                    hint: Some(format!("Check around: {}", snippet)),
-                    code: Some(304),
-                }
-            } else {
-                RuneError::SyntaxError {
-                    message: format!("Path '{}' not found in configuration", path),
-                    line: 0,
-                    column: 0,
-                    hint: Some("Check that the path exists in your config file".into()),
-                    code: Some(304),
-                }
-            }
-        })
+        // Resolve + flatten everything (references, $env/$sys, inline if, and block if/endif).
+        helpers::resolve_value_recursively(
+            &Value::Object(root_items),
+            &temp_parser,
+            main_doc,
+            &helpers::Scope {
+                base_dir: &self.base_dir,
+                context: &self.context,
+                prefetched,
+                resolving: Default::default(),
+            },
+        )
     }
 
     /// Get all keys at a given path level.
@@ -281,11 +381,73 @@ impl RuneConfig {
                 line: 0,
                 column: 0,
                 hint: Some("Only objects have keys".into()),
-                code: Some(306),
+                code: Some(RuneErrorCode::NotAnObject.code()),
             }),
         }
     }
 
+    /// Every leaf value of the resolved config, keyed by its full dotted path, in
+    /// source order.
+    ///
+    /// Array elements get indexed keys (`app.features.0`), so every key can be passed
+    /// back to `get`. Empty blocks and arrays are kept as leaves so they still show up.
+    ///
+    /// # Example
+    /// ```
+    /// # use rune_cfg::{RuneConfig, Value};
+    /// let config = RuneConfig::from_str("app:\n  port 8080\n  tags [\"a\" \"b\"]\nend\n").unwrap();
+    /// let flat = config.flatten().unwrap();
+    ///
+    /// assert_eq!(flat["app.port"], Value::Int(8080));
+    /// assert_eq!(flat["app.tags.1"], Value::String("b".into()));
+    /// ```
+    pub fn flatten(&self) -> Result<IndexMap<String, Value>, RuneError> {
+        let mut out = IndexMap::new();
+        flatten_into(self.get_value("")?, String::new(), &mut out);
+        Ok(out)
+    }
+
+    /// The path of every leaf in the main document, in source order, without
+    /// resolving anything.
+    ///
+    /// Arrays are leaves: their path is listed, not their elements. Keys inside `if`
+    /// blocks are listed once, whichever branch they are in.
+    ///
+    /// # Example
+    /// ```
+    /// # use rune_cfg::RuneConfig;
+    /// let config = RuneConfig::from_str("name \"demo\"\napp:\n  port 8080\n  tags [\"a\"]\nend\n").unwrap();
+    /// assert_eq!(config.all_paths(), ["name", "app.port", "app.tags"]);
+    /// ```
+    pub fn all_paths(&self) -> Vec<String> {
+        let mut out = Vec::new();
+        for (_, key, value) in self.documents[&self.main_doc_key].entries() {
+            collect_paths(value, crate::utils::join_path("", key), &mut out);
+        }
+        out
+    }
+
+    /// Get a metadata value (`@key ...`) from the main document.
+    ///
+    /// Structured metadata written as a block (`@author:` ... `end`) is returned as a
+    /// `Value::Object`.
+    ///
+    /// # Examples
+    /// ```no_run
+    /// # use rune_cfg::RuneConfig;
+    /// # let config = RuneConfig::from_file("config.rune").unwrap();
+    /// if let Some(author) = config.get_metadata("author") {
+    ///     println!("author: {:?}", author);
+    /// }
+    /// ```
+    pub fn get_metadata(&self, key: &str) -> Option<&Value> {
+        self.document()?
+            .metadata
+            .iter()
+            .find(|(k, _)| k == key)
+            .map(|(_, v)| v)
+    }
+
     /// Check if a configuration path exists.
     ///
     /// # Examples
@@ -357,3 +519,53 @@ fn enhance_error_with_line_info(e: RuneError, path: &str, raw_content: &str) ->
         other => other,
     }
 }
+
+/// Push the dotted path of each leaf under `value` onto `out`, from both branches of
+/// `if` blocks. An empty block is a leaf.
+fn collect_paths(value: &Value, path: String, out: &mut Vec<String>) {
+    use crate::ast::ObjectItem;
+
+    fn collect_items(items: &[ObjectItem], path: &str, out: &mut Vec<String>) {
+        for item in items {
+            match item {
+                ObjectItem::Assign(key, v) => {
+                    collect_paths(v, crate::utils::join_path(path, key), out)
+                }
+                ObjectItem::IfBlock(block) => {
+                    collect_items(&block.then_items, path, out);
+                    collect_items(block.else_items.as_deref().unwrap_or_default(), path, out);
+                }
+            }
+        }
+    }
+
+    match value {
+        Value::Object(items) if !items.is_empty() => collect_items(items, &path, out),
+        _ if !out.contains(&path) => out.push(path),
+        _ => {}
+    }
+}
+
+/// Add each leaf under `value` to `out`, keyed by its dotted path from `path`. Array
+/// elements are keyed by index, and an empty block or array is a leaf.
+fn flatten_into(value: Value, path: String, out: &mut IndexMap<String, Value>) {
+    use crate::ast::ObjectItem;
+
+    match value {
+        Value::Object(items) if !items.is_empty() => {
+            for item in items {
+                if let ObjectItem::Assign(key, v) = item {
+                    flatten_into(v, crate::utils::join_path(&path, &key), out);
+                }
+            }
+        }
+        Value::Array(values) if !values.is_empty() => {
+            for (i, v) in values.into_iter().enumerate() {
+                flatten_into(v, crate::utils::join_path(&path, &i.to_string()), out);
+            }
+        }
+        leaf => {
+            out.insert(path, leaf);
+        }
+    }
+}
@@ -0,0 +1,71 @@
+// Author: Dustin Pilgrim
+// License: MIT
+
+use std::cell::RefCell;
+use std::collections::HashMap;
+use std::sync::Arc;
+
+use futures::future::try_join_all;
+
+use super::helpers::Prefetched;
+use super::*;
+
+impl RuneConfig {
+    /// Register a provider whose values are awaited by `get_value_async` and `get_async`.
+    pub fn register_async_provider(
+        &mut self,
+        namespace: impl Into<String>,
+        provider: impl AsyncResolverProvider + 'static,
+    ) {
+        self.async_providers
+            .insert(namespace.into(), Arc::new(provider));
+    }
+
+    /// Like [`get_value`](Self::get_value), but first awaits the references to a
+    /// namespace registered with `register_async_provider` that `path` depends on.
+    ///
+    /// Resolving `path` finds which references it reaches, including through other
+    /// keys and imports; those are fetched concurrently and `path` is resolved again,
+    /// until nothing is missing. A provider is asked for each distinct path once per call.
+    pub async fn get_value_async(&self, path: &str) -> Result<Value, RuneError> {
+        let segs = if path.trim().is_empty() {
+            Vec::new()
+        } else {
+            crate::utils::split_path(path)
+        };
+        let mut prefetched = Prefetched {
+            namespaces: self.async_providers.keys().cloned().collect(),
+            values: HashMap::new(),
+            missing: RefCell::new(Vec::new()),
+        };
+
+        loop {
+            // A failure may come from a reference not fetched yet, e.g. one interpolated
+            // into a string, so only report it once nothing is missing.
+            let root = self.resolve_entries(segs.first().map(String::as_str), Some(&prefetched));
+
+            let mut missing = prefetched.missing.take();
+            missing.sort();
+            missing.dedup();
+            if missing.is_empty() {
+                return self.lookup_resolved(&root?, path, &segs);
+            }
+
+            let fetched = try_join_all(
+                missing
+                    .iter()
+                    .map(|reference| self.async_providers[&reference[0]].resolve(&reference[1..])),
+            )
+            .await?;
+            prefetched.values.extend(missing.into_iter().zip(fetched));
+        }
+    }
+
+    /// Typed counterpart of [`get_value_async`](Self::get_value_async).
+    pub async fn get_async<T>(&self, path: &str) -> Result<T, RuneError>
+    where
+        T: TryFrom<Value, Error = RuneError>,
+    {
+        T::try_from(self.get_value_async(path).await?)
+    }
+}
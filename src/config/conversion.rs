@@ -1,10 +1,16 @@
 // Author: Dustin Pilgrim
 // License: MIT
 
-use std::collections::HashMap;
+use std::collections::{BTreeMap, HashMap};
+use std::net::{IpAddr, Ipv4Addr, Ipv6Addr, SocketAddr};
+use std::path::PathBuf;
+use std::time::Duration;
+
+use indexmap::IndexMap;
+use regex::Regex;
 
 use crate::ast::ObjectItem;
-use crate::{RuneError, Value};
+use crate::{RuneError, RuneErrorCode, Value};
 
 impl TryFrom<Value> for String {
     type Error = RuneError;
@@ -17,231 +23,300 @@ impl TryFrom<Value> for String {
                 line: 0,
                 column: 0,
                 hint: Some("Use a string value in your config".into()),
-                code: Some(401),
+                code: Some(RuneErrorCode::ExpectedString.code()),
             }),
         }
     }
 }
 
+fn expected_number(value: &Value) -> RuneError {
+    RuneError::TypeError {
+        message: format!("Expected number, got {:?}", value),
+        line: 0,
+        column: 0,
+        hint: Some("Use a number value in your config".into()),
+        code: Some(RuneErrorCode::ExpectedNumber.code()),
+    }
+}
+
+/// The value as an `f64`. Numeric strings are accepted too, since `$env` and `$sys`
+/// always resolve to strings: `port $env.PORT` should still read as a number.
+fn float_value(value: &Value) -> Result<f64, RuneError> {
+    match value {
+        Value::String(s) => s.trim().parse::<f64>().map_err(|_| expected_number(value)),
+        _ => value.as_f64().ok_or_else(|| expected_number(value)),
+    }
+}
+
 impl TryFrom<Value> for f64 {
     type Error = RuneError;
 
     fn try_from(value: Value) -> Result<Self, Self::Error> {
-        match value {
-            Value::Number(n) => Ok(n),
-            _ => Err(RuneError::TypeError {
-                message: format!("Expected number, got {:?}", value),
-                line: 0,
-                column: 0,
-                hint: Some("Use a number value in your config".into()),
-                code: Some(402),
-            }),
-        }
+        float_value(&value)
     }
 }
 
+/// Precision is lost silently, as with `as`, but numbers beyond `f32::MAX` are an error.
 impl TryFrom<Value> for f32 {
     type Error = RuneError;
 
     fn try_from(value: Value) -> Result<Self, Self::Error> {
-        match value {
-            Value::Number(n) => Ok(n as f32),
-            _ => Err(RuneError::TypeError {
-                message: format!("Expected number, got {:?}", value),
+        let n = float_value(&value)?;
+        if n.is_finite() && n.abs() > f64::from(f32::MAX) {
+            return Err(RuneError::TypeError {
+                message: format!("Number {} out of range for f32", n),
                 line: 0,
                 column: 0,
-                hint: Some("Use a number value in your config".into()),
-                code: Some(402),
-            }),
+                hint: Some("Use a number within f32 range (about ±3.4e38)".into()),
+                code: Some(RuneErrorCode::OutOfRange.code()),
+            });
         }
+        Ok(n as f32)
     }
 }
 
-impl TryFrom<Value> for i32 {
-    type Error = RuneError;
-
-    fn try_from(value: Value) -> Result<Self, Self::Error> {
-        match value {
-            Value::Number(n) => Ok(n as i32),
-            _ => Err(RuneError::TypeError {
-                message: format!("Expected number, got {:?}", value),
+/// The value as an integer wide enough for every target type. Floats are accepted
+/// only when they have no fractional part, so `2.5` never silently becomes `2`.
+/// Integer strings such as a resolved `$env.PORT` are parsed.
+fn integer_value(value: &Value) -> Result<i128, RuneError> {
+    match value {
+        Value::Int(n) => Ok(i128::from(*n)),
+        Value::String(s) => s.trim().parse::<i128>().map_err(|_| expected_number(value)),
+        Value::Float(_) => value
+            .as_i64()
+            .map(i128::from)
+            .or_else(|| value.as_u64().map(i128::from))
+            .ok_or_else(|| RuneError::TypeError {
+                message: format!("Expected integer, got {:?}", value),
                 line: 0,
                 column: 0,
-                hint: Some("Use a number value in your config".into()),
-                code: Some(402),
+                hint: Some("Use a whole number without a fractional part".into()),
+                code: Some(RuneErrorCode::ExpectedNumber.code()),
             }),
-        }
+        _ => Err(expected_number(value)),
     }
 }
 
-impl TryFrom<Value> for i64 {
+macro_rules! impl_try_from_integer {
+    ($($ty:ty => $hint:expr),* $(,)?) => {
+        $(
+            impl TryFrom<Value> for $ty {
+                type Error = RuneError;
+
+                fn try_from(value: Value) -> Result<Self, Self::Error> {
+                    let n = integer_value(&value)?;
+                    <$ty>::try_from(n).map_err(|_| RuneError::TypeError {
+                        message: format!("Number {} out of range for {}", n, stringify!($ty)),
+                        line: 0,
+                        column: 0,
+                        hint: Some($hint.into()),
+                        code: Some(RuneErrorCode::OutOfRange.code()),
+                    })
+                }
+            }
+        )*
+    };
+}
+
+impl_try_from_integer! {
+    i8 => "Use a number between -128 and 127",
+    i16 => "Use a number between -32768 and 32767",
+    i32 => "Use a number within i32 range",
+    i64 => "Use a number within i64 range",
+    isize => "Use a number within isize range",
+    u8 => "Use a number between 0 and 255",
+    u16 => "Use a number between 0 and 65535",
+    u32 => "Use a number between 0 and 4294967295",
+    u64 => "Use a positive number within u64 range",
+    usize => "Use a positive integer",
+}
+
+/// Reads strings such as `"30s"`, `"1h30m"` or `"250ms"`.
+impl TryFrom<Value> for Duration {
     type Error = RuneError;
 
     fn try_from(value: Value) -> Result<Self, Self::Error> {
+        let invalid = |message: String| RuneError::TypeError {
+            message,
+            line: 0,
+            column: 0,
+            hint: Some(
+                "Write a number followed by ms, s, m, h or d, e.g. \"30s\" or \"1h30m\"".into(),
+            ),
+            code: Some(RuneErrorCode::InvalidDuration.code()),
+        };
         match value {
-            Value::Number(n) => Ok(n as i64),
-            _ => Err(RuneError::TypeError {
-                message: format!("Expected number, got {:?}", value),
-                line: 0,
-                column: 0,
-                hint: Some("Use a number value in your config".into()),
-                code: Some(402),
-            }),
+            Value::String(text) => parse_duration(&text).map_err(invalid),
+            other => Err(invalid(format!(
+                "Expected duration string, got {:?}",
+                other
+            ))),
         }
     }
 }
 
-impl TryFrom<Value> for u8 {
+/// A duration written as one or more `<number><unit>` parts, e.g. `1h30m` or `1.5s`.
+fn parse_duration(text: &str) -> Result<Duration, String> {
+    let invalid = || format!("Invalid duration '{}'", text);
+    let mut rest = text.trim();
+    if rest.is_empty() {
+        return Err(invalid());
+    }
+
+    let mut total = Duration::ZERO;
+    while !rest.is_empty() {
+        let number_len = rest
+            .find(|c: char| !(c.is_ascii_digit() || c == '.'))
+            .unwrap_or(rest.len());
+        let unit_len = rest[number_len..]
+            .find(|c: char| !c.is_ascii_alphabetic())
+            .unwrap_or(rest.len() - number_len);
+        let (number, unit) = (
+            &rest[..number_len],
+            &rest[number_len..number_len + unit_len],
+        );
+        rest = rest[number_len + unit_len..].trim_start();
+
+        let seconds_per_unit = match unit {
+            "ms" => 0.001,
+            "s" => 1.0,
+            "m" => 60.0,
+            "h" => 3600.0,
+            "d" => 86400.0,
+            "" => return Err(format!("Duration '{}' is missing a unit", text)),
+            other => return Err(format!("Unknown duration unit '{}' in '{}'", other, text)),
+        };
+        // Whole numbers stay exact; fractions go through f64.
+        let part = match number.parse::<u64>() {
+            Ok(n) if unit == "ms" => Duration::from_millis(n),
+            Ok(n) => (seconds_per_unit as u64)
+                .checked_mul(n)
+                .map(Duration::from_secs)
+                .ok_or_else(invalid)?,
+            Err(_) => number
+                .parse::<f64>()
+                .ok()
+                .and_then(|n| Duration::try_from_secs_f64(n * seconds_per_unit).ok())
+                .ok_or_else(invalid)?,
+        };
+        total = total.checked_add(part).ok_or_else(invalid)?;
+    }
+    Ok(total)
+}
+
+impl TryFrom<Value> for PathBuf {
     type Error = RuneError;
 
     fn try_from(value: Value) -> Result<Self, Self::Error> {
-        match value {
-            Value::Number(n) => {
-                if n >= 0.0 && n <= u8::MAX as f64 {
-                    Ok(n as u8)
-                } else {
-                    Err(RuneError::TypeError {
-                        message: format!("Number {} out of range for u8", n),
-                        line: 0,
-                        column: 0,
-                        hint: Some("Use a number between 0 and 255".into()),
-                        code: Some(407),
-                    })
-                }
-            }
-            _ => Err(RuneError::TypeError {
-                message: format!("Expected number, got {:?}", value),
-                line: 0,
-                column: 0,
-                hint: Some("Use a number value in your config".into()),
-                code: Some(402),
-            }),
-        }
+        String::try_from(value).map(PathBuf::from)
     }
 }
 
-impl TryFrom<Value> for u16 {
+/// `r"..."` literals are already compiled; plain strings are compiled here.
+impl TryFrom<Value> for Regex {
     type Error = RuneError;
 
     fn try_from(value: Value) -> Result<Self, Self::Error> {
         match value {
-            Value::Number(n) => {
-                if n >= 0.0 && n <= u16::MAX as f64 {
-                    Ok(n as u16)
-                } else {
-                    Err(RuneError::TypeError {
-                        message: format!("Number {} out of range for u16", n),
-                        line: 0,
-                        column: 0,
-                        hint: Some("Use a number between 0 and 65535".into()),
-                        code: Some(403),
-                    })
-                }
-            }
+            Value::Regex(re) => Ok(re),
+            Value::String(pattern) => Regex::new(&pattern).map_err(|e| RuneError::TypeError {
+                message: format!("Invalid regex pattern '{}': {}", pattern, e),
+                line: 0,
+                column: 0,
+                hint: Some("Check your regex syntax".into()),
+                code: Some(RuneErrorCode::InvalidPattern.code()),
+            }),
             _ => Err(RuneError::TypeError {
-                message: format!("Expected number, got {:?}", value),
+                message: format!("Expected regex, got {:?}", value),
                 line: 0,
                 column: 0,
-                hint: Some("Use a number value in your config".into()),
-                code: Some(402),
+                hint: Some("Use a regex literal such as r\"^[a-z]+$\"".into()),
+                code: Some(RuneErrorCode::ExpectedRegex.code()),
             }),
         }
     }
 }
 
-impl TryFrom<Value> for u32 {
-    type Error = RuneError;
+/// Address types parse their string form through `FromStr`.
+macro_rules! impl_try_from_address {
+    ($($ty:ty => $kind:literal, $example:literal;)*) => {
+        $(
+            impl TryFrom<Value> for $ty {
+                type Error = RuneError;
 
-    fn try_from(value: Value) -> Result<Self, Self::Error> {
-        match value {
-            Value::Number(n) => {
-                if n >= 0.0 && n <= u32::MAX as f64 {
-                    Ok(n as u32)
-                } else {
-                    Err(RuneError::TypeError {
-                        message: format!("Number {} out of range for u32", n),
+                fn try_from(value: Value) -> Result<Self, Self::Error> {
+                    let invalid = |message: String| RuneError::TypeError {
+                        message,
                         line: 0,
                         column: 0,
-                        hint: Some("Use a number between 0 and 4294967295".into()),
-                        code: Some(408),
-                    })
+                        hint: Some(format!("Write the {} as a string, e.g. \"{}\"", $kind, $example)),
+                        code: Some(RuneErrorCode::InvalidAddress.code()),
+                    };
+                    match value {
+                        Value::String(text) => text.trim().parse().map_err(|e| {
+                            invalid(format!("Invalid {} '{}': {}", $kind, text, e))
+                        }),
+                        other => Err(invalid(format!("Expected {} string, got {:?}", $kind, other))),
+                    }
                 }
             }
-            _ => Err(RuneError::TypeError {
-                message: format!("Expected number, got {:?}", value),
-                line: 0,
-                column: 0,
-                hint: Some("Use a number value in your config".into()),
-                code: Some(402),
-            }),
-        }
-    }
+        )*
+    };
+}
+
+impl_try_from_address! {
+    IpAddr => "IP address", "127.0.0.1";
+    Ipv4Addr => "IPv4 address", "127.0.0.1";
+    Ipv6Addr => "IPv6 address", "::1";
+    SocketAddr => "socket address", "127.0.0.1:8080";
 }
 
-impl TryFrom<Value> for u64 {
+/// Accepts `t"..."` literals and RFC 3339 strings, e.g. from `$env`.
+#[cfg(feature = "chrono")]
+impl TryFrom<Value> for chrono::DateTime<chrono::FixedOffset> {
     type Error = RuneError;
 
     fn try_from(value: Value) -> Result<Self, Self::Error> {
         match value {
-            Value::Number(n) => {
-                if n >= 0.0 && n <= u64::MAX as f64 {
-                    Ok(n as u64)
-                } else {
-                    Err(RuneError::TypeError {
-                        message: format!("Number {} out of range for u64", n),
-                        line: 0,
-                        column: 0,
-                        hint: Some("Use a positive number within u64 range".into()),
-                        code: Some(406),
-                    })
-                }
+            Value::DateTime(timestamp) => Ok(timestamp),
+            Value::String(text) => {
+                chrono::DateTime::parse_from_rfc3339(&text).map_err(|e| RuneError::TypeError {
+                    message: format!("Invalid timestamp '{}': {}", text, e),
+                    line: 0,
+                    column: 0,
+                    hint: Some("Use an ISO-8601 timestamp such as 2024-01-01T00:00:00Z".into()),
+                    code: Some(RuneErrorCode::ExpectedTimestamp.code()),
+                })
             }
             _ => Err(RuneError::TypeError {
-                message: format!("Expected number, got {:?}", value),
+                message: format!("Expected timestamp, got {:?}", value),
                 line: 0,
                 column: 0,
-                hint: Some("Use a number value in your config".into()),
-                code: Some(402),
+                hint: Some("Use a t\"...\" timestamp in your config".into()),
+                code: Some(RuneErrorCode::ExpectedTimestamp.code()),
             }),
         }
     }
 }
 
-impl TryFrom<Value> for usize {
+#[cfg(feature = "chrono")]
+impl TryFrom<Value> for chrono::DateTime<chrono::Utc> {
     type Error = RuneError;
 
     fn try_from(value: Value) -> Result<Self, Self::Error> {
-        match value {
-            Value::Number(n) => {
-                if n >= 0.0 && n.is_finite() {
-                    Ok(n as usize)
-                } else {
-                    Err(RuneError::TypeError {
-                        message: format!("Number {} out of range for usize", n),
-                        line: 0,
-                        column: 0,
-                        hint: Some("Use a positive integer".into()),
-                        code: Some(409),
-                    })
-                }
-            }
-            _ => Err(RuneError::TypeError {
-                message: format!("Expected number, got {:?}", value),
-                line: 0,
-                column: 0,
-                hint: Some("Use a number value in your config".into()),
-                code: Some(402),
-            }),
-        }
+        chrono::DateTime::<chrono::FixedOffset>::try_from(value).map(|t| t.to_utc())
     }
 }
 
+/// Also accepts the strings `"true"` and `"false"` in any case, as `$env` values are strings.
 impl TryFrom<Value> for bool {
     type Error = RuneError;
 
     fn try_from(value: Value) -> Result<Self, Self::Error> {
         match value {
             Value::Bool(b) => Ok(b),
+            Value::String(ref s) if s.trim().eq_ignore_ascii_case("true") => Ok(true),
+            Value::String(ref s) if s.trim().eq_ignore_ascii_case("false") => Ok(false),
             Value::Reference(ref path) if path.len() == 1 => {
                 let ref_name = &path[0];
                 if ref_name.to_lowercase().starts_with("tru")
@@ -255,7 +330,7 @@ impl TryFrom<Value> for bool {
                         line: 0,
                         column: 0,
                         hint: None,
-                        code: Some(404),
+                        code: Some(RuneErrorCode::ExpectedBool.code()),
                     })
                 } else {
                     Err(RuneError::TypeError {
@@ -266,7 +341,7 @@ impl TryFrom<Value> for bool {
                         line: 0,
                         column: 0,
                         hint: None,
-                        code: Some(404),
+                        code: Some(RuneErrorCode::ExpectedBool.code()),
                     })
                 }
             }
@@ -275,12 +350,14 @@ impl TryFrom<Value> for bool {
                 line: 0,
                 column: 0,
                 hint: None,
-                code: Some(404),
+                code: Some(RuneErrorCode::ExpectedBool.code()),
             }),
         }
     }
 }
 
+/// Also accepts `Value::Bytes`, one integer per byte, which is how `Vec<u8>` reads
+/// a `b64"..."` literal.
 impl<T> TryFrom<Value> for Vec<T>
 where
     T: TryFrom<Value, Error = RuneError>,
@@ -289,6 +366,10 @@ where
 
     fn try_from(value: Value) -> Result<Self, Self::Error> {
         match value {
+            Value::Bytes(bytes) => bytes
+                .into_iter()
+                .map(|b| T::try_from(Value::Int(i64::from(b))))
+                .collect(),
             Value::Array(arr) => {
                 let mut result = Vec::new();
                 for item in arr {
@@ -301,7 +382,7 @@ where
                 line: 0,
                 column: 0,
                 hint: Some("Use an array [...] in your config".into()),
-                code: Some(405),
+                code: Some(RuneErrorCode::ExpectedArray.code()),
             }),
         }
     }
@@ -336,21 +417,23 @@ fn object_items_to_map(items: Vec<ObjectItem>) -> Result<HashMap<String, Value>,
             ObjectItem::Assign(key, val) => {
                 map.insert(key, val);
             }
-            ObjectItem::IfBlock(_) => {
-                return Err(RuneError::TypeError {
-                    message: "Expected object with only key/value pairs, but found an if-block".into(),
-                    line: 0,
-                    column: 0,
-                    hint: Some("This usually means if-blocks were not resolved. Ensure you resolve/evaluate the config before converting it to a HashMap.".into()),
-                    code: Some(410),
-                });
-            }
+            ObjectItem::IfBlock(_) => return Err(unresolved_if_block()),
         }
     }
 
     Ok(map)
 }
 
+fn unresolved_if_block() -> RuneError {
+    RuneError::TypeError {
+        message: "Expected object with only key/value pairs, but found an if-block".into(),
+        line: 0,
+        column: 0,
+        hint: Some("This usually means if-blocks were not resolved. Ensure you resolve/evaluate the config before converting it to a HashMap.".into()),
+        code: Some(RuneErrorCode::ExpectedObject.code()),
+    }
+}
+
 impl TryFrom<Value> for HashMap<String, Value> {
     type Error = RuneError;
 
@@ -362,37 +445,80 @@ impl TryFrom<Value> for HashMap<String, Value> {
                 line: 0,
                 column: 0,
                 hint: Some("Use an object block in your config".into()),
-                code: Some(410),
+                code: Some(RuneErrorCode::ExpectedObject.code()),
             }),
         }
     }
 }
 
-impl TryFrom<Value> for HashMap<String, String> {
-    type Error = RuneError;
-
-    fn try_from(value: Value) -> Result<Self, Self::Error> {
-        match value {
-            Value::Object(items) => {
-                let base = object_items_to_map(items)?;
-                let mut map = HashMap::new();
-                for (key, val) in base {
-                    let string_val = String::try_from(val)?;
-                    map.insert(key, string_val);
-                }
-                Ok(map)
-            }
-            _ => Err(RuneError::TypeError {
+/// Converts each value of an object block in order. The first value that does not
+/// convert is the error, naming its key.
+fn convert_object_values<T, M>(value: Value) -> Result<M, RuneError>
+where
+    T: TryFrom<Value, Error = RuneError>,
+    M: FromIterator<(String, T)>,
+{
+    let items = match value {
+        Value::Object(items) => items,
+        _ => {
+            return Err(RuneError::TypeError {
                 message: format!("Expected object, got {:?}", value),
                 line: 0,
                 column: 0,
-                hint: Some("Use an object block with string values".into()),
-                code: Some(410),
-            }),
+                hint: Some("Use an object block in your config".into()),
+                code: Some(RuneErrorCode::ExpectedObject.code()),
+            });
         }
-    }
+    };
+
+    items
+        .into_iter()
+        .map(|item| {
+            let ObjectItem::Assign(key, val) = item else {
+                return Err(unresolved_if_block());
+            };
+            match T::try_from(val) {
+                Ok(converted) => Ok((key, converted)),
+                Err(RuneError::TypeError {
+                    message,
+                    line,
+                    column,
+                    hint,
+                    code,
+                }) => Err(RuneError::TypeError {
+                    message: format!("Key '{}': {}", key, message),
+                    line,
+                    column,
+                    hint,
+                    code,
+                }),
+                Err(e) => Err(e),
+            }
+        })
+        .collect()
 }
 
+/// Typed maps of an object block, e.g. `HashMap<String, bool>` for a block of feature
+/// flags. `IndexMap` keeps the keys in the order they are written.
+macro_rules! impl_try_from_map {
+    ($($map:ident),*) => {
+        $(
+            impl<T> TryFrom<Value> for $map<String, T>
+            where
+                T: TryFrom<Value, Error = RuneError>,
+            {
+                type Error = RuneError;
+
+                fn try_from(value: Value) -> Result<Self, Self::Error> {
+                    convert_object_values(value)
+                }
+            }
+        )*
+    };
+}
+
+impl_try_from_map!(HashMap, BTreeMap, IndexMap);
+
 impl TryFrom<Value> for (String, String) {
     type Error = RuneError;
 
@@ -408,7 +534,7 @@ impl TryFrom<Value> for (String, String) {
                 line: 0,
                 column: 0,
                 hint: Some("Use [\"key\", \"value\"] format".into()),
-                code: Some(411),
+                code: Some(RuneErrorCode::ExpectedPair.code()),
             }),
         }
     }
@@ -429,7 +555,7 @@ impl TryFrom<Value> for (String, Value) {
                 line: 0,
                 column: 0,
                 hint: Some("Use [\"key\", value] format".into()),
-                code: Some(411),
+                code: Some(RuneErrorCode::ExpectedPair.code()),
             }),
         }
     }
@@ -444,7 +570,8 @@ impl RuneError {
             message,
             path,
             hint: Some("Check file path and permissions".into()),
-            code: Some(300),
+            code: Some(RuneErrorCode::FileUnavailable.code()),
+            source: None,
         }
     }
 }
@@ -1,132 +1,15 @@
 // Author: Dustin Pilgrim
 // License MIT
 
-use std::path::PathBuf;
-
-use crate::{Document, RuneError, Value, parser};
-
-/// Gather statement parsed from a file.
-#[derive(Debug, Clone)]
-pub(super) struct GatherSpec {
-    /// The alias key the parser will use for this gather (either explicit `as`, or file stem).
-    pub alias: String,
-    /// The raw path string inside quotes.
-    pub raw_path: String,
-    /// True if the config explicitly used `as alias`.
-    pub explicit_alias: bool,
-}
-
-/// Parse gather statements from raw file content.
-/// - Skips fully-commented lines (starting with '#').
-/// - Supports `gather "path"` or `gather "path" as alias`.
-/// - Allows trailing inline comments.
-pub(super) fn parse_gather_specs(content: &str) -> Vec<GatherSpec> {
-    let mut out = Vec::new();
-
-    for line in content.lines() {
-        let trimmed = line.trim();
-
-        if trimmed.is_empty() || trimmed.starts_with('#') {
-            continue;
-        }
-
-        if !trimmed.starts_with("gather") {
-            continue;
-        }
-
-        let Some(rest) = trimmed.strip_prefix("gather").map(|s| s.trim()) else {
-            continue;
-        };
-
-        // Extract the quoted path
-        let Some(path) = extract_quoted_string(rest) else {
-            continue;
-        };
-
-        // Determine if there is an explicit `as alias` after the quoted path.
-        // We search the remainder after the closing quote.
-        let (explicit_alias, alias_opt) = {
-            let quote_char = rest.chars().next().unwrap_or('"');
-            let after_open = &rest[1..];
-
-            let end_rel = if quote_char == '"' {
-                after_open.find('"')
-            } else if quote_char == '\'' {
-                after_open.find('\'')
-            } else {
-                None
-            };
+use std::cell::RefCell;
+use std::collections::HashMap;
+use std::path::Path;
 
-            if let Some(end_rel) = end_rel {
-                // +2 accounts for opening quote + the closing quote itself
-                let after_quote = rest[(end_rel + 2)..].trim();
-
-                // allow: `as alias` (with arbitrary whitespace)
-                if let Some(_as_pos) = after_quote.find("as") {
-                    // require `as` to be a standalone token boundary-ish:
-                    // simplest: split whitespace and look for "as"
-                    let mut it = after_quote.split_whitespace();
-                    let mut found_as = false;
-                    let mut alias: Option<String> = None;
-
-                    while let Some(tok) = it.next() {
-                        if !found_as && tok == "as" {
-                            found_as = true;
-                            alias = it.next().map(|s| s.to_string());
-                            break;
-                        }
-                    }
+use indexmap::IndexMap;
 
-                    if found_as {
-                        (true, alias)
-                    } else {
-                        (false, None)
-                    }
-                } else {
-                    (false, None)
-                }
-            } else {
-                (false, None)
-            }
-        };
-
-        let alias = alias_opt.unwrap_or_else(|| {
-            PathBuf::from(&path)
-                .file_stem()
-                .and_then(|s| s.to_str())
-                .unwrap_or("imported")
-                .to_string()
-        });
-
-        out.push(GatherSpec {
-            alias,
-            raw_path: path,
-            explicit_alias,
-        });
-    }
-
-    out
-}
-
-/// Extract the first quoted string from the input.
-/// Supports "double" or 'single' quotes.
-fn extract_quoted_string(input: &str) -> Option<String> {
-    let trimmed = input.trim();
-
-    if trimmed.starts_with('"') {
-        if let Some(end_quote) = trimmed[1..].find('"') {
-            return Some(trimmed[1..end_quote + 1].to_string());
-        }
-    }
-
-    if trimmed.starts_with('\'') {
-        if let Some(end_quote) = trimmed[1..].find('\'') {
-            return Some(trimmed[1..end_quote + 1].to_string());
-        }
-    }
-
-    None
-}
+use crate::resolver::ResolverContext;
+use crate::utils::resolve_path;
+use crate::{Document, RuneError, RuneErrorCode, Value, parser};
 
 pub(super) fn find_config_line(key: &str, raw_content: &str) -> (usize, String) {
     let (line, _, snippet) = find_config_location(key, raw_content);
@@ -144,8 +27,8 @@ pub(super) fn find_config_location(key: &str, raw_content: &str) -> (usize, usiz
             continue;
         }
 
-        // scope open: foo:
-        if trimmed.ends_with(':') && !trimmed.starts_with('@') {
+        // scope open: foo: (or @meta: — its '@' keeps it from matching real paths)
+        if trimmed.ends_with(':') {
             let scope_name = trimmed.trim_end_matches(':').trim().to_string();
             let full_path = {
                 let mut path = scope_stack.clone();
@@ -225,15 +108,25 @@ fn condition_is_met(
     condition: &crate::ast::Condition,
     parser: &parser::Parser,
     doc: &Document,
+    scope: &Scope<'_>,
 ) -> bool {
     use crate::resolver;
 
-    fn resolve_path_value(parser: &parser::Parser, doc: &Document, path: &str) -> Option<Value> {
+    fn resolve_path_value(
+        parser: &parser::Parser,
+        doc: &Document,
+        scope: &Scope<'_>,
+        path: &str,
+    ) -> Option<Value> {
         let segs: Vec<String> = path.split('.').map(String::from).collect();
 
         if segs.len() >= 2 {
             match segs[0].as_str() {
-                "env" | "sys" | "runtime" => resolver::parse_dollar_reference(segs).ok(),
+                "runtime" => lookup_runtime(&scope.context.runtime, &segs[1..]),
+                ns if scope.context.providers.contains_key(ns) => {
+                    scope.context.providers[ns].resolve(&segs[1..]).ok()
+                }
+                "env" | "sys" => resolver::parse_dollar_reference(segs, scope.context).ok(),
                 "var" => parser.resolve_reference(&segs[1..], doc).cloned(),
                 _ => parser.resolve_reference(&segs, doc).cloned(),
             }
@@ -243,16 +136,24 @@ fn condition_is_met(
     }
 
     match condition {
-        crate::ast::Condition::Equals(path, expected) => resolve_path_value(parser, doc, path)
-            .as_ref()
-            .map(|actual| actual == expected)
-            .unwrap_or(false),
-        crate::ast::Condition::NotEquals(path, expected) => resolve_path_value(parser, doc, path)
-            .as_ref()
-            .map(|actual| actual != expected)
-            .unwrap_or(true),
-        crate::ast::Condition::Exists(path) => resolve_path_value(parser, doc, path).is_some(),
-        crate::ast::Condition::NotExists(path) => resolve_path_value(parser, doc, path).is_none(),
+        crate::ast::Condition::Equals(path, expected) => {
+            resolve_path_value(parser, doc, scope, path)
+                .as_ref()
+                .map(|actual| actual == expected)
+                .unwrap_or(false)
+        }
+        crate::ast::Condition::NotEquals(path, expected) => {
+            resolve_path_value(parser, doc, scope, path)
+                .as_ref()
+                .map(|actual| actual != expected)
+                .unwrap_or(true)
+        }
+        crate::ast::Condition::Exists(path) => {
+            resolve_path_value(parser, doc, scope, path).is_some()
+        }
+        crate::ast::Condition::NotExists(path) => {
+            resolve_path_value(parser, doc, scope, path).is_none()
+        }
     }
 }
 
@@ -260,8 +161,9 @@ pub(super) fn evaluate_conditional(
     cond: &crate::ast::ConditionalValue,
     parser: &parser::Parser,
     doc: &Document,
+    scope: &Scope<'_>,
 ) -> Value {
-    if condition_is_met(&cond.condition, parser, doc) {
+    if condition_is_met(&cond.condition, parser, doc, scope) {
         cond.then_value.clone()
     } else {
         cond.else_value.clone().unwrap_or(Value::Null)
@@ -271,7 +173,8 @@ pub(super) fn evaluate_conditional(
 fn stringify_interpolated_value(value: &Value) -> Result<String, RuneError> {
     match value {
         Value::String(s) => Ok(s.clone()),
-        Value::Number(n) => Ok(n.to_string()),
+        Value::Int(n) => Ok(n.to_string()),
+        Value::Float(n) => Ok(n.to_string()),
         Value::Bool(b) => Ok(b.to_string()),
         Value::Null => Ok(String::new()),
         other => Err(RuneError::TypeError {
@@ -282,7 +185,7 @@ fn stringify_interpolated_value(value: &Value) -> Result<String, RuneError> {
             line: 0,
             column: 0,
             hint: Some("Use string/number/bool/null for $var interpolation".into()),
-            code: Some(401),
+            code: Some(RuneErrorCode::NonScalarInterpolation.code()),
         }),
     }
 }
@@ -291,14 +194,16 @@ fn resolve_var_reference(
     path: &[String],
     parser: &parser::Parser,
     main_doc: &Document,
+    scope: &Scope<'_>,
 ) -> Result<Value, RuneError> {
     if path.len() < 2 {
         return Err(RuneError::SyntaxError {
             message: "Invalid $var path".into(),
             line: 0,
             column: 0,
+            span: None,
             hint: Some("Use $var.<name> or $var.<path.to.value>".into()),
-            code: Some(209),
+            code: Some(RuneErrorCode::InvalidDollarVariable.code()),
         });
     }
 
@@ -310,133 +215,286 @@ fn resolve_var_reference(
                 target.join(".")
             ),
             hint: Some("Define the variable before using $var.<name>".into()),
-            code: Some(309),
+            code: Some(RuneErrorCode::VariableNotFound.code()),
         });
     };
-    resolve_value_recursively(resolved, parser, main_doc)
+    follow_reference(target, resolved, parser, main_doc, scope)
 }
 
-fn interpolate_var_refs_in_string(
-    input: &str,
+/// Join the parts of an interpolated string, resolving `$var`, `$runtime`, `$const` and
+/// provider references; references to other namespaces are kept as written.
+fn interpolate_parts(
+    parts: &[Value],
     parser: &parser::Parser,
     main_doc: &Document,
+    scope: &Scope<'_>,
 ) -> Result<String, RuneError> {
     let mut out = String::new();
-    let chars: Vec<char> = input.chars().collect();
-    let mut i = 0usize;
-
-    while i < chars.len() {
-        if chars[i] != '$' {
-            out.push(chars[i]);
-            i += 1;
-            continue;
-        }
 
-        let mut j = i + 1;
-        let mut ns = String::new();
-        while j < chars.len() {
-            let ch = chars[j];
-            if ch.is_alphanumeric() || ch == '_' || ch == '-' {
-                ns.push(ch);
-                j += 1;
-            } else {
-                break;
-            }
-        }
-
-        if ns.is_empty() {
-            out.push('$');
-            i += 1;
+    for part in parts {
+        let Value::Namespaced(path) = part else {
+            let resolved = resolve_value_recursively(part, parser, main_doc, scope)?;
+            out.push_str(&stringify_interpolated_value(&resolved)?);
             continue;
-        }
-
-        let mut path = vec![ns.clone()];
-        while j < chars.len() && chars[j] == '.' {
-            j += 1;
-            let mut seg = String::new();
-            while j < chars.len() {
-                let ch = chars[j];
-                if ch.is_alphanumeric() || ch == '_' || ch == '-' {
-                    seg.push(ch);
-                    j += 1;
-                } else {
-                    break;
-                }
-            }
-            if seg.is_empty() {
-                return Err(RuneError::SyntaxError {
-                    message: "Expected identifier after '.'".into(),
-                    line: 0,
-                    column: 0,
-                    hint: None,
-                    code: Some(210),
-                });
-            }
-            path.push(seg);
-        }
+        };
+        let ns = path[0].as_str();
+
+        let injected_value = if ns == "runtime" {
+            Some(
+                lookup_runtime(&scope.context.runtime, &path[1..])
+                    .ok_or_else(|| runtime_unset(path))?,
+            )
+        } else if ns == "const" {
+            Some(resolve_const_reference(
+                &path[1..],
+                parser,
+                main_doc,
+                scope,
+            )?)
+        } else if let Some(provider) = scope.context.providers.get(ns) {
+            Some(provider.resolve(&path[1..])?)
+        } else {
+            None
+        };
 
         if ns == "var" {
-            let v = resolve_var_reference(&path, parser, main_doc)?;
+            let v = resolve_var_reference(path, parser, main_doc, scope)?;
+            out.push_str(&stringify_interpolated_value(&v)?);
+        } else if let Some(v) = injected_value {
             out.push_str(&stringify_interpolated_value(&v)?);
         } else {
             out.push('$');
             out.push_str(&path.join("."));
         }
-
-        i = j;
     }
 
     Ok(out)
 }
 
+/// What resolution needs from `RuneConfig` beyond the documents: the directory
+/// `$file` paths are relative to, and the resolver context with `$runtime` values
+/// and custom namespace providers.
+pub(super) struct Scope<'a> {
+    pub base_dir: &'a Path,
+    pub context: &'a ResolverContext,
+    /// Values already fetched from async providers, during `get_value_async`.
+    pub prefetched: Option<&'a Prefetched>,
+    /// References currently being resolved, outermost first, for cycle detection.
+    pub resolving: RefCell<Vec<String>>,
+}
+
+/// Values awaited from async providers for one `get_value_async` call.
+pub(super) struct Prefetched {
+    /// Namespaces with an async provider.
+    pub namespaces: Vec<String>,
+    /// Fetched values, keyed by the full reference path.
+    pub values: HashMap<Vec<String>, Value>,
+    /// References to those namespaces that resolution reached but were not fetched yet.
+    pub missing: RefCell<Vec<Vec<String>>>,
+}
+
+/// Resolve `target`, the value `path` points at, failing on cycles such as `a b` / `b a`.
+fn follow_reference(
+    path: &[String],
+    target: &Value,
+    parser: &parser::Parser,
+    main_doc: &Document,
+    scope: &Scope<'_>,
+) -> Result<Value, RuneError> {
+    let key = path.join(".");
+    {
+        let mut chain = scope.resolving.borrow_mut();
+        if let Some(start) = chain.iter().position(|p| *p == key) {
+            let mut cycle = chain[start..].to_vec();
+            cycle.push(key);
+            return Err(RuneError::RuntimeError {
+                message: format!("Circular reference: {}", cycle.join(" -> ")),
+                hint: Some("Break the cycle so each reference ends at a value".into()),
+                code: Some(RuneErrorCode::CircularReference.code()),
+            });
+        }
+        chain.push(key);
+    }
+
+    let result = resolve_value_recursively(target, parser, main_doc, scope);
+    scope.resolving.borrow_mut().pop();
+    result
+}
+
+/// Largest file `$file` will inline.
+const MAX_INLINE_FILE_BYTES: u64 = 1024 * 1024;
+
+/// `$file.<path>`: the file's contents as a string. Unquoted segments are joined with
+/// dots, so `$file.cert.pem` reads `cert.pem`; quote paths with slashes:
+/// `$file."/etc/ssl/cert.pem"`.
+fn read_file_reference(path: &[String], base_dir: &Path) -> Result<Value, RuneError> {
+    let raw = path.join(".");
+    let file = resolve_path(&raw, base_dir)?;
+    let display = file.to_string_lossy().to_string();
+
+    let size = std::fs::metadata(&file)
+        .map_err(|e| RuneError::FileError {
+            message: format!("Failed to read $file.{}: {}", raw, e),
+            path: display.clone(),
+            hint: Some("Check that the file exists and is readable".into()),
+            code: Some(RuneErrorCode::FileRead.code()),
+            source: Some(e.into()),
+        })?
+        .len();
+    if size > MAX_INLINE_FILE_BYTES {
+        return Err(RuneError::FileError {
+            message: format!(
+                "File for $file.{} is {} bytes, over the {} byte limit",
+                raw, size, MAX_INLINE_FILE_BYTES
+            ),
+            path: display,
+            hint: Some("Reference the path as a string and read large files yourself".into()),
+            code: Some(RuneErrorCode::FileTooLarge.code()),
+            source: None,
+        });
+    }
+
+    std::fs::read_to_string(&file)
+        .map(Value::String)
+        .map_err(|e| RuneError::FileError {
+            message: format!("Failed to read $file.{}: {}", raw, e),
+            path: display,
+            hint: Some("Check that the file is readable UTF-8 text".into()),
+            code: Some(RuneErrorCode::FileRead.code()),
+            source: Some(e.into()),
+        })
+}
+
+fn runtime_unset(path: &[String]) -> RuneError {
+    RuneError::RuntimeError {
+        message: format!("Runtime value '{}' not set", path[1..].join(".")),
+        hint: Some("Provide it with RuneConfig::set_runtime before reading this key".into()),
+        code: Some(RuneErrorCode::RuntimeValueNotSet.code()),
+    }
+}
+
+/// Look up a `$runtime` path among values registered with `RuneConfig::set_runtime`.
+/// Segments after the first walk into object values.
+fn lookup_runtime(runtime: &IndexMap<String, Value>, path: &[String]) -> Option<Value> {
+    let (first, rest) = path.split_first()?;
+    descend(runtime.get(first)?, rest)
+}
+
+/// The `$const` value at `path`, resolved so constants may build on each other.
+fn resolve_const_reference(
+    path: &[String],
+    parser: &parser::Parser,
+    main_doc: &Document,
+    scope: &Scope<'_>,
+) -> Result<Value, RuneError> {
+    let found = path.split_first().and_then(|(first, rest)| {
+        let (_, value) = main_doc.constants.iter().find(|(k, _)| k == first)?;
+        descend(value, rest)
+    });
+    let Some(value) = found else {
+        return Err(RuneError::RuntimeError {
+            message: format!("Constant '{}' is not defined", path.join(".")),
+            hint: Some("Declare it in a `const:` block".into()),
+            code: Some(RuneErrorCode::UndefinedConstant.code()),
+        });
+    };
+    let mut key = vec!["const".to_string()];
+    key.extend_from_slice(path);
+    follow_reference(&key, &value, parser, main_doc, scope)
+}
+
+/// Walk `path` through nested objects starting at `value`.
+fn descend(value: &Value, path: &[String]) -> Option<Value> {
+    let mut cur = value;
+    for seg in path {
+        cur = cur.child(seg)?;
+    }
+    Some(cur.clone())
+}
+
+/// Resolve `path` through its namespace (`env`, `sys`, `runtime`, `const`, `file`,
+/// `var` or a provider), leaving `value` as it is for an unknown namespace.
+fn resolve_namespaced(
+    value: &Value,
+    path: &[String],
+    parser: &parser::Parser,
+    main_doc: &Document,
+    scope: &Scope<'_>,
+) -> Result<Value, RuneError> {
+    if path.first().map(|s| s.as_str()) == Some("env") && path.len() == 2 {
+        let var_name = &path[1];
+        scope
+            .context
+            .env_var(var_name)
+            .map(Value::String)
+            .ok_or_else(|| RuneError::RuntimeError {
+                message: format!("Environment variable '{}' not set", var_name),
+                hint: Some("Make sure the environment variable is defined".into()),
+                code: Some(RuneErrorCode::EnvVarNotSet.code()),
+            })
+    } else if path.first().map(|s| s.as_str()) == Some("sys") {
+        // Parsed `$sys` is expanded up front; this covers references built in code.
+        crate::resolver::resolve_reference_value(value, scope.context)
+    } else if path.first().map(|s| s.as_str()) == Some("runtime") {
+        lookup_runtime(&scope.context.runtime, &path[1..]).ok_or_else(|| runtime_unset(path))
+    } else if path.first().map(|s| s.as_str()) == Some("const") {
+        resolve_const_reference(&path[1..], parser, main_doc, scope)
+    } else if path.first().map(|s| s.as_str()) == Some("file") && path.len() > 1 {
+        read_file_reference(&path[1..], scope.base_dir)
+    } else if let Some(provider) = path.first().and_then(|ns| scope.context.providers.get(ns)) {
+        provider.resolve(&path[1..])
+    } else if let Some(prefetched) = scope
+        .prefetched
+        .filter(|p| path.len() > 1 && p.namespaces.contains(&path[0]))
+    {
+        // Left unresolved until `get_value_async` has fetched it.
+        if let Some(fetched) = prefetched.values.get(path) {
+            Ok(fetched.clone())
+        } else {
+            prefetched.missing.borrow_mut().push(path.to_vec());
+            Ok(value.clone())
+        }
+    } else if path.first().map(|s| s.as_str()) == Some("var") {
+        resolve_var_reference(path, parser, main_doc, scope)
+    } else {
+        Ok(value.clone())
+    }
+}
+
 pub(super) fn resolve_value_recursively(
     value: &Value,
     parser: &parser::Parser,
     main_doc: &Document,
+    scope: &Scope<'_>,
 ) -> Result<Value, RuneError> {
     match value {
         Value::Conditional(cond) => {
-            let resolved = evaluate_conditional(cond, parser, main_doc);
-            resolve_value_recursively(&resolved, parser, main_doc)
+            let resolved = evaluate_conditional(cond, parser, main_doc, scope);
+            resolve_value_recursively(&resolved, parser, main_doc, scope)
         }
 
+        Value::Namespaced(path) => resolve_namespaced(value, path, parser, main_doc, scope),
+
         Value::Reference(path) => {
-            if path.get(0).map(|s| s.as_str()) == Some("env") && path.len() == 2 {
-                let var_name = &path[1];
-                std::env::var(var_name)
-                    .map(Value::String)
-                    .map_err(|_| RuneError::RuntimeError {
-                        message: format!("Environment variable '{}' not set", var_name),
-                        hint: Some("Make sure the environment variable is defined".into()),
-                        code: Some(308),
-                    })
-            } else if path.get(0).map(|s| s.as_str()) == Some("sys") {
-                Ok(Value::String(format!(
-                    "sys_placeholder:{}",
-                    path[1..].join(".")
-                )))
-            } else if path.get(0).map(|s| s.as_str()) == Some("runtime") {
-                Ok(Value::String(format!(
-                    "runtime_placeholder:{}",
-                    path[1..].join(".")
-                )))
-            } else if path.get(0).map(|s| s.as_str()) == Some("var") {
-                resolve_var_reference(path, parser, main_doc)
-            } else if let Some(resolved) = parser.resolve_reference(path, main_doc) {
-                resolve_value_recursively(resolved, parser, main_doc)
+            if let Some(resolved) = parser.resolve_reference(path, main_doc) {
+                follow_reference(path, resolved, parser, main_doc, scope)
+            } else if path.first().is_some_and(|ns| ns != "file") {
+                // No such key, so treat it as a namespace, as for a reference built in
+                // code such as `Reference(["env", "HOME"])`. Files are only read for `$file`.
+                resolve_namespaced(value, path, parser, main_doc, scope)
             } else {
                 Ok(value.clone())
             }
         }
 
-        Value::String(s) => Ok(Value::String(interpolate_var_refs_in_string(
-            s, parser, main_doc,
+        Value::Interpolated(parts) => Ok(Value::String(interpolate_parts(
+            parts, parser, main_doc, scope,
         )?)),
 
         Value::Array(arr) => {
             let mut resolved_array = Vec::new();
             for item in arr {
-                resolved_array.push(resolve_value_recursively(item, parser, main_doc)?);
+                resolved_array.push(resolve_value_recursively(item, parser, main_doc, scope)?);
             }
             Ok(Value::Array(resolved_array))
         }
@@ -449,22 +507,28 @@ pub(super) fn resolve_value_recursively(
                 items: &[ObjectItem],
                 parser: &parser::Parser,
                 doc: &Document,
+                scope: &Scope<'_>,
             ) -> Result<(), RuneError> {
                 for item in items {
                     match item {
                         ObjectItem::Assign(k, v) => {
-                            let rv = super::helpers::resolve_value_recursively(v, parser, doc)?;
+                            let rv =
+                                super::helpers::resolve_value_recursively(v, parser, doc, scope)?;
                             out.push(ObjectItem::Assign(k.clone(), rv));
                         }
                         ObjectItem::IfBlock(block) => {
-                            let take_then =
-                                super::helpers::condition_is_met(&block.condition, parser, doc);
+                            let take_then = super::helpers::condition_is_met(
+                                &block.condition,
+                                parser,
+                                doc,
+                                scope,
+                            );
                             let branch: &[ObjectItem] = if take_then {
                                 &block.then_items
                             } else {
                                 block.else_items.as_deref().unwrap_or(&[])
                             };
-                            flatten_items(out, branch, parser, doc)?;
+                            flatten_items(out, branch, parser, doc, scope)?;
                         }
                     }
                 }
@@ -472,7 +536,7 @@ pub(super) fn resolve_value_recursively(
             }
 
             let mut flattened: Vec<ObjectItem> = Vec::new();
-            flatten_items(&mut flattened, items, parser, main_doc)?;
+            flatten_items(&mut flattened, items, parser, main_doc, scope)?;
             Ok(Value::Object(flattened))
         }
 
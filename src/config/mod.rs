@@ -3,23 +3,56 @@
 
 use std::fs;
 use std::path::{Path, PathBuf};
+#[cfg(feature = "tokio")]
+use std::sync::Arc;
 
 use indexmap::IndexMap;
+use once_cell::sync::OnceCell;
 
-use crate::RuneError;
-use crate::ast::{Document, ObjectItem, Value};
+use crate::ast::{Document, MergeStrategy, Value, merge_named_values};
 use crate::parser;
+#[cfg(feature = "tokio")]
+use crate::resolver::AsyncResolverProvider;
+use crate::resolver::{ResolverContext, ResolverProvider};
+use crate::utils::resolve_path;
+use crate::{RuneError, RuneErrorCode};
 
 mod access;
+#[cfg(feature = "tokio")]
+mod async_access;
 mod conversion;
 mod helpers;
+mod mutation;
 mod validation;
+#[cfg(feature = "notify")]
+mod watch;
+
+#[cfg(feature = "notify")]
+pub use watch::ConfigWatcher;
+
+/// When references, conditionals, and `$` values are resolved.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum ResolutionMode {
+    /// Resolve on every access, so only the keys you read need to be valid.
+    #[default]
+    Lazy,
+    /// Resolve the whole config up front and serve reads from the result, so broken
+    /// references fail at load time.
+    Eager,
+}
 
 /// Main configuration struct that holds parsed RUNE documents and handles resolution
 pub struct RuneConfig {
     documents: IndexMap<String, Document>,
     main_doc_key: String,
-    raw_content: String, // Store for error reporting
+    raw_content: String,      // Store for error reporting
+    path: Option<PathBuf>,    // File the main document was read from
+    base_dir: PathBuf,        // Relative `$file` paths resolve against this
+    context: ResolverContext, // `$env` overrides, System cache, `$runtime`, providers
+    #[cfg(feature = "tokio")]
+    async_providers: IndexMap<String, Arc<dyn AsyncResolverProvider>>,
+    resolution_mode: ResolutionMode,
+    resolved: OnceCell<Value>, // Eager mode's resolved root
 }
 
 impl RuneConfig {
@@ -30,12 +63,20 @@ impl RuneConfig {
     /// let config = RuneConfig::from_file("config.rune")?;
     /// ```
     pub fn from_file<P: AsRef<Path>>(path: P) -> Result<Self, RuneError> {
+        Self::from_file_with_context(path, ResolverContext::new())
+    }
+
+    /// Like `from_file`, resolving `$env`, `$sys`, `$runtime` and providers through `context`.
+    pub fn from_file_with_context<P: AsRef<Path>>(
+        path: P,
+        context: ResolverContext,
+    ) -> Result<Self, RuneError> {
         let path_ref = path.as_ref();
 
         // Auto-detect base directory for imports (same directory as the config file)
         let base_dir = path_ref.parent().unwrap_or_else(|| Path::new("."));
 
-        Self::from_file_with_base(path_ref, base_dir)
+        Self::load_file(path_ref, base_dir, context)
     }
 
     /// Load a RUNE config file with fallback support
@@ -51,7 +92,9 @@ impl RuneConfig {
             Err(RuneError::FileError { .. }) => {
                 // Primary file not found, try fallback
                 Self::from_file(&fallback).map_err(|e| match e {
-                    RuneError::FileError { message, .. } => RuneError::FileError {
+                    RuneError::FileError {
+                        message, source, ..
+                    } => RuneError::FileError {
                         message: format!(
                             "Failed to load config from primary path '{}' or fallback path '{}': {}",
                             primary.as_ref().display(),
@@ -64,7 +107,8 @@ impl RuneConfig {
                             fallback.as_ref().display()
                         ),
                         hint: Some("Check that at least one of the config files exists".into()),
-                        code: Some(301),
+                        code: Some(RuneErrorCode::FileRead.code()),
+                        source,
                     },
                     other => other,
                 })
@@ -80,42 +124,64 @@ impl RuneConfig {
     ///     - the gathered file's *globals + items* are merged into the main document
     ///     - and the gathered file is also available under its default alias (file stem)
     /// - `gather "file.rune" as alias` behaves like a **namespaced import** only
+    /// - a missing file is an error, unless written `gather optional "file.rune"`
     pub fn from_file_with_base<P: AsRef<Path>>(path: P, base_dir: P) -> Result<Self, RuneError> {
-        use std::collections::HashSet;
+        Self::load_file(path.as_ref(), base_dir.as_ref(), ResolverContext::new())
+    }
 
-        let content = fs::read_to_string(&path).map_err(|e| RuneError::FileError {
+    fn load_file(
+        path: &Path,
+        base_dir: &Path,
+        context: ResolverContext,
+    ) -> Result<Self, RuneError> {
+        let content = fs::read_to_string(path).map_err(|e| RuneError::FileError {
             message: format!("Failed to read file: {}", e),
-            path: path.as_ref().to_string_lossy().to_string(),
+            path: path.to_string_lossy().to_string(),
             hint: Some("Check that the file exists and is readable".into()),
-            code: Some(301),
+            code: Some(RuneErrorCode::FileRead.code()),
+            source: Some(e.into()),
         })?;
 
-        // Parse main doc (gather statements are parsed for alias discovery, but loading is done here)
-        let mut main_parser = parser::Parser::new(&content)?;
-        let main_doc = main_parser.parse_document()?;
+        // Parse main doc; the parser records gather statements, but loading is done here
+        let mut main_parser =
+            parser::Parser::with_context(&content, &context).map_err(|e| e.in_file(path))?;
+        let main_doc = main_parser.parse_document().map_err(|e| e.in_file(path))?;
 
         // Start documents with the main doc
         let mut documents = IndexMap::new();
         let main_key = "main".to_string();
         documents.insert(main_key.clone(), main_doc);
 
-        // Parse gather specs (alias + path + whether alias was explicit)
-        let gather_specs = helpers::parse_gather_specs(&content);
-
-        // Prevent import cycles / repeated loads (by absolute import path string)
-        let mut visited: HashSet<String> = HashSet::new();
+        // Catches import cycles back to this file. A file gathered along two paths is
+        // still read once per path.
+        let mut imports = ImportState::new(path);
 
         // Load each gathered file, recursively resolving nested gathers
-        for spec in gather_specs.iter() {
-            let import_path = resolve_gather_path(&spec.raw_path, base_dir.as_ref())?;
-
-            // Keep existing behavior: silently skip missing imports
-            if !import_path.exists() {
+        for spec in &main_parser.gather_specs {
+            let import_path = resolve_path(&spec.raw_path, base_dir)?;
+            if spec.optional && !import_path.exists() {
                 continue;
             }
 
             // Load under its alias (overwrites placeholder)
-            load_import_recursive(&mut documents, &spec.alias, &import_path, &mut visited)?;
+            load_import_recursive(
+                &mut documents,
+                &spec.alias,
+                &import_path,
+                &mut imports,
+                &context,
+            )?;
+
+            // `as *`: hoist the imported keys into the main doc, local keys winning.
+            if spec.alias == parser::WILDCARD_ALIAS {
+                if let (Some(import_doc), Some(main_doc_mut)) = (
+                    documents.shift_remove(&spec.alias),
+                    documents.get_mut(&main_key),
+                ) {
+                    hoist_into_document(main_doc_mut, &import_doc);
+                }
+                continue;
+            }
 
             // If no explicit `as`, treat as include: merge into main doc too.
             if !spec.explicit_alias {
@@ -129,27 +195,187 @@ impl RuneConfig {
             }
         }
 
+        // Block-scoped gathers are always namespaced under their qualified alias.
+        for spec in &main_parser.scoped_imports {
+            let import_path = resolve_path(&spec.raw_path, base_dir)?;
+            if spec.optional && !import_path.exists() {
+                continue;
+            }
+            load_import_recursive(
+                &mut documents,
+                &spec.alias,
+                &import_path,
+                &mut imports,
+                &context,
+            )?;
+        }
+
         Ok(Self {
             documents,
             main_doc_key: main_key,
             raw_content: content,
+            path: Some(path.to_path_buf()),
+            base_dir: base_dir.to_path_buf(),
+            context,
+            #[cfg(feature = "tokio")]
+            async_providers: IndexMap::new(),
+            resolution_mode: ResolutionMode::Lazy,
+            resolved: OnceCell::new(),
         })
     }
 
     /// Parse a RUNE config from a string (no file I/O, no import resolution)
     pub fn from_str(content: &str) -> Result<Self, RuneError> {
-        let mut parser = parser::Parser::new(content)?;
+        Self::from_str_with_context(content, ResolverContext::new())
+    }
+
+    /// Parse a RUNE config read to the end from `reader`, e.g. stdin (no import
+    /// resolution, like `from_str`).
+    ///
+    /// # Example
+    /// ```
+    /// # use rune_cfg::RuneConfig;
+    /// let config = RuneConfig::from_reader(std::io::Cursor::new("port 8080\n")).unwrap();
+    /// assert_eq!(config.get::<u16>("port").unwrap(), 8080);
+    /// ```
+    pub fn from_reader<R: std::io::Read>(mut reader: R) -> Result<Self, RuneError> {
+        let mut bytes = Vec::new();
+        reader
+            .read_to_end(&mut bytes)
+            .map_err(|e| RuneError::FileError {
+                message: format!("Failed to read config: {}", e),
+                path: "<reader>".into(),
+                hint: Some("Check that the source is readable".into()),
+                code: Some(RuneErrorCode::FileRead.code()),
+                source: Some(e.into()),
+            })?;
+        Self::from_bytes(&bytes)
+    }
+
+    /// Parse a RUNE config from bytes, e.g. an embedded resource, which must be UTF-8.
+    pub fn from_bytes(bytes: &[u8]) -> Result<Self, RuneError> {
+        let content = std::str::from_utf8(bytes).map_err(|e| RuneError::FileError {
+            message: format!(
+                "Config is not valid UTF-8: invalid byte at offset {}",
+                e.valid_up_to()
+            ),
+            path: "<bytes>".into(),
+            hint: Some("Save the config as UTF-8".into()),
+            code: Some(RuneErrorCode::InvalidUtf8.code()),
+            source: None,
+        })?;
+        Self::from_str(content)
+    }
+
+    /// Like `from_str`, resolving `$env`, `$sys`, `$runtime` and providers through `context`.
+    pub fn from_str_with_context(
+        content: &str,
+        context: ResolverContext,
+    ) -> Result<Self, RuneError> {
+        let mut parser = parser::Parser::with_context(content, &context)?;
         let main_doc = parser.parse_document()?;
+        drop(parser);
 
+        Ok(Self {
+            raw_content: content.to_string(),
+            ..Self::from_document(main_doc, context)
+        })
+    }
+
+    /// A config of one already-parsed document, with no source text or imports.
+    pub(crate) fn from_document(main_doc: Document, context: ResolverContext) -> Self {
         let mut documents = IndexMap::new();
         let main_key = "main".to_string();
         documents.insert(main_key.clone(), main_doc);
 
-        Ok(Self {
+        Self {
             documents,
             main_doc_key: main_key,
-            raw_content: content.to_string(),
-        })
+            raw_content: String::new(),
+            path: None,
+            base_dir: PathBuf::from("."),
+            context,
+            #[cfg(feature = "tokio")]
+            async_providers: IndexMap::new(),
+            resolution_mode: ResolutionMode::Lazy,
+            resolved: OnceCell::new(),
+        }
+    }
+
+    /// Register a value for `$runtime.<key>` references, resolved at `get` time.
+    ///
+    /// # Example
+    /// ```
+    /// # use rune_cfg::RuneConfig;
+    /// let mut config = RuneConfig::from_str("app:\n  build $runtime.build_id\nend\n").unwrap();
+    /// config.set_runtime("build_id", "abc123");
+    /// assert_eq!(config.get::<String>("app.build").unwrap(), "abc123");
+    /// ```
+    pub fn set_runtime(&mut self, key: impl Into<String>, value: impl Into<Value>) {
+        self.context.set_runtime(key, value);
+        self.resolved = OnceCell::new();
+    }
+
+    /// Register a provider that resolves `$<namespace>.<path>` references at `get` time.
+    ///
+    /// Providers are consulted for references whose first segment is `namespace`; a later
+    /// registration for the same namespace replaces the earlier one.
+    pub fn register_provider(
+        &mut self,
+        namespace: impl Into<String>,
+        provider: impl ResolverProvider + 'static,
+    ) {
+        self.context.register_provider(namespace, provider);
+        self.resolved = OnceCell::new();
+    }
+
+    /// Like `register_provider`, with a closure resolving the path after the namespace.
+    ///
+    /// # Example
+    /// ```
+    /// # use rune_cfg::{RuneConfig, Value};
+    /// let mut config = RuneConfig::from_str("beta $flags.new_ui\n").unwrap();
+    /// config.register_namespace("flags", |path| Ok(Value::Bool(path[0] == "new_ui")));
+    /// assert!(config.get::<bool>("beta").unwrap());
+    /// ```
+    pub fn register_namespace<F>(&mut self, namespace: impl Into<String>, resolve: F)
+    where
+        F: Fn(&[String]) -> Result<Value, RuneError> + Send + Sync + 'static,
+    {
+        self.context.register_namespace(namespace, resolve);
+        self.resolved = OnceCell::new();
+    }
+
+    /// Switch between lazy and eager resolution.
+    ///
+    /// Switching to [`ResolutionMode::Eager`] resolves the whole config immediately and
+    /// returns the first error. Later changes such as `set_runtime` drop the cached result;
+    /// it is rebuilt on the next read.
+    ///
+    /// # Example
+    /// ```
+    /// # use rune_cfg::{ResolutionMode, RuneConfig};
+    /// let mut config = RuneConfig::from_str("port 8080\n").unwrap();
+    /// config.set_resolution_mode(ResolutionMode::Eager).unwrap();
+    /// assert_eq!(config.get::<u16>("port").unwrap(), 8080);
+    /// ```
+    pub fn set_resolution_mode(&mut self, mode: ResolutionMode) -> Result<(), RuneError> {
+        self.resolution_mode = mode;
+        self.resolved = OnceCell::new();
+        if mode == ResolutionMode::Eager {
+            let root = self.resolve_entries(None, None)?;
+            let _ = self.resolved.set(root);
+        }
+        Ok(())
+    }
+
+    pub fn resolution_mode(&self) -> ResolutionMode {
+        self.resolution_mode
+    }
+
+    /// The context `$` references are resolved against.
+    pub fn context(&self) -> &ResolverContext {
+        &self.context
     }
 
     pub fn document(&self) -> Option<&Document> {
@@ -162,6 +388,97 @@ impl RuneConfig {
 
     pub fn inject_import(&mut self, alias: String, document: Document) {
         self.documents.insert(alias, document);
+        self.resolved = OnceCell::new();
+    }
+
+    /// Layer `higher` over this config, e.g. a user config over the system one.
+    ///
+    /// The main documents are deep-merged with `Document::merge`, `higher` winning on
+    /// conflicts and keys only one side defines kept; imports with the same alias merge
+    /// the same way. Runtime values and providers are combined likewise. `$file` paths
+    /// stay relative to this config's directory, and the resolution mode is this one's.
+    ///
+    /// Error messages on the result carry no line numbers: a merged value may come from
+    /// either source, so there is no single file to point into.
+    ///
+    /// # Example
+    /// ```
+    /// # use rune_cfg::RuneConfig;
+    /// let system = RuneConfig::from_str("app:\n  port 80\n  host \"0.0.0.0\"\nend\n").unwrap();
+    /// let user = RuneConfig::from_str("app:\n  port 8080\nend\n").unwrap();
+    ///
+    /// let config = system.overlay(&user);
+    /// assert_eq!(config.get::<u16>("app.port").unwrap(), 8080);
+    /// assert_eq!(config.get::<String>("app.host").unwrap(), "0.0.0.0");
+    /// ```
+    pub fn overlay(&self, higher: &RuneConfig) -> RuneConfig {
+        self.overlay_with(higher, MergeStrategy::Replace)
+    }
+
+    fn overlay_with(&self, higher: &RuneConfig, strategy: MergeStrategy) -> RuneConfig {
+        let mut documents = self.documents.clone();
+        for (alias, doc) in &higher.documents {
+            // Both sides call their main document "main".
+            let alias = if *alias == higher.main_doc_key {
+                &self.main_doc_key
+            } else {
+                alias
+            };
+            let merged = match documents.get(alias) {
+                Some(lower) => lower.merge_with(doc, strategy),
+                None => doc.clone(),
+            };
+            documents.insert(alias.clone(), merged);
+        }
+
+        #[cfg(feature = "tokio")]
+        let mut async_providers = self.async_providers.clone();
+        #[cfg(feature = "tokio")]
+        async_providers.extend(higher.async_providers.clone());
+
+        RuneConfig {
+            documents,
+            main_doc_key: self.main_doc_key.clone(),
+            raw_content: String::new(),
+            path: None,
+            base_dir: self.base_dir.clone(),
+            context: self.context.overlay(&higher.context),
+            #[cfg(feature = "tokio")]
+            async_providers,
+            resolution_mode: self.resolution_mode,
+            resolved: OnceCell::new(),
+        }
+    }
+
+    /// Merge `other` into this config in place, `other` winning, as `overlay` does.
+    ///
+    /// Blocks merge key by key recursively, so overriding `server.port` keeps
+    /// `server.host`. Scalars and arrays from `other` replace this config's; use
+    /// `merge_with` and `MergeStrategy::Concat` to append arrays instead.
+    ///
+    /// # Example
+    /// ```
+    /// # use rune_cfg::RuneConfig;
+    /// let mut config = RuneConfig::from_str("server:\n  host \"0.0.0.0\"\n  port 80\nend\n").unwrap();
+    /// config.merge_from_str("server:\n  port 8080\nend\n").unwrap();
+    ///
+    /// assert_eq!(config.get::<u16>("server.port").unwrap(), 8080);
+    /// assert_eq!(config.get::<String>("server.host").unwrap(), "0.0.0.0");
+    /// ```
+    pub fn merge(&mut self, other: &RuneConfig) {
+        self.merge_with(other, MergeStrategy::Replace);
+    }
+
+    /// Like `merge`, combining arrays at the same key as `strategy` says.
+    pub fn merge_with(&mut self, other: &RuneConfig, strategy: MergeStrategy) {
+        *self = self.overlay_with(other, strategy);
+    }
+
+    /// Parse `content` with this config's context and merge it in, as `merge` does.
+    pub fn merge_from_str(&mut self, content: &str) -> Result<(), RuneError> {
+        let other = Self::from_str_with_context(content, self.context.clone())?;
+        self.merge(&other);
+        Ok(())
     }
 
     pub fn import_aliases(&self) -> Vec<String> {
@@ -179,6 +496,31 @@ impl RuneConfig {
     pub fn get_document(&self, name: &str) -> Option<&Document> {
         self.documents.get(name)
     }
+
+    /// The file the config was loaded from; `None` for configs parsed from a string.
+    pub fn path(&self) -> Option<&Path> {
+        self.path.as_deref()
+    }
+
+    /// `error`, from reading this config, with the config's source so it renders with
+    /// the offending line.
+    ///
+    /// # Example
+    /// ```
+    /// # use rune_cfg::RuneConfig;
+    /// let config = RuneConfig::from_str("port \"eighty\"\n").unwrap();
+    /// if let Err(e) = config.get::<u16>("port") {
+    ///     eprintln!("{:?}", miette::Report::new(config.report(e)));
+    /// }
+    /// ```
+    #[cfg(feature = "miette")]
+    pub fn report(&self, error: RuneError) -> crate::RuneReport {
+        let name = match &self.path {
+            Some(path) => path.display().to_string(),
+            None => "<config>".to_string(),
+        };
+        crate::RuneReport::new(error, name, self.raw_content.clone())
+    }
 }
 
 fn merge_overrides_into_document(target: &mut Document, overrides: &Document) {
@@ -186,138 +528,148 @@ fn merge_overrides_into_document(target: &mut Document, overrides: &Document) {
     merge_named_values(&mut target.items, &overrides.items);
 }
 
-fn merge_named_values(target: &mut Vec<(String, Value)>, overrides: &[(String, Value)]) {
-    for (override_key, override_value) in overrides {
-        let Some((_, target_value)) = target.iter_mut().find(|(key, _)| key == override_key) else {
-            target.push((override_key.clone(), override_value.clone()));
-            continue;
-        };
+/// Add the globals and items of `imported` that `target` does not already define.
+///
+/// Unlike `merge_overrides_into_document`, existing keys are never touched, so a local
+/// definition shadows the hoisted one entirely.
+fn hoist_into_document(target: &mut Document, imported: &Document) {
+    let defined = |target: &Document, key: &str| {
+        target.globals.iter().any(|(k, _)| k == key) || target.items.iter().any(|(k, _)| k == key)
+    };
 
-        if let (Value::Object(target_items), Value::Object(override_items)) =
-            (&mut *target_value, override_value)
-        {
-            merge_object_items(target_items, override_items);
-        } else {
-            *target_value = override_value.clone();
+    for (key, value) in &imported.globals {
+        if !defined(target, key) {
+            target.globals.push((key.clone(), value.clone()));
         }
     }
-}
-
-fn merge_object_items(target: &mut Vec<ObjectItem>, overrides: &[ObjectItem]) {
-    for override_item in overrides {
-        let ObjectItem::Assign(override_key, override_value) = override_item else {
-            target.push(override_item.clone());
-            continue;
-        };
-
-        let Some(ObjectItem::Assign(_, target_value)) = target
-            .iter_mut()
-            .find(|item| matches!(item, ObjectItem::Assign(key, _) if key == override_key))
-        else {
-            target.push(override_item.clone());
-            continue;
-        };
-
-        if let (Value::Object(target_items), Value::Object(override_items)) =
-            (&mut *target_value, override_value)
-        {
-            merge_object_items(target_items, override_items);
-        } else {
-            *target_value = override_value.clone();
+    for (key, value) in &imported.items {
+        if !defined(target, key) {
+            target.items.push((key.clone(), value.clone()));
         }
     }
 }
 
-/// Expand "~/" and resolve relative paths against base_dir.
-fn resolve_gather_path(raw_path: &str, base_dir: &Path) -> Result<PathBuf, RuneError> {
-    let mut p = if let Some(rest) = raw_path.strip_prefix("~/") {
-        let home = home_dir_fallback().ok_or_else(|| RuneError::FileError {
-            message: "Could not determine home directory for ~ expansion".into(),
-            path: raw_path.to_string(),
-            hint: Some(
-                "Set HOME (or USERPROFILE on Windows) or use an absolute path in gather".into(),
-            ),
-            code: Some(300),
-        })?;
-        home.join(rest)
-    } else {
-        PathBuf::from(raw_path)
-    };
-
-    if p.is_relative() {
-        p = base_dir.join(p);
-    }
-    Ok(p)
+/// Files whose imports are being loaded, outermost first, by canonical path.
+struct ImportState {
+    stack: Vec<PathBuf>,
 }
 
-/// Best-effort home directory lookup without external crates.
-fn home_dir_fallback() -> Option<PathBuf> {
-    // Unix-like: HOME
-    if let Some(home) = std::env::var_os("HOME") {
-        if !home.is_empty() {
-            return Some(PathBuf::from(home));
-        }
-    }
-
-    // Windows: USERPROFILE, or HOMEDRIVE + HOMEPATH
-    if let Some(up) = std::env::var_os("USERPROFILE") {
-        if !up.is_empty() {
-            return Some(PathBuf::from(up));
+impl ImportState {
+    fn new(main_path: &Path) -> Self {
+        Self {
+            stack: vec![canonical_path(main_path)],
         }
     }
+}
 
-    let drive = std::env::var_os("HOMEDRIVE");
-    let path = std::env::var_os("HOMEPATH");
-    match (drive, path) {
-        (Some(d), Some(p)) if !d.is_empty() && !p.is_empty() => {
-            Some(PathBuf::from(d).join(PathBuf::from(p)))
-        }
-        _ => None,
-    }
+fn canonical_path(path: &Path) -> PathBuf {
+    path.canonicalize().unwrap_or_else(|_| path.to_path_buf())
 }
 
 /// Load an import file, parse its doc, inject into `documents` under `alias`,
 /// then recursively load that file’s gathers.
 ///
-/// NOTE: this always loads nested gathers as *namespaced imports* (per their alias).
-/// Include/merge semantics are handled only at the top-level loader.
+/// NOTE: nested gathers are namespaced under `alias` (`defaults.base`), and also
+/// available by their own alias unless another import already has it. A nested
+/// `as *` gather is hoisted into the importing file; other include/merge semantics
+/// are handled only at the top-level loader.
 fn load_import_recursive(
     documents: &mut IndexMap<String, Document>,
     alias: &str,
     import_path: &Path,
-    visited: &mut std::collections::HashSet<String>,
+    imports: &mut ImportState,
+    context: &ResolverContext,
 ) -> Result<(), RuneError> {
-    let key = import_path.to_string_lossy().to_string();
-    if visited.contains(&key) {
-        return Ok(());
+    let canonical = canonical_path(import_path);
+    if let Some(start) = imports.stack.iter().position(|p| *p == canonical) {
+        let chain: Vec<String> = imports.stack[start..]
+            .iter()
+            .chain([&canonical])
+            .map(|p| p.display().to_string())
+            .collect();
+        return Err(RuneError::FileError {
+            message: format!("Import cycle: {}", chain.join(" -> ")),
+            path: import_path.to_string_lossy().to_string(),
+            hint: Some("Remove one of the gather statements in the loop".into()),
+            code: Some(RuneErrorCode::ImportCycle.code()),
+            source: None,
+        });
     }
-    visited.insert(key);
 
+    imports.stack.push(canonical);
+    let result = load_import_file(documents, alias, import_path, imports, context);
+    imports.stack.pop();
+    result
+}
+
+fn load_import_file(
+    documents: &mut IndexMap<String, Document>,
+    alias: &str,
+    import_path: &Path,
+    imports: &mut ImportState,
+    context: &ResolverContext,
+) -> Result<(), RuneError> {
     let import_content = fs::read_to_string(import_path).map_err(|e| RuneError::FileError {
         message: format!("Failed to read import file: {}", e),
         path: import_path.to_string_lossy().to_string(),
-        hint: Some("Check that the imported file exists".into()),
-        code: Some(302),
+        hint: Some(
+            "Check that the imported file exists, or use `gather optional` if it may be missing"
+                .into(),
+        ),
+        code: Some(RuneErrorCode::ImportRead.code()),
+        source: Some(e.into()),
     })?;
 
-    let mut import_parser = parser::Parser::new(&import_content)?;
-    let import_doc = import_parser.parse_document()?;
+    let mut import_parser = parser::Parser::with_context(&import_content, context)
+        .map_err(|e| e.in_file(import_path))?;
+    let import_doc = import_parser
+        .parse_document()
+        .map_err(|e| e.in_file(import_path))?;
 
     // Overwrite any placeholder and/or previous doc with the real parsed doc
     documents.insert(alias.to_string(), import_doc);
 
     // Recurse into nested gathers
-    let nested_specs = helpers::parse_gather_specs(&import_content);
     let nested_base = import_path.parent().unwrap_or_else(|| Path::new("."));
 
-    for spec in nested_specs.iter() {
-        let nested_path = resolve_gather_path(&spec.raw_path, nested_base)?;
-        if !nested_path.exists() {
+    for spec in &import_parser.gather_specs {
+        let nested_path = resolve_path(&spec.raw_path, nested_base)?;
+        if spec.optional && !nested_path.exists() {
             continue;
         }
 
-        // Nested gathers: keep them as namespaced imports only.
-        load_import_recursive(documents, &spec.alias, &nested_path, visited)?;
+        // `as *`: hoist into the importing document, as the top level does. It is
+        // loaded under its own key first, since `*` may be the importing file's.
+        if spec.alias == parser::WILDCARD_ALIAS {
+            let hoisted = format!("{}.{}", alias, spec.alias);
+            load_import_recursive(documents, &hoisted, &nested_path, imports, context)?;
+            if let (Some(nested_doc), Some(importing_doc)) =
+                (documents.shift_remove(&hoisted), documents.get_mut(alias))
+            {
+                hoist_into_document(importing_doc, &nested_doc);
+            }
+            continue;
+        }
+
+        // Nested gathers are namespaced under the importing alias (`defaults.base.x`).
+        // The bare alias is added too, so references inside the importing file resolve,
+        // but never over another import of that name, and the main file's own gather of
+        // that name replaces it.
+        let namespaced = format!("{}.{}", alias, spec.alias);
+        load_import_recursive(documents, &namespaced, &nested_path, imports, context)?;
+        if !documents.contains_key(&spec.alias)
+            && let Some(doc) = documents.get(&namespaced).cloned()
+        {
+            documents.insert(spec.alias.clone(), doc);
+        }
+    }
+
+    for spec in &import_parser.scoped_imports {
+        let nested_path = resolve_path(&spec.raw_path, nested_base)?;
+        if spec.optional && !nested_path.exists() {
+            continue;
+        }
+        load_import_recursive(documents, &spec.alias, &nested_path, imports, context)?;
     }
 
     Ok(())
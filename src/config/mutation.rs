@@ -0,0 +1,275 @@
+// Author: Dustin Pilgrim
+// License: MIT
+
+use regex::Regex;
+
+use super::*;
+use crate::ast::{EntryKind, ObjectItem};
+
+impl RuneConfig {
+    /// Set the value at `path` in the main document, creating missing blocks on the way.
+    ///
+    /// A new top-level key becomes a block if `value` is an object and a global
+    /// otherwise; new nested keys are appended to their block. Numeric segments index
+    /// existing array elements. Going through a value that is neither a block nor an
+    /// array, or past the end of an array, is an error and changes nothing.
+    ///
+    /// The original source text is not rewritten, so errors about keys set this way
+    /// carry no line number, while keys from the file keep theirs.
+    ///
+    /// # Example
+    /// ```
+    /// # use rune_cfg::RuneConfig;
+    /// let mut config = RuneConfig::from_str("app:\n  port 8080\nend\n").unwrap();
+    /// config.set("app.port", 9090).unwrap();
+    /// config.set("app.tls.enabled", true).unwrap();
+    ///
+    /// assert_eq!(config.get::<u16>("app.port").unwrap(), 9090);
+    /// assert!(config.get::<bool>("app.tls.enabled").unwrap());
+    /// ```
+    pub fn set<T: Into<Value>>(&mut self, path: &str, value: T) -> Result<(), RuneError> {
+        self.set_value(path, value.into())
+    }
+
+    /// Like `set`, taking a `Value` as is, e.g. one built with `ObjectBuilder` or read
+    /// from another config.
+    pub fn set_value(&mut self, path: &str, value: Value) -> Result<(), RuneError> {
+        let segs = crate::utils::split_path(path);
+        let doc = self
+            .documents
+            .get_mut(&self.main_doc_key)
+            .expect("main document is always present");
+        set_in_document(doc, path, &segs, value)?;
+        self.resolved = OnceCell::new();
+        Ok(())
+    }
+
+    /// Override values from environment variables named `<PREFIX>_<PATH>`, where `__`
+    /// separates path segments: `MYAPP_APP__PORT=9090` sets `app.port`.
+    ///
+    /// Segments match keys case-insensitively, and `_` matches `-`, so
+    /// `MYAPP_LOG_LEVEL` also sets `log-level`. Each string is converted to the type of
+    /// the value it replaces; a value that does not convert is an error. Variables whose
+    /// path is not in the config are ignored; see `apply_env_overrides_strict`.
+    ///
+    /// Variables are read through the config's [`ResolverContext`], and returns how many
+    /// values were overridden.
+    ///
+    /// # Example
+    /// ```
+    /// # use rune_cfg::{ResolverContext, RuneConfig};
+    /// let context = ResolverContext::with_env([("MYAPP_APP__PORT", "9090")]);
+    /// let mut config = RuneConfig::from_str_with_context("app:\n  port 8080\nend\n", context).unwrap();
+    ///
+    /// config.apply_env_overrides("MYAPP").unwrap();
+    /// assert_eq!(config.get::<u16>("app.port").unwrap(), 9090);
+    /// ```
+    pub fn apply_env_overrides(&mut self, prefix: &str) -> Result<usize, RuneError> {
+        self.apply_env_overrides_with(prefix, false)
+    }
+
+    /// Like `apply_env_overrides`, but a variable whose path is not in the config is an
+    /// error, which catches typos in variable names.
+    pub fn apply_env_overrides_strict(&mut self, prefix: &str) -> Result<usize, RuneError> {
+        self.apply_env_overrides_with(prefix, true)
+    }
+
+    fn apply_env_overrides_with(&mut self, prefix: &str, strict: bool) -> Result<usize, RuneError> {
+        let var_prefix = format!("{}_", prefix);
+        let mut overrides: Vec<(String, String)> = self
+            .context
+            .env_vars()
+            .into_iter()
+            .filter(|(name, _)| name.starts_with(&var_prefix) && name.len() > var_prefix.len())
+            .collect();
+        // Apply in a fixed order so results never depend on the environment's order.
+        overrides.sort();
+
+        let mut applied = 0;
+        for (name, raw) in overrides {
+            let segs: Vec<String> = name[var_prefix.len()..]
+                .split("__")
+                .map(str::to_string)
+                .collect();
+
+            let Some(target) = self
+                .documents
+                .get_mut(&self.main_doc_key)
+                .and_then(|doc| document_entry_mut(doc, &segs, env_key_matches))
+            else {
+                if strict {
+                    return Err(RuneError::RuntimeError {
+                        message: format!(
+                            "{} does not match a key in the configuration (path '{}')",
+                            name,
+                            segs.join(".").to_lowercase()
+                        ),
+                        hint: Some("Separate nested keys with `__`, e.g. MYAPP_APP__PORT".into()),
+                        code: Some(RuneErrorCode::UnknownEnvOverride.code()),
+                    });
+                }
+                continue;
+            };
+
+            *target = coerce_like(target, &raw).map_err(|expected| RuneError::TypeError {
+                message: format!("{}='{}' is not a valid {}", name, raw, expected),
+                line: 0,
+                column: 0,
+                hint: Some(format!("The value it overrides is a {}", expected)),
+                code: Some(RuneErrorCode::InvalidEnvOverride.code()),
+            })?;
+            applied += 1;
+        }
+
+        if applied > 0 {
+            self.resolved = OnceCell::new();
+        }
+        Ok(applied)
+    }
+}
+
+/// An environment segment such as `LOG_LEVEL` against a key such as `log-level`.
+fn env_key_matches(key: &str, seg: &str) -> bool {
+    key.len() == seg.len()
+        && key
+            .chars()
+            .zip(seg.chars())
+            .all(|(k, s)| k.eq_ignore_ascii_case(&s) || (k == '-' && s == '_'))
+}
+
+/// The value at `segs` in `doc`, found through globals and items, then object keys.
+/// Items inside `if` blocks are not reached.
+fn document_entry_mut<'d>(
+    doc: &'d mut Document,
+    segs: &[String],
+    matches: fn(&str, &str) -> bool,
+) -> Option<&'d mut Value> {
+    let (first, rest) = segs.split_first()?;
+    let mut cur = doc
+        .items
+        .iter_mut()
+        .chain(doc.globals.iter_mut())
+        .find(|(k, _)| matches(k, first))
+        .map(|(_, v)| v)?;
+
+    for seg in rest {
+        cur = match cur {
+            Value::Object(items) => items.iter_mut().find_map(|item| match item {
+                ObjectItem::Assign(k, v) if matches(k, seg) => Some(v),
+                _ => None,
+            })?,
+            Value::Array(values) => values.get_mut(seg.parse::<usize>().ok()?)?,
+            _ => return None,
+        };
+    }
+    Some(cur)
+}
+
+/// Store `value` at `segs`, creating blocks for missing keys; `path` is only used for errors.
+fn set_in_document(
+    doc: &mut Document,
+    path: &str,
+    segs: &[String],
+    value: Value,
+) -> Result<(), RuneError> {
+    let cannot_set = |reason: String| RuneError::RuntimeError {
+        message: format!("Cannot set '{}': {}", path, reason),
+        hint: Some("Only blocks and existing array elements can be set into".into()),
+        code: Some(RuneErrorCode::SetFailed.code()),
+    };
+
+    let Some((first, rest)) = segs.split_first() else {
+        return Err(cannot_set("the path is empty".into()));
+    };
+
+    let existing = doc
+        .items
+        .iter_mut()
+        .chain(doc.globals.iter_mut())
+        .find(|(k, _)| k == first)
+        .map(|(_, v)| v);
+    let Some(mut cur) = existing else {
+        let value = nest(rest, value);
+        let (kind, list) = if matches!(value, Value::Object(_)) {
+            (EntryKind::Item, &mut doc.items)
+        } else {
+            (EntryKind::Global, &mut doc.globals)
+        };
+        doc.order.push((kind, list.len()));
+        list.push((first.clone(), value));
+        return Ok(());
+    };
+
+    for (i, seg) in rest.iter().enumerate() {
+        // `segs[..=i]` is the value being stepped through.
+        let parent = || segs[..=i].join(".");
+        cur = match cur {
+            Value::Object(items) => {
+                let found = items
+                    .iter()
+                    .position(|item| matches!(item, ObjectItem::Assign(k, _) if k == seg));
+                match found {
+                    Some(index) => match &mut items[index] {
+                        ObjectItem::Assign(_, v) => v,
+                        ObjectItem::IfBlock(_) => unreachable!("position matched an assignment"),
+                    },
+                    None => {
+                        items.push(ObjectItem::Assign(seg.clone(), nest(&rest[i + 1..], value)));
+                        return Ok(());
+                    }
+                }
+            }
+            Value::Array(values) => {
+                let len = values.len();
+                seg.parse::<usize>()
+                    .ok()
+                    .and_then(|index| values.get_mut(index))
+                    .ok_or_else(|| {
+                        cannot_set(format!(
+                            "'{}' is an array of {} element(s) and '{}' is not one of its indices",
+                            parent(),
+                            len,
+                            seg
+                        ))
+                    })?
+            }
+            _ => {
+                return Err(cannot_set(format!("'{}' is not a block", parent())));
+            }
+        };
+    }
+
+    *cur = value;
+    Ok(())
+}
+
+/// `value` wrapped in one block per segment: `["a", "b"]` gives `a: b value end end`.
+fn nest(segs: &[String], value: Value) -> Value {
+    segs.iter().rev().fold(value, |inner, key| {
+        Value::Object(vec![ObjectItem::Assign(key.clone(), inner)])
+    })
+}
+
+/// `raw` converted to the type of `existing`, or the name of that type when it does
+/// not convert. Arrays take comma-separated items, typed like the first element.
+fn coerce_like(existing: &Value, raw: &str) -> Result<Value, &'static str> {
+    let text = raw.trim();
+    match existing {
+        Value::Int(_) => text.parse().map(Value::Int).map_err(|_| "integer"),
+        Value::Float(_) => text.parse().map(Value::Float).map_err(|_| "number"),
+        Value::Bool(_) if text.eq_ignore_ascii_case("true") => Ok(Value::Bool(true)),
+        Value::Bool(_) if text.eq_ignore_ascii_case("false") => Ok(Value::Bool(false)),
+        Value::Bool(_) => Err("boolean (true or false)"),
+        Value::Regex(_) => Regex::new(raw).map(Value::Regex).map_err(|_| "regex"),
+        Value::Array(values) => {
+            let element = values.first().cloned().unwrap_or(Value::Null);
+            text.split(',')
+                .filter(|item| !item.trim().is_empty())
+                .map(|item| coerce_like(&element, item.trim()))
+                .collect::<Result<_, _>>()
+                .map(Value::Array)
+        }
+        Value::Object(_) => Err("block, which cannot be replaced by a single value"),
+        _ => Ok(Value::String(raw.to_string())),
+    }
+}
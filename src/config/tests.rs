@@ -73,6 +73,95 @@ end
     assert!(invalid.is_err());
 }
 
+#[test]
+fn test_range_validation() {
+    let config_content = r#"
+app:
+  server:
+    port 8080
+    low 0
+    high 70000
+  end
+end
+"#;
+    let config = RuneConfig::from_str(config_content).expect("Failed to parse config");
+
+    assert_eq!(
+        config.get_in_range("app.server.port", 1u32, 65535).unwrap(),
+        8080
+    );
+
+    let below = config.get_in_range("app.server.low", 1u32, 65535);
+    assert!(matches!(
+        below,
+        Err(RuneError::ValidationError { ref message, line: 5, code: Some(452), .. })
+            if message.contains("1 to 65535")
+    ));
+
+    let above = config.get_in_range("app.server.high", 1u32, 65535);
+    assert!(matches!(
+        above,
+        Err(RuneError::ValidationError {
+            line: 6,
+            code: Some(452),
+            ..
+        })
+    ));
+
+    // Values that do not convert fail before the range is checked.
+    assert!(matches!(
+        config.get_in_range("app.server.high", 1u16, 1000),
+        Err(RuneError::TypeError { .. })
+    ));
+    assert!(config.get_in_range("app.server.port", 0.5, 8080.0).is_ok());
+}
+
+#[test]
+fn test_pattern_validation() {
+    let config_content = r#"
+app:
+  version "1.4.2"
+  tag "latest"
+  pattern r"^v\d+$"
+  loose "^[a-z]+$"
+  broken "([a-z"
+end
+"#;
+    let config = RuneConfig::from_str(config_content).expect("Failed to parse config");
+
+    assert_eq!(
+        config
+            .get_matching("app.version", r"^\d+\.\d+\.\d+$")
+            .unwrap(),
+        "1.4.2"
+    );
+    assert!(matches!(
+        config.get_matching("app.tag", r"^\d+\.\d+\.\d+$"),
+        Err(RuneError::ValidationError { ref message, line: 4, code: Some(453), .. })
+            if message.contains(r"^\d+\.\d+\.\d+$")
+    ));
+    assert!(matches!(
+        config.get_matching("app.version", "([0-9"),
+        Err(RuneError::RuntimeError {
+            code: Some(418),
+            ..
+        })
+    ));
+
+    // Configured patterns read as compiled regexes, from literals or strings.
+    let pattern: regex::Regex = config.get("app.pattern").unwrap();
+    assert!(pattern.is_match("v12"));
+    let loose: regex::Regex = config.get("app.loose").unwrap();
+    assert!(loose.is_match("abc"));
+    assert!(matches!(
+        config.get::<regex::Regex>("app.broken"),
+        Err(RuneError::TypeError {
+            code: Some(418),
+            ..
+        })
+    ));
+}
+
 #[test]
 fn test_order_preservation() {
     let config_content = r#"
@@ -248,7 +337,7 @@ fn test_string_conversion() {
 
 #[test]
 fn test_string_conversion_error() {
-    let value = Value::Number(42.0);
+    let value = Value::Int(42);
     let result: Result<String, RuneError> = value.try_into();
     assert!(result.is_err());
 }
@@ -257,7 +346,7 @@ fn test_string_conversion_error() {
 
 #[test]
 fn test_f64_conversion() {
-    let value = Value::Number(3.14);
+    let value = Value::Float(3.14);
     let result: Result<f64, RuneError> = value.try_into();
     assert!(result.is_ok());
     assert_eq!(result.unwrap(), 3.14);
@@ -265,7 +354,7 @@ fn test_f64_conversion() {
 
 #[test]
 fn test_f32_conversion() {
-    let value = Value::Number(2.5);
+    let value = Value::Float(2.5);
     let result: Result<f32, RuneError> = value.try_into();
     assert!(result.is_ok());
     assert_eq!(result.unwrap(), 2.5_f32);
@@ -273,7 +362,7 @@ fn test_f32_conversion() {
 
 #[test]
 fn test_i32_conversion() {
-    let value = Value::Number(42.0);
+    let value = Value::Int(42);
     let result: Result<i32, RuneError> = value.try_into();
     assert!(result.is_ok());
     assert_eq!(result.unwrap(), 42);
@@ -281,7 +370,7 @@ fn test_i32_conversion() {
 
 #[test]
 fn test_i64_conversion() {
-    let value = Value::Number(1234567890.0);
+    let value = Value::Int(1234567890);
     let result: Result<i64, RuneError> = value.try_into();
     assert!(result.is_ok());
     assert_eq!(result.unwrap(), 1234567890);
@@ -289,7 +378,7 @@ fn test_i64_conversion() {
 
 #[test]
 fn test_u8_conversion() {
-    let value = Value::Number(255.0);
+    let value = Value::Int(255);
     let result: Result<u8, RuneError> = value.try_into();
     assert!(result.is_ok());
     assert_eq!(result.unwrap(), 255);
@@ -297,18 +386,18 @@ fn test_u8_conversion() {
 
 #[test]
 fn test_u8_conversion_out_of_range() {
-    let value = Value::Number(256.0);
+    let value = Value::Int(256);
     let result: Result<u8, RuneError> = value.try_into();
     assert!(result.is_err());
 
-    let value = Value::Number(-1.0);
+    let value = Value::Int(-1);
     let result: Result<u8, RuneError> = value.try_into();
     assert!(result.is_err());
 }
 
 #[test]
 fn test_u16_conversion() {
-    let value = Value::Number(65535.0);
+    let value = Value::Int(65535);
     let result: Result<u16, RuneError> = value.try_into();
     assert!(result.is_ok());
     assert_eq!(result.unwrap(), 65535);
@@ -316,14 +405,14 @@ fn test_u16_conversion() {
 
 #[test]
 fn test_u16_conversion_out_of_range() {
-    let value = Value::Number(65536.0);
+    let value = Value::Int(65536);
     let result: Result<u16, RuneError> = value.try_into();
     assert!(result.is_err());
 }
 
 #[test]
 fn test_u32_conversion() {
-    let value = Value::Number(4294967295.0);
+    let value = Value::Int(4294967295);
     let result: Result<u32, RuneError> = value.try_into();
     assert!(result.is_ok());
     assert_eq!(result.unwrap(), 4294967295);
@@ -331,20 +420,133 @@ fn test_u32_conversion() {
 
 #[test]
 fn test_u64_conversion() {
-    let value = Value::Number(123456789.0);
+    let value = Value::Int(123456789);
     let result: Result<u64, RuneError> = value.try_into();
     assert!(result.is_ok());
     assert_eq!(result.unwrap(), 123456789);
 }
 
+#[test]
+fn test_u64_beyond_i64_reads_from_a_quoted_literal() {
+    let config = RuneConfig::from_str("max \"18446744073709551615\"\n").unwrap();
+    assert_eq!(config.get::<u64>("max").unwrap(), u64::MAX);
+
+    match RuneConfig::from_str("max 18446744073709551615\n") {
+        Err(e) => assert_eq!(e.code(), Some(106)),
+        Ok(_) => panic!("an integer beyond i64 should not parse"),
+    }
+}
+
 #[test]
 fn test_usize_conversion() {
-    let value = Value::Number(1000.0);
+    let value = Value::Int(1000);
     let result: Result<usize, RuneError> = value.try_into();
     assert!(result.is_ok());
     assert_eq!(result.unwrap(), 1000);
 }
 
+#[test]
+fn test_small_signed_integer_boundaries() {
+    assert_eq!(i8::try_from(Value::Int(-128)).unwrap(), i8::MIN);
+    assert_eq!(i8::try_from(Value::Int(127)).unwrap(), i8::MAX);
+    assert_eq!(i16::try_from(Value::Int(-32768)).unwrap(), i16::MIN);
+    assert_eq!(i16::try_from(Value::Int(32767)).unwrap(), i16::MAX);
+    assert_eq!(isize::try_from(Value::Int(-1)).unwrap(), -1);
+    assert_eq!(i8::try_from(Value::String("-5".into())).unwrap(), -5);
+
+    for (value, result) in [
+        (
+            Value::Int(128),
+            i8::try_from(Value::Int(128)).map(i64::from),
+        ),
+        (
+            Value::Int(-129),
+            i8::try_from(Value::Int(-129)).map(i64::from),
+        ),
+        (
+            Value::Int(32768),
+            i16::try_from(Value::Int(32768)).map(i64::from),
+        ),
+        (
+            Value::Int(-32769),
+            i16::try_from(Value::Int(-32769)).map(i64::from),
+        ),
+    ] {
+        match result {
+            Err(RuneError::TypeError {
+                code: Some(407),
+                hint,
+                ..
+            }) => assert!(hint.unwrap().starts_with("Use a number between")),
+            other => panic!(
+                "expected an out-of-range error for {:?}, got {:?}",
+                value, other
+            ),
+        }
+    }
+}
+
+#[test]
+fn test_f32_conversion_range() {
+    assert_eq!(f32::try_from(Value::Float(1.5)).unwrap(), 1.5);
+    assert_eq!(f32::try_from(Value::Int(-3)).unwrap(), -3.0);
+    assert_eq!(
+        f32::try_from(Value::Float(f64::from(f32::MAX))).unwrap(),
+        f32::MAX
+    );
+    assert!(matches!(
+        f32::try_from(Value::Float(1e39)),
+        Err(RuneError::TypeError {
+            code: Some(407),
+            ..
+        })
+    ));
+}
+
+// ===== Duration Conversion Tests =====
+
+#[test]
+fn test_duration_conversion() {
+    use std::time::Duration;
+
+    for (text, expected) in [
+        ("30s", Duration::from_secs(30)),
+        ("5m", Duration::from_secs(300)),
+        ("1h30m", Duration::from_secs(5400)),
+        ("1h 30m 15s", Duration::from_secs(5415)),
+        ("2d", Duration::from_secs(172_800)),
+        ("250ms", Duration::from_millis(250)),
+        ("1s500ms", Duration::from_millis(1500)),
+        ("1.5s", Duration::from_millis(1500)),
+    ] {
+        let result: Result<Duration, RuneError> = Value::String(text.into()).try_into();
+        assert_eq!(result.unwrap(), expected, "{}", text);
+    }
+}
+
+#[test]
+fn test_duration_conversion_errors() {
+    use std::time::Duration;
+
+    for value in [
+        Value::String("30x".into()),
+        Value::String("30".into()),
+        Value::String("".into()),
+        Value::String("ms".into()),
+        Value::Int(30),
+    ] {
+        let result: Result<Duration, RuneError> = value.clone().try_into();
+        match result {
+            Err(RuneError::TypeError {
+                code: Some(415),
+                hint,
+                ..
+            }) => assert!(hint.unwrap().contains("ms, s, m, h or d")),
+            other => panic!("expected a duration error for {:?}, got {:?}", value, other),
+        }
+    }
+}
+
 // ===== Boolean Conversion Tests =====
 
 #[test]
@@ -395,11 +597,7 @@ fn test_vec_string_conversion() {
 
 #[test]
 fn test_vec_number_conversion() {
-    let value = Value::Array(vec![
-        Value::Number(1.0),
-        Value::Number(2.0),
-        Value::Number(3.0),
-    ]);
+    let value = Value::Array(vec![Value::Int(1), Value::Int(2), Value::Int(3)]);
 
     let result: Result<Vec<i32>, RuneError> = value.try_into();
     assert!(result.is_ok());
@@ -421,7 +619,7 @@ fn test_vec_bool_conversion() {
 
 #[test]
 fn test_vec_mixed_types_error() {
-    let value = Value::Array(vec![Value::String("one".to_string()), Value::Number(2.0)]);
+    let value = Value::Array(vec![Value::String("one".to_string()), Value::Int(2)]);
 
     let result: Result<Vec<String>, RuneError> = value.try_into();
     assert!(result.is_err());
@@ -435,6 +633,52 @@ fn test_empty_vec_conversion() {
     assert_eq!(result.unwrap(), Vec::<String>::new());
 }
 
+// ===== Path and Address Conversion Tests =====
+
+#[test]
+fn test_pathbuf_conversion() {
+    let path = std::path::PathBuf::try_from(Value::String("/etc/app.rune".into())).unwrap();
+    assert_eq!(path, std::path::PathBuf::from("/etc/app.rune"));
+    assert!(std::path::PathBuf::try_from(Value::Int(1)).is_err());
+}
+
+#[test]
+fn test_address_conversion() {
+    use std::net::{IpAddr, Ipv4Addr, Ipv6Addr, SocketAddr};
+
+    let config = RuneConfig::from_str(
+        "server:\n  bind \"0.0.0.0:8080\"\n  host \"10.0.0.1\"\n  v6 \"::1\"\n  bad \"localhost:80\"\nend\n",
+    )
+    .unwrap();
+
+    let bind: SocketAddr = config.get("server.bind").unwrap();
+    assert_eq!(bind.port(), 8080);
+    assert_eq!(
+        config.get::<IpAddr>("server.host").unwrap(),
+        IpAddr::V4(Ipv4Addr::new(10, 0, 0, 1))
+    );
+    assert_eq!(
+        config.get::<Ipv6Addr>("server.v6").unwrap(),
+        Ipv6Addr::LOCALHOST
+    );
+
+    assert!(matches!(
+        config.get::<SocketAddr>("server.bad"),
+        Err(RuneError::TypeError {
+            code: Some(416),
+            ..
+        })
+    ));
+    assert!(config.get::<Ipv4Addr>("server.v6").is_err());
+    match Ipv4Addr::try_from(Value::String("300.1.1.1".into())) {
+        Err(RuneError::TypeError { message, .. }) => {
+            assert!(message.contains("Invalid IPv4 address '300.1.1.1'"))
+        }
+        other => panic!("expected a type error, got {:?}", other),
+    }
+    assert!(IpAddr::try_from(Value::Int(1)).is_err());
+}
+
 // ===== Option Conversion Tests =====
 
 #[test]
@@ -455,12 +699,28 @@ fn test_option_some_conversion() {
 
 #[test]
 fn test_option_number_conversion() {
-    let value = Value::Number(42.0);
+    let value = Value::Int(42);
     let result: Result<Option<i32>, RuneError> = value.try_into();
     assert!(result.is_ok());
     assert_eq!(result.unwrap(), Some(42));
 }
 
+#[test]
+fn test_get_option_through_config() {
+    let config =
+        RuneConfig::from_str("app:\n  port 8080\n  proxy-port null\n  weights [1 null 3]\nend\n")
+            .unwrap();
+
+    assert_eq!(config.get::<Option<u16>>("app.port").unwrap(), Some(8080));
+    assert_eq!(config.get::<Option<u16>>("app.proxy-port").unwrap(), None);
+    assert_eq!(
+        config.get::<Vec<Option<u8>>>("app.weights").unwrap(),
+        vec![Some(1), None, Some(3)]
+    );
+    // A present value that does not convert is still an error.
+    assert!(config.get::<Option<u8>>("app.port").is_err());
+}
+
 // ===== HashMap Conversion Tests =====
 //
 // NOTE:
@@ -472,7 +732,7 @@ fn test_option_number_conversion() {
 fn test_hashmap_value_conversion() {
     let value = Value::Object(vec![
         ObjectItem::Assign("key1".to_string(), Value::String("value1".to_string())),
-        ObjectItem::Assign("key2".to_string(), Value::Number(42.0)),
+        ObjectItem::Assign("key2".to_string(), Value::Int(42)),
     ]);
 
     let result: Result<HashMap<String, Value>, RuneError> = value.try_into();
@@ -503,7 +763,7 @@ fn test_hashmap_string_conversion() {
 fn test_hashmap_string_conversion_error() {
     let value = Value::Object(vec![
         ObjectItem::Assign("name".to_string(), Value::String("Alice".to_string())),
-        ObjectItem::Assign("age".to_string(), Value::Number(30.0)),
+        ObjectItem::Assign("age".to_string(), Value::Int(30)),
     ]);
 
     let result: Result<HashMap<String, String>, RuneError> = value.try_into();
@@ -526,16 +786,13 @@ fn test_tuple_string_string_conversion() {
 
 #[test]
 fn test_tuple_string_value_conversion() {
-    let value = Value::Array(vec![
-        Value::String("config".to_string()),
-        Value::Number(42.0),
-    ]);
+    let value = Value::Array(vec![Value::String("config".to_string()), Value::Int(42)]);
 
     let result: Result<(String, Value), RuneError> = value.try_into();
     assert!(result.is_ok());
     let (key, val) = result.unwrap();
     assert_eq!(key, "config");
-    assert_eq!(val, Value::Number(42.0));
+    assert_eq!(val, Value::Int(42));
 }
 
 #[test]
@@ -614,6 +871,163 @@ end
     assert!(large_u32.is_ok());
 }
 
+#[test]
+fn test_config_from_reader_and_bytes() {
+    let reader = std::io::Cursor::new("app:\n  port 8080\nend\n");
+    let config = RuneConfig::from_reader(reader).expect("Failed to read config");
+    assert_eq!(config.get::<u16>("app.port").unwrap(), 8080);
+
+    let config = RuneConfig::from_bytes(b"name \"demo\"\n").unwrap();
+    assert_eq!(config.get::<String>("name").unwrap(), "demo");
+
+    let result = RuneConfig::from_bytes(b"name \"\xff\xfe\"\n");
+    assert!(matches!(
+        result,
+        Err(RuneError::FileError { ref message, code: Some(319), .. })
+            if message.contains("UTF-8") && message.contains("offset 6")
+    ));
+}
+
+#[test]
+fn test_config_flatten() {
+    let config_content = r#"
+name "demo"
+app:
+  server:
+    host "localhost"
+    port 8080
+  end
+  features ["auth" "cache"]
+  owner name
+  extra:
+  end
+end
+"#;
+    let config = RuneConfig::from_str(config_content).unwrap();
+    let flat = config.flatten().unwrap();
+
+    assert_eq!(
+        flat.keys().collect::<Vec<_>>(),
+        [
+            "name",
+            "app.server.host",
+            "app.server.port",
+            "app.features.0",
+            "app.features.1",
+            "app.owner",
+            "app.extra",
+        ]
+    );
+    assert_eq!(flat["app.server.host"], Value::String("localhost".into()));
+    assert_eq!(flat["app.features.1"], Value::String("cache".into()));
+    // References are resolved.
+    assert_eq!(flat["app.owner"], Value::String("demo".into()));
+    assert_eq!(flat["app.extra"], Value::Object(Vec::new()));
+}
+
+#[test]
+fn test_config_all_paths() {
+    let config_content = r#"
+name "demo"
+app:
+  server:
+    host "localhost"
+    port 8080
+  end
+  if debug:
+    workers 1
+  else:
+    workers 8
+    profile "release"
+  endif
+  features ["auth" "cache"]
+end
+version "1.0"
+"#;
+    let config = RuneConfig::from_str(config_content).unwrap();
+
+    assert_eq!(
+        config.all_paths(),
+        [
+            "name",
+            "app.server.host",
+            "app.server.port",
+            "app.workers",
+            "app.profile",
+            "app.features",
+            "version",
+        ]
+    );
+}
+
+#[test]
+fn test_file_error_source_is_the_io_error() {
+    use std::error::Error;
+
+    let dir = tempfile::tempdir().expect("temp dir");
+    let err = match RuneConfig::from_file(dir.path().join("absent.rune")) {
+        Err(err) => err,
+        Ok(_) => panic!("expected a missing file to fail"),
+    };
+
+    let io = err
+        .source()
+        .and_then(|source| source.downcast_ref::<std::io::Error>())
+        .expect("the IO error as source");
+    assert_eq!(io.kind(), std::io::ErrorKind::NotFound);
+    assert_eq!(err.error_code(), Some(crate::RuneErrorCode::FileRead));
+    assert!(err.to_string().starts_with("[RUNE] File Error '"));
+
+    // Errors not caused by IO have no source.
+    let config = RuneConfig::from_str("port 8080\n").unwrap();
+    assert!(config.get::<u16>("host").unwrap_err().source().is_none());
+}
+
+#[test]
+fn test_error_codes_round_trip() {
+    use crate::RuneErrorCode;
+
+    assert_eq!(RuneErrorCode::UnclosedString.code(), 103);
+    assert_eq!(
+        RuneErrorCode::from_code(212),
+        Some(RuneErrorCode::UnknownSysKey)
+    );
+    assert_eq!(
+        RuneErrorCode::from_code(304),
+        Some(RuneErrorCode::PathNotFound)
+    );
+    assert_eq!(RuneErrorCode::from_code(999), None);
+
+    let err = RuneConfig::from_str("name \"open\n")
+        .err()
+        .expect("an unclosed string");
+    assert_eq!(err.code(), Some(103));
+    assert_eq!(err.error_code(), Some(RuneErrorCode::UnclosedString));
+}
+
+#[test]
+fn test_distinct_parse_errors_have_distinct_codes() {
+    use crate::RuneErrorCode;
+
+    for (source, expected) in [
+        ("gather as base\n", RuneErrorCode::MissingGatherPath),
+        ("gather \"a.rune\" as\n", RuneErrorCode::InvalidGatherAlias),
+        (
+            "app:\n  gather \"a.rune\" as *\nend\n",
+            RuneErrorCode::ScopedWildcardGather,
+        ),
+        ("$env \"x\"\n", RuneErrorCode::DollarVariableAsKey),
+        ("pattern r\"(\"\n", RuneErrorCode::InvalidRegexLiteral),
+        ("a b.\n", RuneErrorCode::InvalidReference),
+        ("a $runtime.x:-1\n", RuneErrorCode::UnsupportedEnvModifier),
+    ] {
+        let err = RuneConfig::from_str(source)
+            .err()
+            .unwrap_or_else(|| panic!("{:?} should fail", source));
+        assert_eq!(err.error_code(), Some(expected), "{:?}: {}", source, err);
+    }
+}
+
 #[test]
 fn test_config_type_mismatch_errors() {
     let config_content = r#"
@@ -647,6 +1061,38 @@ end
     assert_eq!(flag, false);
 }
 
+#[test]
+fn test_config_conditional_values_pick_a_branch() {
+    let config_content = r#"
+mode "prod"
+debug true
+level if mode = "dev" "debug" else "info"
+verbose if mode = "prod" "yes" else "no"
+trace if debug "on" else "off"
+color if tty "always" else "never"
+cache if mode != "prod" "off" else "on"
+pager if not tty "less" else "none"
+app:
+  if debug:
+    workers 1
+  else:
+    workers 8
+  endif
+end
+"#;
+
+    let config = RuneConfig::from_str(config_content).expect("Failed to parse config");
+
+    assert_eq!(config.get::<String>("level").unwrap(), "info");
+    assert_eq!(config.get::<String>("verbose").unwrap(), "yes");
+    assert_eq!(config.get::<String>("trace").unwrap(), "on");
+    // `tty` is not set anywhere, so the `Exists` condition takes the else branch.
+    assert_eq!(config.get::<String>("color").unwrap(), "never");
+    assert_eq!(config.get::<String>("cache").unwrap(), "on");
+    assert_eq!(config.get::<String>("pager").unwrap(), "less");
+    assert_eq!(config.get::<u32>("app.workers").unwrap(), 1);
+}
+
 #[test]
 fn test_schema_validation_success() {
     let schema = SchemaDocument::from_str(
@@ -838,3 +1284,1639 @@ end
     assert_eq!(diagnostics.len(), 1);
     assert!(diagnostics[0].message.contains("between 1 and 65535"));
 }
+
+#[test]
+fn test_block_scoped_gather_is_only_visible_inside_block() {
+    let dir = tempfile::tempdir().expect("temp dir");
+    let theme_path = dir.path().join("theme.rune");
+    let config_path = dir.path().join("config.rune");
+
+    std::fs::write(
+        &theme_path,
+        r##"
+colors:
+  accent "#d65d26"
+end
+"##,
+    )
+    .expect("write theme");
+    std::fs::write(
+        &config_path,
+        r##"
+theme:
+  gather "theme.rune" as t
+  accent t.colors.accent
+end
+
+leaked t.colors.accent
+"##,
+    )
+    .expect("write config");
+
+    let config = RuneConfig::from_file(&config_path).expect("config should parse");
+
+    assert_eq!(config.get::<String>("theme.accent").unwrap(), "#d65d26");
+    assert!(!config.import_aliases().contains(&"t".to_string()));
+    assert!(
+        config.get::<String>("leaked").is_err(),
+        "block-scoped alias must not resolve at the document root"
+    );
+}
+
+#[test]
+fn test_wildcard_gather_hoists_globals_with_local_precedence() {
+    let dir = tempfile::tempdir().expect("temp dir");
+    let common_path = dir.path().join("common.rune");
+    let config_path = dir.path().join("config.rune");
+
+    std::fs::write(
+        &common_path,
+        r#"
+region "eu-west"
+timeout 30
+logging:
+  level "info"
+end
+"#,
+    )
+    .expect("write common");
+    std::fs::write(
+        &config_path,
+        r#"
+gather "common.rune" as *
+
+timeout 60
+
+app:
+  region region
+  timeout timeout
+end
+"#,
+    )
+    .expect("write config");
+
+    let config = RuneConfig::from_file(&config_path).expect("config should parse");
+
+    assert_eq!(config.get::<String>("app.region").unwrap(), "eu-west");
+    assert_eq!(config.get::<String>("logging.level").unwrap(), "info");
+    assert_eq!(config.get::<u32>("app.timeout").unwrap(), 60);
+    assert_eq!(config.get::<u32>("timeout").unwrap(), 60);
+    assert!(config.import_aliases().is_empty());
+}
+
+#[test]
+fn test_nested_wildcard_gathers_hoist_every_level() {
+    let dir = tempfile::tempdir().expect("temp dir");
+    let config_path = dir.path().join("config.rune");
+
+    std::fs::write(dir.path().join("b.rune"), "from_b \"B\"\nshared \"b\"\n").expect("write b");
+    std::fs::write(
+        dir.path().join("a.rune"),
+        "gather \"b.rune\" as *\nfrom_a \"A\"\nshared \"a\"\n",
+    )
+    .expect("write a");
+    std::fs::write(&config_path, "gather \"a.rune\" as *\nport 80\n").expect("write config");
+
+    let config = RuneConfig::from_file(&config_path).expect("config should parse");
+
+    assert_eq!(config.get::<String>("from_a").unwrap(), "A");
+    assert_eq!(config.get::<String>("from_b").unwrap(), "B");
+    assert_eq!(config.get::<String>("shared").unwrap(), "a");
+    assert_eq!(config.get::<u16>("port").unwrap(), 80);
+}
+
+#[test]
+fn test_structured_metadata_block() {
+    let config_content = r#"
+@version "1.0.0"
+@author:
+  name "Dustin"
+  email "dustin@example.com"
+end
+
+app:
+  name "RuneApp"
+end
+"#;
+    let config = RuneConfig::from_str(config_content).expect("Failed to parse config");
+
+    let author = config.get_metadata("author").expect("author metadata");
+    let map = HashMap::<String, String>::try_from(author.clone()).expect("author object");
+    assert_eq!(map.get("name").map(String::as_str), Some("Dustin"));
+    assert_eq!(
+        map.get("email").map(String::as_str),
+        Some("dustin@example.com")
+    );
+
+    assert_eq!(
+        config.get_metadata("version"),
+        Some(&Value::String("1.0.0".into()))
+    );
+    assert!(config.get_metadata("missing").is_none());
+    assert_eq!(config.get::<String>("app.name").unwrap(), "RuneApp");
+}
+
+#[test]
+fn test_quoted_path_segments_address_dotted_keys() {
+    let config_content = r#"
+servers:
+  "east.1":
+    host "east-1.example.com"
+  end
+end
+
+primary servers."east.1".host
+"#;
+    let config = RuneConfig::from_str(config_content).expect("Failed to parse config");
+
+    let host: String = config
+        .get(r#"servers."east.1".host"#)
+        .expect("quoted segment should resolve");
+    assert_eq!(host, "east-1.example.com");
+
+    let primary: String = config.get("primary").expect("quoted reference");
+    assert_eq!(primary, "east-1.example.com");
+
+    assert!(!config.has("servers.east.1.host"));
+}
+
+#[test]
+fn test_validate_metadata_accepts_conforming_document() {
+    use crate::{MetadataSchema, SchemaType};
+
+    let config = RuneConfig::from_str(
+        r#"
+@version "1.0.0"
+@port 8080
+name "app"
+"#,
+    )
+    .expect("Failed to parse config");
+
+    let schema = MetadataSchema::new()
+        .required("version", SchemaType::String)
+        .optional("port", SchemaType::Number)
+        .optional("author", SchemaType::String);
+
+    assert!(config.validate_metadata(&schema).is_ok());
+}
+
+#[test]
+fn test_validate_metadata_reports_every_violation() {
+    use crate::{MetadataSchema, SchemaType};
+
+    let config = RuneConfig::from_str(
+        r#"
+@port "8080"
+name "app"
+"#,
+    )
+    .expect("Failed to parse config");
+
+    let schema = MetadataSchema::new()
+        .required("version", SchemaType::String)
+        .required("port", SchemaType::Number);
+
+    let errors = config
+        .validate_metadata(&schema)
+        .expect_err("metadata should be invalid");
+    assert_eq!(errors.len(), 2);
+    assert!(matches!(
+        &errors[0],
+        RuneError::ValidationError { code: Some(455), message, .. } if message.contains("@version")
+    ));
+    assert!(matches!(
+        &errors[1],
+        RuneError::ValidationError {
+            code: Some(456),
+            line: 2,
+            ..
+        }
+    ));
+}
+
+#[test]
+fn test_set_runtime_resolves_runtime_references() {
+    let mut config = RuneConfig::from_str(
+        "app:\n  build $runtime.build_id\n  banner \"build $runtime.build_id\"\n  workers $runtime.workers\nend\n",
+    )
+    .unwrap();
+    config.set_runtime("build_id", "abc123");
+    config.set_runtime("workers", 4);
+
+    assert_eq!(config.get::<String>("app.build").unwrap(), "abc123");
+    assert_eq!(config.get::<String>("app.banner").unwrap(), "build abc123");
+    assert_eq!(config.get::<u32>("app.workers").unwrap(), 4);
+
+    config.set_runtime("build_id", "def456");
+    assert_eq!(config.get::<String>("app.build").unwrap(), "def456");
+}
+
+#[test]
+fn test_runtime_values_resolve_through_block_references() {
+    let mut config = RuneConfig::from_str(
+        "session:\n  user $runtime.user\nend\naudit:\n  actor session.user\nend\ntrace:\n  request $runtime.request_id\nend\n",
+    )
+    .unwrap();
+    config.set_runtime("user", "dustin");
+
+    assert_eq!(config.get::<String>("audit.actor").unwrap(), "dustin");
+    match config.get::<String>("trace.request") {
+        Err(RuneError::RuntimeError {
+            message,
+            code: Some(311),
+            ..
+        }) => assert!(message.contains("request_id")),
+        other => panic!("expected an unset runtime value error, got {:?}", other),
+    }
+}
+
+#[test]
+fn test_custom_namespace_provider_resolves_references() {
+    use crate::resolver::ResolverProvider;
+
+    struct Vault;
+
+    impl ResolverProvider for Vault {
+        fn resolve(&self, path: &[String]) -> Result<Value, RuneError> {
+            match path.join(".").as_str() {
+                "db.password" => Ok(Value::String("hunter2".into())),
+                other => Err(RuneError::RuntimeError {
+                    message: format!("No secret named '{}'", other),
+                    hint: None,
+                    code: Some(309),
+                }),
+            }
+        }
+    }
+
+    let mut config = RuneConfig::from_str(
+        "db:\n  password $vault.db.password\n  dsn \"user:$vault.db.password@localhost\"\nend\n",
+    )
+    .unwrap();
+    config.register_provider("vault", Vault);
+
+    assert_eq!(config.get::<String>("db.password").unwrap(), "hunter2");
+    assert_eq!(
+        config.get::<String>("db.dsn").unwrap(),
+        "user:hunter2@localhost"
+    );
+
+    let mut missing = RuneConfig::from_str("key $vault.nope\n").unwrap();
+    missing.register_provider("vault", Vault);
+    assert!(matches!(
+        missing.get_value("key"),
+        Err(RuneError::RuntimeError {
+            code: Some(309),
+            ..
+        })
+    ));
+}
+
+#[test]
+fn test_file_namespace_inlines_file_contents() {
+    let dir = tempfile::tempdir().expect("temp dir");
+    let cert_path = dir.path().join("cert.pem");
+    let config_path = dir.path().join("config.rune");
+
+    std::fs::write(&cert_path, "-----BEGIN CERTIFICATE-----\n").expect("write cert");
+    std::fs::write(
+        &config_path,
+        format!(
+            "tls:\n  cert $file.cert.pem\n  absolute $file.\"{}\"\nend\n",
+            cert_path.display()
+        ),
+    )
+    .expect("write config");
+
+    let config = RuneConfig::from_file(&config_path).expect("load config");
+    assert_eq!(
+        config.get::<String>("tls.cert").unwrap(),
+        "-----BEGIN CERTIFICATE-----\n"
+    );
+    assert_eq!(
+        config.get::<String>("tls.absolute").unwrap(),
+        "-----BEGIN CERTIFICATE-----\n"
+    );
+}
+
+#[test]
+fn test_file_namespace_errors_on_missing_or_oversized_file() {
+    let dir = tempfile::tempdir().expect("temp dir");
+    let config_path = dir.path().join("config.rune");
+    std::fs::write(&config_path, "cert $file.missing.pem\n").expect("write config");
+
+    let config = RuneConfig::from_file(&config_path).expect("load config");
+    assert!(matches!(
+        config.get_value("cert"),
+        Err(RuneError::FileError {
+            code: Some(301),
+            ..
+        })
+    ));
+
+    std::fs::write(dir.path().join("big.bin"), vec![b'a'; 2 * 1024 * 1024]).expect("write big");
+    std::fs::write(&config_path, "blob $file.big.bin\n").expect("write config");
+
+    let config = RuneConfig::from_file(&config_path).expect("load config");
+    assert!(matches!(
+        config.get_value("blob"),
+        Err(RuneError::FileError {
+            code: Some(310),
+            ..
+        })
+    ));
+}
+
+#[test]
+fn test_blocks_named_like_namespaces_are_not_shadowed() {
+    let mut config = RuneConfig::from_str(
+        r#"
+file:
+  name "cert.pem"
+end
+sys:
+  os "custom"
+end
+runtime:
+  mode "from-config"
+end
+env:
+  HOME "/srv"
+end
+tls:
+  cert file.name
+  os sys.os
+  mode runtime.mode
+  home env.HOME
+  live_mode $runtime.mode
+end
+"#,
+    )
+    .unwrap();
+    config.set_runtime("mode", "from-context");
+
+    assert_eq!(config.get::<String>("tls.cert").unwrap(), "cert.pem");
+    assert_eq!(config.get::<String>("tls.os").unwrap(), "custom");
+    assert_eq!(config.get::<String>("tls.mode").unwrap(), "from-config");
+    assert_eq!(config.get::<String>("tls.home").unwrap(), "/srv");
+    assert_eq!(
+        config.get::<String>("tls.live_mode").unwrap(),
+        "from-context"
+    );
+}
+
+#[test]
+fn test_env_strings_coerce_to_requested_type() {
+    unsafe {
+        std::env::set_var("RUNE_COERCE_TEST_PORT", "8443");
+        std::env::set_var("RUNE_COERCE_TEST_DEBUG", "true");
+        std::env::set_var("RUNE_COERCE_TEST_RATIO", "0.25");
+    }
+
+    let config = RuneConfig::from_str(
+        "app:\n  port $env.RUNE_COERCE_TEST_PORT\n  debug $env.RUNE_COERCE_TEST_DEBUG\n  ratio $env.RUNE_COERCE_TEST_RATIO\nend\n",
+    )
+    .unwrap();
+
+    assert_eq!(config.get::<u16>("app.port").unwrap(), 8443);
+    assert!(config.get::<bool>("app.debug").unwrap());
+    assert_eq!(config.get::<f64>("app.ratio").unwrap(), 0.25);
+    assert_eq!(config.get::<String>("app.port").unwrap(), "8443");
+    assert!(config.get::<u8>("app.port").is_err());
+    assert!(config.get::<u16>("app.ratio").is_err());
+}
+
+#[test]
+fn test_sys_reference_resolves_to_real_value() {
+    use crate::DocumentBuilder;
+
+    let os = crate::resolver::parse_dollar_reference(
+        vec!["sys".into(), "os".into()],
+        crate::resolver::ResolverContext::global(),
+    )
+    .unwrap();
+
+    let mut config =
+        RuneConfig::from_str("app:\n  host_os $sys.os\n  via_import extra.os\nend\n").unwrap();
+    config.inject_import(
+        "extra".into(),
+        DocumentBuilder::new()
+            .global("os", Value::Reference(vec!["sys".into(), "os".into()]))
+            .build(),
+    );
+
+    assert_eq!(config.get_value("app.host_os").unwrap(), os);
+    assert_eq!(config.get_value("app.via_import").unwrap(), os);
+}
+
+#[test]
+fn test_runtime_reference_errors_when_unset() {
+    let mut config =
+        RuneConfig::from_str("build $runtime.build_id\napp:\n  build build\nend\n").unwrap();
+
+    assert!(matches!(
+        config.get_value("app.build"),
+        Err(RuneError::RuntimeError {
+            code: Some(311),
+            ..
+        })
+    ));
+
+    config.set_runtime("build_id", "abc123");
+    assert_eq!(config.get::<String>("app.build").unwrap(), "abc123");
+}
+
+#[test]
+fn test_const_references_resolve_and_undefined_ones_error() {
+    let config = RuneConfig::from_str(
+        r#"
+const:
+  PORT 8080
+  HOST "localhost"
+  URL "http://$const.HOST"
+end
+
+server:
+  port $const.PORT
+  url $const.URL
+  banner "listening on $const.HOST"
+end
+"#,
+    )
+    .unwrap();
+    assert_eq!(config.get::<u16>("server.port").unwrap(), 8080);
+    assert_eq!(
+        config.get::<String>("server.url").unwrap(),
+        "http://localhost"
+    );
+    assert_eq!(
+        config.get::<String>("server.banner").unwrap(),
+        "listening on localhost"
+    );
+    assert!(config.get_value("const").is_err());
+
+    let undefined = RuneConfig::from_str("missing $const.NOPE\n").unwrap();
+    assert!(matches!(
+        undefined.get_value("missing"),
+        Err(RuneError::RuntimeError {
+            code: Some(312),
+            ..
+        })
+    ));
+}
+
+#[test]
+fn test_escaped_dollar_is_not_expanded() {
+    unsafe {
+        std::env::set_var("RUNE_ESCAPE_TEST_HOME", "/home/rune");
+    }
+
+    let config = RuneConfig::from_str(
+        r#"
+name "rune"
+doc:
+  price "price is \$env"
+  shell "echo \$RUNE_ESCAPE_TEST_HOME is $env.RUNE_ESCAPE_TEST_HOME"
+  var "\$var.name stays, $var.name expands"
+end
+"#,
+    )
+    .unwrap();
+
+    assert_eq!(config.get::<String>("doc.price").unwrap(), "price is $env");
+    assert_eq!(
+        config.get::<String>("doc.shell").unwrap(),
+        "echo $RUNE_ESCAPE_TEST_HOME is /home/rune"
+    );
+    assert_eq!(
+        config.get::<String>("doc.var").unwrap(),
+        "$var.name stays, rune expands"
+    );
+}
+
+#[test]
+fn test_escaped_backslash_before_dollar_still_expands() {
+    unsafe {
+        std::env::set_var("RUNE_ESCAPE_TEST_PX", "v");
+    }
+
+    let config = RuneConfig::from_str(
+        r#"
+a "C:\\$env.RUNE_ESCAPE_TEST_PX"
+b "C:\\\$env"
+"#,
+    )
+    .unwrap();
+
+    assert_eq!(config.get::<String>("a").unwrap(), r"C:\v");
+    assert_eq!(config.get::<String>("b").unwrap(), r"C:\$env");
+}
+
+#[test]
+fn test_escaped_dollar_is_plain_text_in_the_document() {
+    let config =
+        RuneConfig::from_str("price \"cost \\$5\"\nmark \"\u{E000}$var.price\"\n").unwrap();
+    let doc = config.document().unwrap();
+
+    assert_eq!(doc.globals[0].1.as_str(), Some("cost $5"));
+    assert_eq!(
+        doc.globals[1].1,
+        Value::Interpolated(vec![
+            Value::String("\u{E000}".into()),
+            Value::Namespaced(vec!["var".into(), "price".into()]),
+        ])
+    );
+    assert_eq!(config.get::<String>("mark").unwrap(), "\u{E000}cost $5");
+
+    let rendered = RuneConfig::from_str(&doc.to_string()).unwrap();
+    assert_eq!(rendered.document().unwrap(), doc);
+}
+
+#[test]
+fn test_escaped_dollar_exports_without_a_marker() {
+    let config = RuneConfig::from_str(
+        r#"
+doc:
+  price "price \$env"
+  path "C:\\dir"
+end
+"#,
+    )
+    .unwrap();
+    let doc = config.document().unwrap();
+
+    let json = crate::export::export_document_to_json(doc).unwrap();
+    let v: serde_json::Value = serde_json::from_str(&json).unwrap();
+    let items = v["items"].to_string();
+    assert!(items.contains(r#""price $env""#), "{}", items);
+    assert!(items.contains(r#""C:\\dir""#), "{}", items);
+
+    let resolved = crate::export::export_resolved_to_json(&config).unwrap();
+    let v: serde_json::Value = serde_json::from_str(&resolved).unwrap();
+    assert_eq!(v["doc"]["price"], "price $env");
+
+    let rendered = crate::export::to_rune_string(doc);
+    assert!(rendered.contains(r#"price "price \$env""#), "{}", rendered);
+    assert_eq!(
+        RuneConfig::from_str(&rendered)
+            .unwrap()
+            .get::<String>("doc.price")
+            .unwrap(),
+        "price $env"
+    );
+}
+
+#[test]
+fn test_self_reference_reports_a_cycle() {
+    for (source, path) in [("a a\n", "a"), ("app:\n  x app.x\nend\n", "app.x")] {
+        let config = RuneConfig::from_str(source).unwrap();
+        match config.get_value(path) {
+            Err(RuneError::RuntimeError {
+                message,
+                code: Some(313),
+                ..
+            }) => assert!(
+                message.ends_with(&format!("{0} -> {0}", path)),
+                "{}",
+                message
+            ),
+            other => panic!("expected a cycle error for {:?}, got {:?}", source, other),
+        }
+    }
+}
+
+#[test]
+fn test_mutual_references_report_a_cycle() {
+    let config = RuneConfig::from_str("a b\nb a\n").unwrap();
+
+    match config.get::<String>("a") {
+        Err(RuneError::RuntimeError {
+            message,
+            code: Some(313),
+            ..
+        }) => assert!(message.contains("a -> b -> a") || message.contains("b -> a -> b")),
+        other => panic!("expected a cycle error, got {:?}", other),
+    }
+
+    let nested = RuneConfig::from_str("app:\n  x app.y\n  y app.x\nend\n").unwrap();
+    assert!(matches!(
+        nested.get_value("app.x"),
+        Err(RuneError::RuntimeError {
+            code: Some(313),
+            ..
+        })
+    ));
+
+    let chain = RuneConfig::from_str("a b\nb c\nc \"end\"\nd a\n").unwrap();
+    assert_eq!(chain.get::<String>("d").unwrap(), "end");
+}
+
+#[test]
+fn test_eager_resolution_fails_at_load_while_lazy_fails_on_access() {
+    use crate::ResolutionMode;
+
+    let source = "name \"rune\"\nbroken $const.MISSING\n";
+
+    let lazy = RuneConfig::from_str(source).unwrap();
+    assert_eq!(lazy.resolution_mode(), ResolutionMode::Lazy);
+    assert_eq!(lazy.get::<String>("name").unwrap(), "rune");
+    assert!(matches!(
+        lazy.get_value("broken"),
+        Err(RuneError::RuntimeError {
+            code: Some(312),
+            ..
+        })
+    ));
+
+    let mut eager = RuneConfig::from_str(source).unwrap();
+    assert!(matches!(
+        eager.set_resolution_mode(ResolutionMode::Eager),
+        Err(RuneError::RuntimeError {
+            code: Some(312),
+            ..
+        })
+    ));
+}
+
+#[test]
+fn test_eager_resolution_cache_is_refreshed_after_changes() {
+    use crate::ResolutionMode;
+
+    let mut config = RuneConfig::from_str("build $runtime.build_id\n").unwrap();
+    config.set_runtime("build_id", "one");
+    config
+        .set_resolution_mode(ResolutionMode::Eager)
+        .expect("eager resolution");
+    assert_eq!(config.get::<String>("build").unwrap(), "one");
+
+    config.set_runtime("build_id", "two");
+    assert_eq!(config.get::<String>("build").unwrap(), "two");
+}
+
+#[test]
+fn test_sys_numeric_keys_resolve_to_numbers() {
+    let config = RuneConfig::from_str(
+        "workers $sys.cpu_count\nmemory $sys.memory_total\nos $sys.os\nsummary \"$sys.memory_total total\"\n",
+    )
+    .unwrap();
+
+    assert!(config.get::<usize>("workers").unwrap() > 0);
+    assert!(matches!(config.get_value("workers"), Ok(Value::Int(n)) if n > 0));
+    assert!(matches!(config.get_value("memory"), Ok(Value::Int(n)) if n > 0));
+    assert!(matches!(config.get_value("os"), Ok(Value::String(_))));
+
+    // Inside strings byte sizes stay human-readable.
+    let summary: String = config.get("summary").unwrap();
+    assert!(summary.ends_with("B total"), "got {}", summary);
+}
+
+#[test]
+fn test_env_list_reads_into_vec() {
+    let joined = std::env::join_paths(["/opt/rune/bin", "/usr/bin"]).unwrap();
+    unsafe {
+        std::env::set_var("RUNE_CONFIG_TEST_LIST", &joined);
+    }
+
+    let config = RuneConfig::from_str("dirs $env_list.RUNE_CONFIG_TEST_LIST\n").unwrap();
+    let dirs: Vec<String> = config.get("dirs").unwrap();
+    assert_eq!(dirs, vec!["/opt/rune/bin", "/usr/bin"]);
+}
+
+#[cfg(feature = "tokio")]
+#[tokio::test]
+async fn test_async_provider_resolves_key() {
+    use crate::{AsyncResolverProvider, ResolveFuture};
+
+    struct Vault;
+
+    impl AsyncResolverProvider for Vault {
+        fn resolve<'a>(&'a self, path: &'a [String]) -> ResolveFuture<'a> {
+            Box::pin(async move {
+                tokio::task::yield_now().await;
+                Ok(Value::String(format!("secret-{}", path.join("."))))
+            })
+        }
+    }
+
+    let mut config =
+        RuneConfig::from_str("db:\n  password $vault.db.password\n  user \"admin\"\nend\n")
+            .unwrap();
+    config.register_async_provider("vault", Vault);
+
+    let password: String = config.get_async("db.password").await.unwrap();
+    assert_eq!(password, "secret-db.password");
+    let user: String = config.get_async("db.user").await.unwrap();
+    assert_eq!(user, "admin");
+}
+
+#[cfg(feature = "tokio")]
+#[tokio::test]
+async fn test_async_get_fetches_only_what_the_path_reaches() {
+    use std::sync::{Arc, Mutex};
+
+    use crate::{AsyncResolverProvider, ResolveFuture};
+
+    struct Vault(Arc<Mutex<Vec<String>>>);
+
+    impl AsyncResolverProvider for Vault {
+        fn resolve<'a>(&'a self, path: &'a [String]) -> ResolveFuture<'a> {
+            Box::pin(async move {
+                self.0.lock().unwrap().push(path.join("."));
+                Ok(Value::String(format!("secret-{}", path.join("."))))
+            })
+        }
+    }
+
+    let asked = Arc::new(Mutex::new(Vec::new()));
+    let mut config = RuneConfig::from_str(
+        "db:\n  password $vault.db.password\nend\ncache:\n  password $vault.cache.password\nend\napp:\n  db db.password\n  url \"postgres://admin:$var.db.password@db\"\nend\n",
+    )
+    .unwrap();
+    config.register_async_provider("vault", Vault(asked.clone()));
+
+    let password: String = config.get_async("app.db").await.unwrap();
+    assert_eq!(password, "secret-db.password");
+    assert_eq!(*asked.lock().unwrap(), vec!["db.password"]);
+
+    let url: String = config.get_async("app.url").await.unwrap();
+    assert_eq!(url, "postgres://admin:secret-db.password@db");
+    assert!(
+        !asked
+            .lock()
+            .unwrap()
+            .contains(&"cache.password".to_string())
+    );
+}
+
+#[test]
+fn test_context_env_map_replaces_process_env() {
+    // PATH is set in any real environment; a context built `with_env` must not see it.
+    let context = ResolverContext::with_env([("RUNE_CONFIG_FAKE_HOST", "db.internal")]);
+    let config = RuneConfig::from_str_with_context(
+        "host $env.RUNE_CONFIG_FAKE_HOST\nurl \"postgres://$env.RUNE_CONFIG_FAKE_HOST/app\"\npath $env.PATH:-\"unset\"\n",
+        context,
+    )
+    .unwrap();
+
+    assert_eq!(config.get::<String>("host").unwrap(), "db.internal");
+    assert_eq!(
+        config.get::<String>("url").unwrap(),
+        "postgres://db.internal/app"
+    );
+    assert_eq!(config.get::<String>("path").unwrap(), "unset");
+    assert_eq!(config.context().env_var("PATH"), None);
+}
+
+#[test]
+fn test_env_reference_built_in_code_reads_the_context() {
+    let context = ResolverContext::with_env([("ONLY_IN_CTX", "yes")]);
+    let mut config = RuneConfig::from_str_with_context("", context).unwrap();
+    let reference = |name: &str| vec!["env".to_string(), name.to_string()];
+
+    config
+        .set_value("plain", Value::Reference(reference("ONLY_IN_CTX")))
+        .unwrap();
+    config
+        .set_value("dollar", Value::Namespaced(reference("ONLY_IN_CTX")))
+        .unwrap();
+    config
+        .set_value("unset", Value::Namespaced(reference("PATH")))
+        .unwrap();
+
+    assert_eq!(config.get::<String>("plain").unwrap(), "yes");
+    assert_eq!(config.get::<String>("dollar").unwrap(), "yes");
+    assert!(config.get_value("unset").is_err());
+}
+
+#[test]
+fn test_numeric_path_segments_index_arrays() {
+    // Arrays of objects have no literal syntax yet, so the servers come from an import.
+    let server = |host: &str, port: i64| {
+        crate::ObjectBuilder::new()
+            .set("host", host)
+            .set("port", port)
+            .build()
+    };
+    let servers = crate::DocumentBuilder::new()
+        .global(
+            "servers",
+            Value::Array(vec![
+                server("east.example.com", 8080),
+                server("west.example.com", 8081),
+            ]),
+        )
+        .build();
+
+    let mut config = RuneConfig::from_str(
+        "app:\n  servers net.servers\n  primary net.servers.\"0\".host\nend\n",
+    )
+    .unwrap();
+    config.inject_import("net".into(), servers);
+
+    assert_eq!(
+        config.get::<String>("app.servers.0.host").unwrap(),
+        "east.example.com"
+    );
+    assert_eq!(config.get::<u16>("app.servers.1.port").unwrap(), 8081);
+    assert_eq!(
+        config.get::<String>("app.primary").unwrap(),
+        "east.example.com"
+    );
+}
+
+#[test]
+fn test_out_of_range_index_is_not_found() {
+    let config = RuneConfig::from_str("hosts [\"a\", \"b\"]\n").unwrap();
+
+    let err = config.get::<String>("hosts.2").unwrap_err();
+    match err {
+        RuneError::SyntaxError {
+            message,
+            hint,
+            code,
+            ..
+        } => {
+            assert_eq!(code, Some(304));
+            assert!(
+                message.contains("Index 2 out of range for 'hosts'"),
+                "got {}",
+                message
+            );
+            assert_eq!(hint.as_deref(), Some("The array has 2 element(s)"));
+        }
+        other => panic!("expected not-found error, got {:?}", other),
+    }
+}
+
+#[cfg(feature = "serde")]
+#[test]
+fn test_deserialize_into_nested_struct() {
+    #[derive(serde::Deserialize, Debug, PartialEq)]
+    struct Server {
+        host: String,
+        port: u16,
+    }
+
+    #[derive(serde::Deserialize, Debug, PartialEq)]
+    struct App {
+        name: String,
+        version: String,
+        debug: bool,
+        server: Server,
+        features: Vec<String>,
+        #[serde(default)]
+        log_level: Option<String>,
+    }
+
+    #[derive(serde::Deserialize, Debug, PartialEq)]
+    struct Config {
+        app_name: String,
+        app: App,
+    }
+
+    let config_content = r#"
+@description "Test config"
+app_name "TestApp"
+app:
+  name app_name
+  version "1.0.0"
+  debug true
+
+  server:
+    host "localhost"
+    port 8080
+  end
+
+  features [
+    "auth"
+    "logging"
+  ]
+end
+"#;
+    let config = RuneConfig::from_str(config_content).unwrap();
+    let parsed: Config = config.deserialize("").unwrap();
+
+    assert_eq!(
+        parsed,
+        Config {
+            app_name: "TestApp".into(),
+            app: App {
+                name: "TestApp".into(),
+                version: "1.0.0".into(),
+                debug: true,
+                server: Server {
+                    host: "localhost".into(),
+                    port: 8080
+                },
+                features: vec!["auth".into(), "logging".into()],
+                log_level: None,
+            },
+        }
+    );
+}
+
+#[cfg(feature = "serde")]
+#[test]
+fn test_deserialize_coerces_env_strings_and_kebab_keys() {
+    #[derive(serde::Deserialize)]
+    struct Pool {
+        max_connections: u32,
+        enabled: bool,
+        mode: Mode,
+    }
+
+    #[derive(serde::Deserialize, Debug, PartialEq)]
+    #[serde(rename_all = "lowercase")]
+    enum Mode {
+        Fast,
+        Safe,
+    }
+
+    let context = ResolverContext::with_env([("POOL_SIZE", "16"), ("POOL_ON", "true")]);
+    let config = RuneConfig::from_str_with_context(
+        "pool:\n  max-connections $env.POOL_SIZE\n  enabled $env.POOL_ON\n  mode \"safe\"\nend\n",
+        context,
+    )
+    .unwrap();
+
+    let pool: Pool = crate::from_value(config.get_value("pool").unwrap()).unwrap();
+    assert_eq!(pool.max_connections, 16);
+    assert!(pool.enabled);
+    assert_eq!(pool.mode, Mode::Safe);
+
+    let err = crate::from_value::<u8>(Value::Int(300)).unwrap_err();
+    assert!(matches!(
+        err,
+        RuneError::TypeError {
+            code: Some(407),
+            ..
+        }
+    ));
+}
+
+#[cfg(feature = "serde")]
+#[test]
+fn test_deserialize_at_path() {
+    #[derive(serde::Deserialize, Debug, PartialEq)]
+    enum Backend {
+        Memory,
+        Disk { path: String },
+    }
+
+    #[derive(serde::Deserialize, Debug, PartialEq)]
+    struct Tls {
+        cert: String,
+    }
+
+    #[derive(serde::Deserialize, Debug, PartialEq)]
+    struct ServerCfg {
+        host: String,
+        port: u16,
+        tls: Option<Tls>,
+        proxy: Option<Tls>,
+        backends: Vec<Backend>,
+    }
+
+    let config = RuneConfig::from_str(
+        r#"
+default_host "0.0.0.0"
+app:
+  server:
+    host default_host
+    port 8080
+    tls:
+      cert "/etc/cert.pem"
+    end
+    proxy null
+    backends [
+      "Memory"
+      { Disk { path "/var/cache" } }
+    ]
+  end
+end
+"#,
+    )
+    .unwrap();
+
+    let server: ServerCfg = config.deserialize("app.server").unwrap();
+    assert_eq!(
+        server,
+        ServerCfg {
+            host: "0.0.0.0".into(),
+            port: 8080,
+            tls: Some(Tls {
+                cert: "/etc/cert.pem".into()
+            }),
+            proxy: None,
+            backends: vec![
+                Backend::Memory,
+                Backend::Disk {
+                    path: "/var/cache".into()
+                }
+            ],
+        }
+    );
+    assert_eq!(config.deserialize::<u16>("app.server.port").unwrap(), 8080);
+
+    match config.deserialize::<bool>("app.server.host") {
+        Err(RuneError::TypeError { line, .. }) => assert_eq!(line, 5),
+        other => panic!("expected a type error, got {:?}", other),
+    }
+    assert!(config.deserialize::<u16>("app.missing").is_err());
+}
+
+#[test]
+fn test_get_block_as_typed_map() {
+    let config = RuneConfig::from_str(
+        "features:\n  auth true\n  dark-mode false\n  beta $env.RUNE_CONFIG_UNSET_FLAG:-true\nend\n",
+    )
+    .unwrap();
+
+    let features: HashMap<String, bool> = config.get("features").unwrap();
+    let expected: HashMap<String, bool> = [("auth", true), ("dark-mode", false), ("beta", true)]
+        .into_iter()
+        .map(|(k, v)| (k.to_string(), v))
+        .collect();
+    assert_eq!(features, expected);
+}
+
+#[test]
+fn test_typed_map_rejects_wrong_value_type() {
+    let config = RuneConfig::from_str("limits:\n  cpu 4\n  memory \"lots\"\nend\n").unwrap();
+
+    let err = config.get::<HashMap<String, u32>>("limits").unwrap_err();
+    match err {
+        RuneError::TypeError { message, code, .. } => {
+            assert_eq!(code, Some(402));
+            assert!(message.starts_with("Key 'memory':"), "got {}", message);
+        }
+        other => panic!("expected type error, got {:?}", other),
+    }
+}
+
+#[test]
+fn test_get_block_as_ordered_maps() {
+    let config =
+        RuneConfig::from_str("ports:\n  web 8080\n  admin 9000\n  api 3000\nend\n").unwrap();
+
+    let sorted: std::collections::BTreeMap<String, u16> = config.get("ports").unwrap();
+    assert_eq!(
+        sorted.keys().map(String::as_str).collect::<Vec<_>>(),
+        ["admin", "api", "web"]
+    );
+
+    let written: indexmap::IndexMap<String, u16> = config.get("ports").unwrap();
+    assert_eq!(
+        written.into_iter().collect::<Vec<_>>(),
+        [
+            ("web".to_string(), 8080),
+            ("admin".to_string(), 9000),
+            ("api".to_string(), 3000)
+        ]
+    );
+
+    let err = std::collections::BTreeMap::<String, u16>::try_from(Value::Int(1)).unwrap_err();
+    assert!(matches!(
+        err,
+        RuneError::TypeError {
+            code: Some(410),
+            ..
+        }
+    ));
+}
+
+#[test]
+fn test_overlay_prefers_higher_and_keeps_other_keys() {
+    let system = RuneConfig::from_str(
+        r#"
+log_level "info"
+app:
+  port 80
+  host "0.0.0.0"
+  tls:
+    enabled false
+    cert "/etc/ssl/app.pem"
+  end
+end
+"#,
+    )
+    .unwrap();
+    let user = RuneConfig::from_str(
+        r#"
+app:
+  port 8080
+  tls:
+    enabled true
+  end
+end
+theme "dark"
+"#,
+    )
+    .unwrap();
+
+    let config = system.overlay(&user);
+
+    assert_eq!(config.get::<u16>("app.port").unwrap(), 8080);
+    assert!(config.get::<bool>("app.tls.enabled").unwrap());
+    assert_eq!(config.get::<String>("app.host").unwrap(), "0.0.0.0");
+    assert_eq!(
+        config.get::<String>("app.tls.cert").unwrap(),
+        "/etc/ssl/app.pem"
+    );
+    assert_eq!(config.get::<String>("log_level").unwrap(), "info");
+    assert_eq!(config.get::<String>("theme").unwrap(), "dark");
+
+    // No single source to point into, so errors come without a line number.
+    match config.get::<String>("app.missing").unwrap_err() {
+        RuneError::SyntaxError { line, code, .. } => {
+            assert_eq!(line, 0);
+            assert_eq!(code, Some(304));
+        }
+        other => panic!("expected not-found error, got {:?}", other),
+    }
+}
+
+#[test]
+fn test_merge_overrides_nested_port_and_keeps_host() {
+    let mut config = RuneConfig::from_str(
+        "server:\n  host \"0.0.0.0\"\n  port 80\n  plugins [\"auth\"]\nend\nname \"base\"\n",
+    )
+    .unwrap();
+    let production =
+        RuneConfig::from_str("server:\n  port 8080\n  plugins [\"metrics\"]\nend\n").unwrap();
+
+    config.merge(&production);
+
+    assert_eq!(config.get::<u16>("server.port").unwrap(), 8080);
+    assert_eq!(config.get::<String>("server.host").unwrap(), "0.0.0.0");
+    assert_eq!(config.get::<String>("name").unwrap(), "base");
+    // Arrays are replaced by default.
+    assert_eq!(
+        config.get::<Vec<String>>("server.plugins").unwrap(),
+        ["metrics"]
+    );
+
+    config.merge_with(&production, MergeStrategy::Concat);
+    assert_eq!(
+        config.get::<Vec<String>>("server.plugins").unwrap(),
+        ["metrics", "metrics"]
+    );
+
+    config
+        .merge_from_str("server:\n  host \"127.0.0.1\"\nend\n")
+        .unwrap();
+    assert_eq!(config.get::<String>("server.host").unwrap(), "127.0.0.1");
+    assert_eq!(config.get::<u16>("server.port").unwrap(), 8080);
+    assert!(config.merge_from_str("server:\n  port\n").is_err());
+}
+
+#[test]
+fn test_env_overrides_nested_number_and_bool() {
+    let context = ResolverContext::with_env([
+        ("MYAPP_APP__SERVER__PORT", "9090"),
+        ("MYAPP_DEBUG", "TRUE"),
+        ("MYAPP_LOG_LEVEL", "warn"),
+        ("MYAPP_APP__UNKNOWN", "ignored"),
+        ("OTHER_DEBUG", "false"),
+    ]);
+    let mut config = RuneConfig::from_str_with_context(
+        "debug false\nlog-level \"info\"\napp:\n  server:\n    port 8080\n    host \"localhost\"\n  end\nend\n",
+        context,
+    )
+    .unwrap();
+    assert_eq!(config.get::<u16>("app.server.port").unwrap(), 8080);
+
+    assert_eq!(config.apply_env_overrides("MYAPP").unwrap(), 3);
+
+    assert!(matches!(
+        config.get_value("app.server.port"),
+        Ok(Value::Int(9090))
+    ));
+    assert!(config.get::<bool>("debug").unwrap());
+    assert_eq!(config.get::<String>("log-level").unwrap(), "warn");
+    assert_eq!(
+        config.get::<String>("app.server.host").unwrap(),
+        "localhost"
+    );
+    assert!(!config.has("app.unknown"));
+}
+
+#[test]
+fn test_env_overrides_reject_bad_values_and_unknown_paths_when_strict() {
+    let context = ResolverContext::with_env([("MYAPP_PORT", "not-a-port")]);
+    let mut config = RuneConfig::from_str_with_context("port 8080\n", context).unwrap();
+    let err = config.apply_env_overrides("MYAPP").unwrap_err();
+    assert!(matches!(
+        err,
+        RuneError::TypeError {
+            code: Some(414),
+            ..
+        }
+    ));
+
+    let context = ResolverContext::with_env([("MYAPP_PROT", "9090")]);
+    let mut config = RuneConfig::from_str_with_context("port 8080\n", context).unwrap();
+    assert_eq!(config.apply_env_overrides("MYAPP").unwrap(), 0);
+    let err = config.apply_env_overrides_strict("MYAPP").unwrap_err();
+    assert!(matches!(
+        err,
+        RuneError::RuntimeError {
+            code: Some(314),
+            ..
+        }
+    ));
+}
+
+#[test]
+fn test_set_existing_and_new_nested_keys() {
+    let mut config =
+        RuneConfig::from_str("name \"RuneApp\"\napp:\n  port 8080\n  hosts [\"a\", \"b\"]\nend\n")
+            .unwrap();
+
+    config.set("app.port", 9090).unwrap();
+    config.set("app.hosts.1", "c").unwrap();
+    config.set("app.tls.cert.path", "/etc/ssl/app.pem").unwrap();
+    config.set("log.level", "debug").unwrap();
+    config.set("debug", true).unwrap();
+
+    assert_eq!(config.get::<u16>("app.port").unwrap(), 9090);
+    assert_eq!(
+        config.get::<Vec<String>>("app.hosts").unwrap(),
+        vec!["a", "c"]
+    );
+    assert_eq!(
+        config.get::<String>("app.tls.cert.path").unwrap(),
+        "/etc/ssl/app.pem"
+    );
+    assert_eq!(config.get::<String>("log.level").unwrap(), "debug");
+    assert!(config.get::<bool>("debug").unwrap());
+    assert_eq!(config.get::<String>("name").unwrap(), "RuneApp");
+
+    // New entries keep the document's layout: blocks as items, scalars as globals.
+    let doc = config.document().unwrap();
+    assert!(doc.items.iter().any(|(k, _)| k == "log"));
+    assert!(doc.globals.iter().any(|(k, _)| k == "debug"));
+}
+
+#[test]
+fn test_set_value_on_existing_block_and_new_nested_path() {
+    let mut config =
+        RuneConfig::from_str("app:\n  server:\n    host \"localhost\"\n    port 80\n  end\nend\n")
+            .unwrap();
+
+    config
+        .set_value("app.server.port", Value::Int(8080))
+        .unwrap();
+    config
+        .set_value(
+            "cache.redis.limits",
+            Value::Object(vec![ObjectItem::Assign("max".into(), Value::Int(64))]),
+        )
+        .unwrap();
+
+    assert_eq!(config.get::<u16>("app.server.port").unwrap(), 8080);
+    assert_eq!(
+        config.get::<String>("app.server.host").unwrap(),
+        "localhost"
+    );
+    assert_eq!(config.get::<u32>("cache.redis.limits.max").unwrap(), 64);
+
+    let err = config
+        .set_value("app.server.host.name", Value::Null)
+        .unwrap_err();
+    assert!(matches!(
+        err,
+        RuneError::RuntimeError {
+            code: Some(315),
+            ..
+        }
+    ));
+}
+
+#[test]
+fn test_set_through_scalar_or_past_array_end_fails() {
+    let mut config = RuneConfig::from_str("port 8080\nhosts [\"a\"]\n").unwrap();
+
+    let err = config.set("port.number", 1).unwrap_err();
+    assert!(matches!(
+        err,
+        RuneError::RuntimeError {
+            code: Some(315),
+            ..
+        }
+    ));
+    let err = config.set("hosts.3", "d").unwrap_err();
+    assert!(matches!(
+        err,
+        RuneError::RuntimeError {
+            code: Some(315),
+            ..
+        }
+    ));
+
+    assert_eq!(config.get::<u16>("port").unwrap(), 8080);
+}
+
+#[cfg(feature = "notify")]
+#[test]
+fn test_watch_reloads_on_change() {
+    use std::sync::mpsc;
+    use std::time::Duration;
+
+    let dir = tempfile::tempdir().unwrap();
+    let path = dir.path().join("app.rune");
+    std::fs::write(&path, "port 8080\n").unwrap();
+
+    let (tx, rx) = mpsc::channel();
+    let _watcher = RuneConfig::watch(&path, move |reloaded| {
+        let _ = tx.send(reloaded.and_then(|config| config.get::<u16>("port")));
+    })
+    .unwrap();
+
+    // Two quick writes are debounced into a single reload of the final content.
+    std::fs::write(&path, "port 9000\n").unwrap();
+    std::fs::write(&path, "port 9090\n").unwrap();
+
+    let port = rx.recv_timeout(Duration::from_secs(5)).expect("no reload");
+    assert_eq!(port.unwrap(), 9090);
+    assert!(rx.recv_timeout(Duration::from_millis(500)).is_err());
+}
+
+#[test]
+fn test_inline_objects_are_reachable_by_path() {
+    let config = RuneConfig::from_str(
+        "servers [ { host \"a\" port 1 } { host \"b\" port 2 } ]\npoint { x 1 y 2 }\n",
+    )
+    .unwrap();
+
+    assert_eq!(config.get::<String>("servers.1.host").unwrap(), "b");
+    assert_eq!(config.get::<u16>("servers.0.port").unwrap(), 1);
+    assert_eq!(config.get::<i64>("point.y").unwrap(), 2);
+}
+
+#[test]
+fn test_import_cycle_is_reported_with_its_chain() {
+    let dir = tempfile::tempdir().expect("temp dir");
+    std::fs::write(dir.path().join("a.rune"), "gather \"b.rune\"\nname \"a\"\n").unwrap();
+    std::fs::write(
+        dir.path().join("b.rune"),
+        "gather \"a.rune\" as a\nname \"b\"\n",
+    )
+    .unwrap();
+    std::fs::write(dir.path().join("self.rune"), "gather \"self.rune\" as me\n").unwrap();
+
+    match RuneConfig::from_file(dir.path().join("a.rune")) {
+        Err(RuneError::FileError {
+            message,
+            code: Some(317),
+            ..
+        }) => {
+            let files: Vec<&str> = message
+                .trim_start_matches("Import cycle: ")
+                .split(" -> ")
+                .map(|p| p.rsplit(['/', '\\']).next().unwrap())
+                .collect();
+            assert_eq!(files, vec!["a.rune", "b.rune", "a.rune"]);
+        }
+        other => panic!("expected an import cycle error, got {:?}", other.err()),
+    }
+
+    assert!(matches!(
+        RuneConfig::from_file(dir.path().join("self.rune")),
+        Err(RuneError::FileError {
+            code: Some(317),
+            ..
+        })
+    ));
+}
+
+#[test]
+fn test_shared_import_is_not_a_cycle() {
+    let dir = tempfile::tempdir().expect("temp dir");
+    std::fs::write(dir.path().join("common.rune"), "level \"info\"\n").unwrap();
+    std::fs::write(
+        dir.path().join("left.rune"),
+        "gather \"common.rune\" as c\n",
+    )
+    .unwrap();
+    std::fs::write(
+        dir.path().join("right.rune"),
+        "gather \"common.rune\" as c\n",
+    )
+    .unwrap();
+    std::fs::write(
+        dir.path().join("main.rune"),
+        "gather \"left.rune\" as l\ngather \"right.rune\" as r\nlevel c.level\n",
+    )
+    .unwrap();
+
+    let config = RuneConfig::from_file(dir.path().join("main.rune")).unwrap();
+    assert_eq!(config.get::<String>("level").unwrap(), "info");
+}
+
+#[test]
+fn test_nested_gathers_are_namespaced_under_their_importer() {
+    let dir = tempfile::tempdir().expect("temp dir");
+    std::fs::create_dir(dir.path().join("shared")).unwrap();
+    std::fs::write(
+        dir.path().join("shared/base.rune"),
+        "gather \"deep.rune\" as deep\nx \"from base\"\nport 8080\n",
+    )
+    .unwrap();
+    std::fs::write(dir.path().join("shared/deep.rune"), "z \"from deep\"\n").unwrap();
+    std::fs::write(
+        dir.path().join("defaults.rune"),
+        "gather \"shared/base.rune\" as base\nport base.port\n",
+    )
+    .unwrap();
+    std::fs::write(
+        dir.path().join("main.rune"),
+        "gather \"defaults.rune\" as defaults\napp:\n  x defaults.base.x\n  z defaults.base.deep.z\n  port defaults.port\nend\n",
+    )
+    .unwrap();
+
+    let config = RuneConfig::from_file(dir.path().join("main.rune")).unwrap();
+    assert_eq!(config.get::<String>("app.x").unwrap(), "from base");
+    assert_eq!(config.get::<String>("app.z").unwrap(), "from deep");
+    assert_eq!(config.get::<u16>("app.port").unwrap(), 8080);
+}
+
+#[test]
+fn test_nested_gather_does_not_replace_a_top_level_alias() {
+    let dir = tempfile::tempdir().expect("temp dir");
+    std::fs::create_dir(dir.path().join("sub")).unwrap();
+    std::fs::write(dir.path().join("base.rune"), "port 1111\n").unwrap();
+    std::fs::write(dir.path().join("sub/base.rune"), "port 2222\n").unwrap();
+    std::fs::write(
+        dir.path().join("sub/defaults.rune"),
+        "gather \"base.rune\" as base\n",
+    )
+    .unwrap();
+
+    for gathers in [
+        "gather \"base.rune\" as base\ngather \"sub/defaults.rune\" as defaults\n",
+        "gather \"sub/defaults.rune\" as defaults\ngather \"base.rune\" as base\n",
+    ] {
+        std::fs::write(
+            dir.path().join("main.rune"),
+            format!("{}a base.port\nb defaults.base.port\n", gathers),
+        )
+        .unwrap();
+
+        let config = RuneConfig::from_file(dir.path().join("main.rune")).unwrap();
+        assert_eq!(config.get::<u16>("a").unwrap(), 1111, "{}", gathers);
+        assert_eq!(config.get::<u16>("b").unwrap(), 2222, "{}", gathers);
+    }
+}
+
+#[test]
+fn test_gathers_are_read_from_statements_not_text() {
+    let dir = tempfile::tempdir().expect("temp dir");
+    std::fs::create_dir(dir.path().join("gather")).unwrap();
+    std::fs::write(dir.path().join("gather/base.rune"), "port 8080\n").unwrap();
+    std::fs::write(dir.path().join("ghost.rune"), "port 1\n").unwrap();
+    // The second line of `note` looks like a gather but is part of the string.
+    std::fs::write(
+        dir.path().join("main.rune"),
+        "note \"line one\ngather 'ghost.rune' as ghost\"\ngather \"gather/base.rune\" as base\nport base.port\n",
+    )
+    .unwrap();
+
+    let config = RuneConfig::from_file(dir.path().join("main.rune")).unwrap();
+    assert_eq!(config.get::<u16>("port").unwrap(), 8080);
+    assert!(config.documents.contains_key("base"));
+    assert!(!config.documents.contains_key("ghost"));
+}
+
+#[test]
+fn test_missing_gather_fails_unless_optional() {
+    let dir = tempfile::tempdir().expect("temp dir");
+    std::fs::write(
+        dir.path().join("required.rune"),
+        "gather \"absent.rune\" as extra\nport 8080\n",
+    )
+    .unwrap();
+    std::fs::write(
+        dir.path().join("optional.rune"),
+        "gather optional \"absent.rune\" as extra\nport 8080\n",
+    )
+    .unwrap();
+
+    let result = RuneConfig::from_file(dir.path().join("required.rune"));
+    assert!(matches!(
+        result,
+        Err(RuneError::FileError { ref path, code: Some(302), .. }) if path.ends_with("absent.rune")
+    ));
+
+    let config = RuneConfig::from_file(dir.path().join("optional.rune")).unwrap();
+    assert_eq!(config.get::<u16>("port").unwrap(), 8080);
+    assert!(!config.documents.contains_key("extra"));
+}
+
+#[test]
+fn test_parse_errors_name_the_file_they_are_in() {
+    let dir = tempfile::tempdir().expect("temp dir");
+    std::fs::write(
+        dir.path().join("db.rune"),
+        "host \"localhost\"\nport 5432 ^\n",
+    )
+    .unwrap();
+    std::fs::write(
+        dir.path().join("main.rune"),
+        "gather \"db.rune\" as db\nport db.port\n",
+    )
+    .unwrap();
+    std::fs::write(dir.path().join("broken.rune"), "app:\n  port 8080\n").unwrap();
+
+    let err = RuneConfig::from_file(dir.path().join("main.rune"))
+        .err()
+        .expect("the import has a syntax error");
+    let file = err.file().expect("the error names a file");
+    assert!(file.ends_with("db.rune"));
+    let text = err.to_string();
+    assert!(
+        text.starts_with(&format!(
+            "[RUNE] Unexpected character '^' in '{}' at line 2",
+            file
+        )),
+        "{}",
+        text
+    );
+    assert!(matches!(
+        &err,
+        RuneError::InFile { source, .. }
+            if matches!(**source, RuneError::UnexpectedCharacter { line: 2, .. })
+    ));
+    assert_eq!(err.code(), Some(104));
+
+    let err = RuneConfig::from_file(dir.path().join("broken.rune"))
+        .err()
+        .expect("the block is never closed");
+    assert!(err.file().unwrap().ends_with("broken.rune"));
+    assert!(err.to_string().contains("broken.rune"));
+
+    // Parsing a string names no file.
+    let err = RuneConfig::from_str("app:\n  port 8080\n").err().unwrap();
+    assert_eq!(err.file(), None);
+}
+
+#[test]
+fn test_env_default_covers_empty_variables() {
+    let context = ResolverContext::with_env([("PORT", ""), ("HOST", "")]);
+    let config = RuneConfig::from_str_with_context(
+        "port $env.PORT:-8080\nurl \"http://$env.HOST:-localhost/\"\n",
+        context,
+    )
+    .unwrap();
+
+    assert_eq!(config.get::<u16>("port").unwrap(), 8080);
+    assert_eq!(config.get::<String>("url").unwrap(), "http://localhost/");
+}
+
+#[test]
+fn test_strict_env_requires_the_variable() {
+    let source = "key $env.API_KEY!\ngreeting \"hi $env.API_KEY!\"\n";
+
+    let set =
+        RuneConfig::from_str_with_context(source, ResolverContext::with_env([("API_KEY", "k")]))
+            .unwrap();
+    assert_eq!(set.get::<String>("key").unwrap(), "k");
+    // In strings '!' is ordinary punctuation.
+    assert_eq!(set.get::<String>("greeting").unwrap(), "hi k!");
+
+    match RuneConfig::from_str_with_context(
+        source,
+        ResolverContext::with_env(Vec::<(String, String)>::new()),
+    ) {
+        Err(RuneError::RuntimeError {
+            message,
+            code: Some(318),
+            ..
+        }) => assert!(message.contains("API_KEY")),
+        other => panic!("expected an unset variable error, got {:?}", other.err()),
+    }
+}
+
+#[test]
+fn test_register_namespace_with_a_closure() {
+    let mut config = RuneConfig::from_str(
+        "db:\n  password $vault.DB_PASSWORD\n  url \"postgres://app:$vault.DB_PASSWORD@db\"\nend\n",
+    )
+    .unwrap();
+
+    config.register_namespace("vault", |path: &[String]| match path {
+        [name] if name == "DB_PASSWORD" => Ok(Value::String("s3cret".into())),
+        _ => Err(RuneError::RuntimeError {
+            message: format!("No secret {}", path.join(".")),
+            hint: None,
+            code: Some(309),
+        }),
+    });
+
+    assert_eq!(config.get::<String>("db.password").unwrap(), "s3cret");
+    assert_eq!(
+        config.get::<String>("db.url").unwrap(),
+        "postgres://app:s3cret@db"
+    );
+}
@@ -3,7 +3,7 @@
 
 use super::*;
 use crate::diagnostic::RuneDiagnostic;
-use crate::schema::{SchemaDocument, SchemaField, SchemaType};
+use crate::schema::{MetadataSchema, SchemaDocument, SchemaField, SchemaType};
 
 impl RuneConfig {
     pub fn get_validated<T, F>(
@@ -29,13 +29,81 @@ impl RuneConfig {
                     "Valid values are: {}\n  → {}",
                     valid_values, snippet
                 )),
-                code: Some(450),
+                code: Some(RuneErrorCode::InvalidValueForKey.code()),
             });
         }
 
         Ok(typed_value)
     }
 
+    /// The value at `path` if it is within `min..=max`, such as a port number.
+    ///
+    /// # Example
+    /// ```
+    /// # use rune_cfg::RuneConfig;
+    /// let config = RuneConfig::from_str("workers 64\n").unwrap();
+    /// assert_eq!(config.get_in_range("workers", 1u32, 64).unwrap(), 64);
+    /// assert!(config.get_in_range("workers", 1u32, 16).is_err());
+    /// ```
+    pub fn get_in_range<T>(&self, path: &str, min: T, max: T) -> Result<T, RuneError>
+    where
+        T: PartialOrd + std::fmt::Display + TryFrom<Value, Error = RuneError>,
+    {
+        let value: T = self.get(path)?;
+        if min <= value && value <= max {
+            return Ok(value);
+        }
+
+        let (line, snippet) = helpers::find_config_line(path, &self.raw_content);
+        Err(RuneError::ValidationError {
+            message: format!(
+                "Value {} for `{}` is out of range\nExpected: {} to {}",
+                value, path, min, max
+            ),
+            line,
+            column: 0,
+            hint: Some(format!(
+                "Use a value from {} to {}\n  → {}",
+                min, max, snippet
+            )),
+            code: Some(RuneErrorCode::NotInRange.code()),
+        })
+    }
+
+    /// The string at `path` if `pattern` matches it.
+    ///
+    /// # Example
+    /// ```
+    /// # use rune_cfg::RuneConfig;
+    /// let config = RuneConfig::from_str("version \"1.4.2\"\n").unwrap();
+    /// let version = config.get_matching("version", r"^\d+\.\d+\.\d+$").unwrap();
+    /// assert_eq!(version, "1.4.2");
+    /// ```
+    pub fn get_matching(&self, path: &str, pattern: &str) -> Result<String, RuneError> {
+        let re = regex::Regex::new(pattern).map_err(|e| RuneError::RuntimeError {
+            message: format!("Invalid regex pattern '{}': {}", pattern, e),
+            hint: Some("Check the pattern passed to get_matching".into()),
+            code: Some(RuneErrorCode::InvalidPattern.code()),
+        })?;
+
+        let value: String = self.get(path)?;
+        if re.is_match(&value) {
+            return Ok(value);
+        }
+
+        let (line, snippet) = helpers::find_config_line(path, &self.raw_content);
+        Err(RuneError::ValidationError {
+            message: format!(
+                "Invalid value '{}' for `{}`\nExpected a match for: {}",
+                value, path, pattern
+            ),
+            line,
+            column: 0,
+            hint: Some(format!("Use a value matching {}\n  → {}", pattern, snippet)),
+            code: Some(RuneErrorCode::NoPatternMatch.code()),
+        })
+    }
+
     pub fn get_string_enum(
         &self,
         path: &str,
@@ -51,7 +119,7 @@ impl RuneConfig {
                     line: 0,
                     column: 0,
                     hint: Some("Use a string value in your config".into()),
-                    code: Some(401),
+                    code: Some(RuneErrorCode::ExpectedString.code()),
                 });
             }
         };
@@ -72,7 +140,7 @@ impl RuneConfig {
                     allowed_values.join(", "),
                     snippet
                 )),
-                code: Some(451),
+                code: Some(RuneErrorCode::NotAllowedValue.code()),
             });
         }
 
@@ -84,6 +152,59 @@ impl RuneConfig {
         line > 0
     }
 
+    /// Check the document's `@metadata` against `schema`.
+    ///
+    /// Every violation is collected, so a single call reports all missing keys and
+    /// type mismatches as `ValidationError`s.
+    pub fn validate_metadata(&self, schema: &MetadataSchema) -> Result<(), Vec<RuneError>> {
+        let mut errors = Vec::new();
+
+        for field in &schema.fields {
+            let line = metadata_line(&field.name, &self.raw_content);
+            match self.get_metadata(&field.name) {
+                Some(value) if !type_matches(&field.kind, value) => {
+                    errors.push(RuneError::ValidationError {
+                        message: format!(
+                            "Metadata '@{}' expected {}, got {}",
+                            field.name,
+                            field.kind.name(),
+                            value.type_name()
+                        ),
+                        line,
+                        column: 0,
+                        hint: Some(format!(
+                            "Use a {} value for @{}",
+                            field.kind.name(),
+                            field.name
+                        )),
+                        code: Some(RuneErrorCode::InvalidMetadataType.code()),
+                    });
+                }
+                Some(_) => {}
+                None if field.required => {
+                    errors.push(RuneError::ValidationError {
+                        message: format!("Missing required metadata '@{}'", field.name),
+                        line: 0,
+                        column: 0,
+                        hint: Some(format!(
+                            "Add '@{} <{}>' to the config",
+                            field.name,
+                            field.kind.name()
+                        )),
+                        code: Some(RuneErrorCode::MissingMetadata.code()),
+                    });
+                }
+                None => {}
+            }
+        }
+
+        if errors.is_empty() {
+            Ok(())
+        } else {
+            Err(errors)
+        }
+    }
+
     pub fn validate_schema(&self, schema: &SchemaDocument) -> Vec<RuneDiagnostic> {
         let mut diagnostics = Vec::new();
 
@@ -94,7 +215,7 @@ impl RuneConfig {
                         diagnostics.push(type_diagnostic(
                             &block.root,
                             "object",
-                            &value_type_name(&value),
+                            value.type_name(),
                             &self.raw_content,
                         ));
                         continue;
@@ -148,14 +269,14 @@ fn validate_value(
         diagnostics.push(type_diagnostic(
             path,
             &field.kind.name(),
-            &value_type_name(value),
+            value.type_name(),
             &config.raw_content,
         ));
         return;
     }
 
-    if let (Some((min, max)), Value::Number(number)) = (field.range, value) {
-        if *number < min || *number > max {
+    if let (Some((min, max)), Some(number)) = (field.range, value.as_f64()) {
+        if number < min || number > max {
             diagnostics.push(
                 line_diagnostic(
                     path,
@@ -196,7 +317,7 @@ fn validate_value(
                                 path,
                                 index,
                                 inner.name(),
-                                value_type_name(item)
+                                item.type_name()
                             ),
                             &config.raw_content,
                         )
@@ -213,19 +334,36 @@ fn validate_value(
 }
 
 fn type_matches(kind: &SchemaType, value: &Value) -> bool {
-    match (kind, value) {
-        (SchemaType::Any, _) => true,
-        (SchemaType::String, Value::String(_)) => true,
-        (SchemaType::Int, Value::Number(number)) => number.fract() == 0.0,
-        (SchemaType::Float | SchemaType::Number, Value::Number(_)) => true,
-        (SchemaType::Bool, Value::Bool(_)) => true,
-        (SchemaType::Regex, Value::Regex(_)) => true,
-        (SchemaType::Null, Value::Null) => true,
-        (SchemaType::Array(_), Value::Array(_)) => true,
-        (SchemaType::Enum(_), Value::String(_)) => true,
-        (SchemaType::Object, Value::Object(_)) => true,
-        _ => false,
-    }
+    matches!(
+        (kind, value),
+        (SchemaType::Any, _)
+            | (SchemaType::String, Value::String(_))
+            | (SchemaType::Int, Value::Int(_))
+            | (
+                SchemaType::Float | SchemaType::Number,
+                Value::Int(_) | Value::Float(_)
+            )
+            | (SchemaType::Bool, Value::Bool(_))
+            | (SchemaType::Regex, Value::Regex(_))
+            | (SchemaType::Null, Value::Null)
+            | (SchemaType::Array(_), Value::Array(_))
+            | (SchemaType::Enum(_), Value::String(_))
+            | (SchemaType::Object, Value::Object(_))
+    )
+}
+
+/// Line of the `@name` metadata declaration, or 0 if it can't be found.
+fn metadata_line(name: &str, raw_content: &str) -> usize {
+    raw_content
+        .lines()
+        .position(|line| {
+            line.trim()
+                .strip_prefix('@')
+                .and_then(|rest| rest.strip_prefix(name))
+                .is_some_and(|rest| rest.is_empty() || rest.starts_with([' ', '\t', ':']))
+        })
+        .map(|idx| idx + 1)
+        .unwrap_or(0)
 }
 
 fn has_required_descendant(field: &SchemaField) -> bool {
@@ -301,19 +439,3 @@ fn line_diagnostic(path: &str, message: String, raw_content: &str) -> RuneDiagno
         diagnostic
     }
 }
-
-fn value_type_name(value: &Value) -> String {
-    match value {
-        Value::String(_) => "string".into(),
-        Value::Number(number) if number.fract() == 0.0 => "int".into(),
-        Value::Number(_) => "number".into(),
-        Value::Bool(_) => "bool".into(),
-        Value::Regex(_) => "regex".into(),
-        Value::Array(_) => "array".into(),
-        Value::Object(_) => "object".into(),
-        Value::Reference(_) => "reference".into(),
-        Value::Interpolated(_) => "interpolated".into(),
-        Value::Conditional(_) => "conditional".into(),
-        Value::Null => "null".into(),
-    }
-}
@@ -0,0 +1,98 @@
+// Author: Dustin Pilgrim
+// License: MIT
+
+use std::sync::mpsc::{self, RecvTimeoutError};
+use std::thread;
+use std::time::Duration;
+
+use notify::{RecommendedWatcher, RecursiveMode, Watcher};
+
+use super::*;
+
+/// How long a file must stay quiet before it is reloaded, so an editor's burst of
+/// writes triggers one reload.
+const WATCH_DEBOUNCE: Duration = Duration::from_millis(200);
+
+/// Keeps a [`RuneConfig::watch`] running; dropping it stops watching.
+pub struct ConfigWatcher {
+    _watcher: RecommendedWatcher,
+}
+
+fn watch_error(path: &Path, e: notify::Error) -> RuneError {
+    let message = format!("Failed to watch file: {}", e);
+    RuneError::FileError {
+        message,
+        path: path.to_string_lossy().to_string(),
+        hint: Some("Check that the file's directory exists and is readable".into()),
+        code: Some(RuneErrorCode::WatchFailed.code()),
+        source: match e.kind {
+            notify::ErrorKind::Io(io) => Some(io.into()),
+            _ => None,
+        },
+    }
+}
+
+impl RuneConfig {
+    /// Reload the config at `path` with `from_file` whenever it changes, passing the
+    /// new config, or the error that loading it gave, to `callback`.
+    ///
+    /// The file's directory is watched rather than the file, so editors that save by
+    /// replacing the file are seen too. Writes closer together than 200 ms are
+    /// debounced into one reload. The callback runs on a background thread until the
+    /// returned [`ConfigWatcher`] is dropped.
+    ///
+    /// # Example
+    /// ```no_run
+    /// # use rune_cfg::RuneConfig;
+    /// let _watcher = RuneConfig::watch("config.rune", |reloaded| match reloaded {
+    ///     Ok(config) => println!("port is now {}", config.get::<u16>("port").unwrap_or(0)),
+    ///     Err(e) => eprintln!("config not reloaded: {}", e),
+    /// })?;
+    /// # Ok::<(), rune_cfg::RuneError>(())
+    /// ```
+    pub fn watch<P, F>(path: P, mut callback: F) -> Result<ConfigWatcher, RuneError>
+    where
+        P: AsRef<Path>,
+        F: FnMut(Result<RuneConfig, RuneError>) + Send + 'static,
+    {
+        let path = path.as_ref().to_path_buf();
+        let dir = match path.parent() {
+            Some(dir) if !dir.as_os_str().is_empty() => dir.to_path_buf(),
+            _ => PathBuf::from("."),
+        };
+        let file_name = path.file_name().map(|name| name.to_os_string());
+
+        let (tx, rx) = mpsc::channel();
+        let mut watcher =
+            notify::recommended_watcher(move |event: notify::Result<notify::Event>| {
+                let Ok(event) = event else { return };
+                let touches_file = event
+                    .paths
+                    .iter()
+                    .any(|p| p.file_name().map(|n| n.to_os_string()) == file_name);
+                if touches_file && !event.kind.is_access() {
+                    let _ = tx.send(());
+                }
+            })
+            .map_err(|e| watch_error(&path, e))?;
+        watcher
+            .watch(&dir, RecursiveMode::NonRecursive)
+            .map_err(|e| watch_error(&path, e))?;
+
+        // The sender lives in the watcher, so this thread ends once it is dropped.
+        thread::spawn(move || {
+            while rx.recv().is_ok() {
+                loop {
+                    match rx.recv_timeout(WATCH_DEBOUNCE) {
+                        Ok(()) => continue,
+                        Err(RecvTimeoutError::Timeout) => break,
+                        Err(RecvTimeoutError::Disconnected) => return,
+                    }
+                }
+                callback(RuneConfig::from_file(&path));
+            }
+        });
+
+        Ok(ConfigWatcher { _watcher: watcher })
+    }
+}
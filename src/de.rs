@@ -0,0 +1,318 @@
+// Author: Dustin Pilgrim
+// License: MIT
+
+//! serde support: a resolved [`Value`] is a [`Deserializer`].
+//!
+//! Conversions follow the `TryFrom<Value>` impls: numeric and boolean strings (as
+//! `$env` gives) read as numbers and bools, and `kebab-case` keys fill `snake_case`
+//! struct fields.
+
+use std::fmt;
+
+use serde::de::{
+    self, DeserializeOwned, DeserializeSeed, Deserializer, EnumAccess, IntoDeserializer, MapAccess,
+    SeqAccess, VariantAccess, Visitor,
+};
+
+use crate::ast::{ObjectItem, Value};
+use crate::{RuneError, RuneErrorCode};
+
+/// Deserialize `T` from a resolved value, e.g. one returned by `RuneConfig::get_value`.
+pub fn from_value<T: DeserializeOwned>(value: Value) -> Result<T, RuneError> {
+    T::deserialize(value)
+}
+
+impl de::Error for RuneError {
+    fn custom<T: fmt::Display>(msg: T) -> Self {
+        RuneError::TypeError {
+            message: msg.to_string(),
+            line: 0,
+            column: 0,
+            hint: Some("Check that the config matches the shape of the target type".into()),
+            code: Some(RuneErrorCode::DeserializeFailed.code()),
+        }
+    }
+}
+
+fn unresolved(value: &Value) -> RuneError {
+    RuneError::TypeError {
+        message: format!("Cannot deserialize unresolved value {:?}", value),
+        line: 0,
+        column: 0,
+        hint: Some("Deserialize from RuneConfig, which resolves references first".into()),
+        code: Some(RuneErrorCode::DeserializeFailed.code()),
+    }
+}
+
+/// The assignments of an object; an if-block means the object was not resolved.
+fn object_entries(items: Vec<ObjectItem>) -> Result<Vec<(String, Value)>, RuneError> {
+    items
+        .into_iter()
+        .map(|item| match item {
+            ObjectItem::Assign(key, value) => Ok((key, value)),
+            ObjectItem::IfBlock(_) => Err(unresolved(&Value::Object(Vec::new()))),
+        })
+        .collect()
+}
+
+/// Integer targets go through the `TryFrom` impls so range checks and string
+/// parsing match `RuneConfig::get`.
+macro_rules! deserialize_integer {
+    ($($method:ident => $ty:ty, $visit:ident;)*) => {
+        $(
+            fn $method<V: Visitor<'de>>(self, visitor: V) -> Result<V::Value, RuneError> {
+                match self {
+                    Value::Int(_) | Value::Float(_) | Value::String(_) => {
+                        visitor.$visit(<$ty>::try_from(self)?)
+                    }
+                    other => other.deserialize_any(visitor),
+                }
+            }
+        )*
+    };
+}
+
+impl<'de> Deserializer<'de> for Value {
+    type Error = RuneError;
+
+    fn deserialize_any<V: Visitor<'de>>(self, visitor: V) -> Result<V::Value, RuneError> {
+        match self {
+            Value::Null => visitor.visit_unit(),
+            Value::Bool(b) => visitor.visit_bool(b),
+            Value::Int(n) => visitor.visit_i64(n),
+            Value::Float(n) => visitor.visit_f64(n),
+            Value::String(s) => visitor.visit_string(s),
+            Value::Regex(re) => visitor.visit_string(re.as_str().to_string()),
+            Value::Bytes(bytes) => visitor.visit_byte_buf(bytes),
+            #[cfg(feature = "chrono")]
+            Value::DateTime(timestamp) => visitor.visit_string(timestamp.to_rfc3339()),
+            Value::Array(values) => visitor.visit_seq(SeqDeserializer {
+                values: values.into_iter(),
+            }),
+            Value::Object(items) => visitor.visit_map(MapDeserializer {
+                entries: object_entries(items)?.into_iter(),
+                value: None,
+                fields: &[],
+            }),
+            other @ (Value::Reference(_)
+            | Value::Namespaced(_)
+            | Value::Interpolated(_)
+            | Value::Conditional(_)) => Err(unresolved(&other)),
+        }
+    }
+
+    deserialize_integer! {
+        deserialize_i8 => i8, visit_i8;
+        deserialize_i16 => i16, visit_i16;
+        deserialize_i32 => i32, visit_i32;
+        deserialize_i64 => i64, visit_i64;
+        deserialize_u8 => u8, visit_u8;
+        deserialize_u16 => u16, visit_u16;
+        deserialize_u32 => u32, visit_u32;
+        deserialize_u64 => u64, visit_u64;
+    }
+
+    fn deserialize_f32<V: Visitor<'de>>(self, visitor: V) -> Result<V::Value, RuneError> {
+        match self {
+            Value::Int(_) | Value::Float(_) | Value::String(_) => {
+                visitor.visit_f32(f32::try_from(self)?)
+            }
+            other => other.deserialize_any(visitor),
+        }
+    }
+
+    fn deserialize_f64<V: Visitor<'de>>(self, visitor: V) -> Result<V::Value, RuneError> {
+        match self {
+            Value::Int(_) | Value::Float(_) | Value::String(_) => {
+                visitor.visit_f64(f64::try_from(self)?)
+            }
+            other => other.deserialize_any(visitor),
+        }
+    }
+
+    fn deserialize_bool<V: Visitor<'de>>(self, visitor: V) -> Result<V::Value, RuneError> {
+        match self {
+            Value::String(_) => visitor.visit_bool(bool::try_from(self)?),
+            other => other.deserialize_any(visitor),
+        }
+    }
+
+    fn deserialize_option<V: Visitor<'de>>(self, visitor: V) -> Result<V::Value, RuneError> {
+        match self {
+            Value::Null => visitor.visit_none(),
+            other => visitor.visit_some(other),
+        }
+    }
+
+    fn deserialize_newtype_struct<V: Visitor<'de>>(
+        self,
+        _name: &'static str,
+        visitor: V,
+    ) -> Result<V::Value, RuneError> {
+        visitor.visit_newtype_struct(self)
+    }
+
+    fn deserialize_struct<V: Visitor<'de>>(
+        self,
+        _name: &'static str,
+        fields: &'static [&'static str],
+        visitor: V,
+    ) -> Result<V::Value, RuneError> {
+        match self {
+            Value::Object(items) => visitor.visit_map(MapDeserializer {
+                entries: object_entries(items)?.into_iter(),
+                value: None,
+                fields,
+            }),
+            other => other.deserialize_any(visitor),
+        }
+    }
+
+    fn deserialize_enum<V: Visitor<'de>>(
+        self,
+        _name: &'static str,
+        _variants: &'static [&'static str],
+        visitor: V,
+    ) -> Result<V::Value, RuneError> {
+        match self {
+            // `mode "fast"` is a unit variant.
+            Value::String(variant) => visitor.visit_enum(variant.into_deserializer()),
+            // `mode:` / `fast: ... end` / `end` is a variant with content.
+            Value::Object(items) => {
+                let mut entries = object_entries(items)?;
+                if entries.len() != 1 {
+                    return Err(de::Error::custom(format!(
+                        "Expected a block with exactly one key naming the variant, found {} keys",
+                        entries.len()
+                    )));
+                }
+                let (variant, value) = entries.remove(0);
+                visitor.visit_enum(EnumDeserializer { variant, value })
+            }
+            other => Err(de::Error::custom(format!(
+                "Expected a string or single-key block for an enum, got {:?}",
+                other
+            ))),
+        }
+    }
+
+    serde::forward_to_deserialize_any! {
+        i128 u128 char str string bytes byte_buf unit unit_struct seq tuple
+        tuple_struct map identifier ignored_any
+    }
+}
+
+struct SeqDeserializer {
+    values: std::vec::IntoIter<Value>,
+}
+
+impl<'de> SeqAccess<'de> for SeqDeserializer {
+    type Error = RuneError;
+
+    fn next_element_seed<T: DeserializeSeed<'de>>(
+        &mut self,
+        seed: T,
+    ) -> Result<Option<T::Value>, RuneError> {
+        self.values
+            .next()
+            .map(|value| seed.deserialize(value))
+            .transpose()
+    }
+
+    fn size_hint(&self) -> Option<usize> {
+        Some(self.values.len())
+    }
+}
+
+struct MapDeserializer {
+    entries: std::vec::IntoIter<(String, Value)>,
+    value: Option<Value>,
+    /// Struct field names, so `max-connections` can fill `max_connections`.
+    fields: &'static [&'static str],
+}
+
+impl<'de> MapAccess<'de> for MapDeserializer {
+    type Error = RuneError;
+
+    fn next_key_seed<K: DeserializeSeed<'de>>(
+        &mut self,
+        seed: K,
+    ) -> Result<Option<K::Value>, RuneError> {
+        let Some((key, value)) = self.entries.next() else {
+            return Ok(None);
+        };
+        self.value = Some(value);
+
+        let snake = key.replace('-', "_");
+        let key = if !self.fields.contains(&key.as_str()) && self.fields.contains(&snake.as_str()) {
+            snake
+        } else {
+            key
+        };
+        seed.deserialize(Value::String(key)).map(Some)
+    }
+
+    fn next_value_seed<V: DeserializeSeed<'de>>(&mut self, seed: V) -> Result<V::Value, RuneError> {
+        let value = self
+            .value
+            .take()
+            .ok_or_else(|| <RuneError as de::Error>::custom("value requested before key"))?;
+        seed.deserialize(value)
+    }
+
+    fn size_hint(&self) -> Option<usize> {
+        Some(self.entries.len())
+    }
+}
+
+struct EnumDeserializer {
+    variant: String,
+    value: Value,
+}
+
+impl<'de> EnumAccess<'de> for EnumDeserializer {
+    type Error = RuneError;
+    type Variant = Value;
+
+    fn variant_seed<V: DeserializeSeed<'de>>(
+        self,
+        seed: V,
+    ) -> Result<(V::Value, Value), RuneError> {
+        let variant = seed.deserialize(Value::String(self.variant))?;
+        Ok((variant, self.value))
+    }
+}
+
+impl<'de> VariantAccess<'de> for Value {
+    type Error = RuneError;
+
+    fn unit_variant(self) -> Result<(), RuneError> {
+        match self {
+            Value::Null => Ok(()),
+            other => Err(de::Error::custom(format!(
+                "Expected a unit variant, got {:?}",
+                other
+            ))),
+        }
+    }
+
+    fn newtype_variant_seed<T: DeserializeSeed<'de>>(self, seed: T) -> Result<T::Value, RuneError> {
+        seed.deserialize(self)
+    }
+
+    fn tuple_variant<V: Visitor<'de>>(
+        self,
+        _len: usize,
+        visitor: V,
+    ) -> Result<V::Value, RuneError> {
+        self.deserialize_any(visitor)
+    }
+
+    fn struct_variant<V: Visitor<'de>>(
+        self,
+        fields: &'static [&'static str],
+        visitor: V,
+    ) -> Result<V::Value, RuneError> {
+        self.deserialize_struct("", fields, visitor)
+    }
+}
@@ -2,6 +2,9 @@
 // License: MIT
 
 use std::fmt;
+use std::sync::Arc;
+
+use crate::lexer::Span;
 
 /// The main error type for RUNE parsing and lexing.
 #[derive(Debug, Clone, PartialEq)]
@@ -10,6 +13,8 @@ pub enum RuneError {
         message: String,
         line: usize,
         column: usize,
+        /// The offending token, when the parser knows it.
+        span: Option<Span>,
         hint: Option<String>,
         code: Option<u32>,
     },
@@ -55,6 +60,8 @@ pub enum RuneError {
         path: String,
         hint: Option<String>,
         code: Option<u32>,
+        /// The I/O error that caused this one, returned by `Error::source`.
+        source: Option<IoSource>,
     },
     /// Raised for runtime issues, such as missing environment variables.
     RuntimeError {
@@ -69,10 +76,23 @@ pub enum RuneError {
         hint: Option<String>,
         code: Option<u32>,
     },
+    /// An error parsing a file loaded from disk: the file given to `from_file` or one
+    /// it gathers. Its line and column point into `file`.
+    InFile {
+        file: String,
+        source: Box<RuneError>,
+    },
 }
 
 impl fmt::Display for RuneError {
     fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        self.write_in(f, None)
+    }
+}
+
+impl RuneError {
+    /// Write this error, naming `file` next to its line when it happened in one.
+    fn write_in(&self, f: &mut fmt::Formatter<'_>, file: Option<&str>) -> fmt::Result {
         match self {
             RuneError::SyntaxError {
                 message,
@@ -81,11 +101,12 @@ impl fmt::Display for RuneError {
                 code,
                 ..
             } => {
-                if *line > 0 {
-                    write!(f, "[RUNE] Syntax Error at line {}: {}", line, message)?;
-                } else {
-                    write!(f, "[RUNE] Syntax Error: {}", message)?;
-                }
+                write!(
+                    f,
+                    "[RUNE] Syntax Error{}: {}",
+                    location(file, *line),
+                    message
+                )?;
                 if let Some(h) = hint {
                     write!(f, " Hint: {}", h)?;
                 }
@@ -101,11 +122,12 @@ impl fmt::Display for RuneError {
                 code,
                 ..
             } => {
-                if *line > 0 {
-                    write!(f, "[RUNE] Invalid Token '{}' at line {}", token, line)?;
-                } else {
-                    write!(f, "[RUNE] Invalid Token '{}'", token)?;
-                }
+                write!(
+                    f,
+                    "[RUNE] Invalid Token '{}'{}",
+                    token,
+                    location(file, *line)
+                )?;
                 if let Some(h) = hint {
                     write!(f, " Hint: {}", h)?;
                 }
@@ -121,11 +143,12 @@ impl fmt::Display for RuneError {
                 code,
                 ..
             } => {
-                if *line > 0 {
-                    write!(f, "[RUNE] Unexpected EOF at line {}: {}", line, message)?;
-                } else {
-                    write!(f, "[RUNE] Unexpected EOF: {}", message)?;
-                }
+                write!(
+                    f,
+                    "[RUNE] Unexpected EOF{}: {}",
+                    location(file, *line),
+                    message
+                )?;
                 if let Some(h) = hint {
                     write!(f, " Hint: {}", h)?;
                 }
@@ -141,11 +164,7 @@ impl fmt::Display for RuneError {
                 code,
                 ..
             } => {
-                if *line > 0 {
-                    write!(f, "[RUNE] Type Error at line {}: {}", line, message)?;
-                } else {
-                    write!(f, "[RUNE] Type Error: {}", message)?;
-                }
+                write!(f, "[RUNE] Type Error{}: {}", location(file, *line), message)?;
                 if let Some(h) = hint {
                     write!(f, " Hint: {}", h)?;
                 }
@@ -161,15 +180,12 @@ impl fmt::Display for RuneError {
                 code,
                 ..
             } => {
-                if *line > 0 {
-                    write!(
-                        f,
-                        "[RUNE] Unclosed string starting with '{}' at line {}",
-                        quote, line
-                    )?;
-                } else {
-                    write!(f, "[RUNE] Unclosed string starting with '{}'", quote)?;
-                }
+                write!(
+                    f,
+                    "[RUNE] Unclosed string starting with '{}'{}",
+                    quote,
+                    location(file, *line)
+                )?;
                 if let Some(h) = hint {
                     write!(f, " Hint: {}", h)?;
                 }
@@ -185,15 +201,12 @@ impl fmt::Display for RuneError {
                 code,
                 ..
             } => {
-                if *line > 0 {
-                    write!(
-                        f,
-                        "[RUNE] Unexpected character '{}' at line {}",
-                        character, line
-                    )?;
-                } else {
-                    write!(f, "[RUNE] Unexpected character '{}'", character)?;
-                }
+                write!(
+                    f,
+                    "[RUNE] Unexpected character '{}'{}",
+                    character,
+                    location(file, *line)
+                )?;
                 if let Some(h) = hint {
                     write!(f, " Hint: {}", h)?;
                 }
@@ -207,6 +220,7 @@ impl fmt::Display for RuneError {
                 path,
                 hint,
                 code,
+                ..
             } => {
                 write!(f, "[RUNE] File Error '{}': {}", path, message)?;
                 if let Some(h) = hint {
@@ -222,7 +236,7 @@ impl fmt::Display for RuneError {
                 hint,
                 code,
             } => {
-                write!(f, "[RUNE] Runtime Error: {}", message)?;
+                write!(f, "[RUNE] Runtime Error{}: {}", location(file, 0), message)?;
                 if let Some(h) = hint {
                     write!(f, " Hint: {}", h)?;
                 }
@@ -237,7 +251,7 @@ impl fmt::Display for RuneError {
                 code,
                 ..
             } => {
-                write!(f, "{}", message)?;
+                write!(f, "{}{}", message, location(file, 0))?;
                 if let Some(h) = hint {
                     write!(f, "\nHint: {}", h)?;
                 }
@@ -246,8 +260,387 @@ impl fmt::Display for RuneError {
                 }
                 Ok(())
             }
+            RuneError::InFile { file, source } => source.write_in(f, Some(file)),
+        }
+    }
+}
+
+/// Where an error happened, as written after its kind: ` in 'config.rune' at line 2`,
+/// leaving out the file or line when it is not known.
+fn location(file: Option<&str>, line: usize) -> String {
+    let mut out = String::new();
+    if let Some(file) = file {
+        out.push_str(&format!(" in '{}'", file));
+    }
+    if line > 0 {
+        out.push_str(&format!(" at line {}", line));
+    }
+    out
+}
+
+impl std::error::Error for RuneError {
+    fn source(&self) -> Option<&(dyn std::error::Error + 'static)> {
+        match self {
+            RuneError::FileError {
+                source: Some(source),
+                ..
+            } => Some(source.error()),
+            RuneError::InFile { source, .. } => Some(source.as_ref()),
+            _ => None,
         }
     }
 }
 
-impl std::error::Error for RuneError {}
+impl RuneError {
+    /// The numeric code of this error, if it has one.
+    pub fn code(&self) -> Option<u32> {
+        match self {
+            RuneError::SyntaxError { code, .. }
+            | RuneError::InvalidToken { code, .. }
+            | RuneError::UnexpectedEof { code, .. }
+            | RuneError::TypeError { code, .. }
+            | RuneError::UnclosedString { code, .. }
+            | RuneError::UnexpectedCharacter { code, .. }
+            | RuneError::FileError { code, .. }
+            | RuneError::RuntimeError { code, .. }
+            | RuneError::ValidationError { code, .. } => *code,
+            RuneError::InFile { source, .. } => source.code(),
+        }
+    }
+
+    /// The hint on how to fix this error, if it has one.
+    pub fn hint(&self) -> Option<&str> {
+        match self {
+            RuneError::SyntaxError { hint, .. }
+            | RuneError::InvalidToken { hint, .. }
+            | RuneError::UnexpectedEof { hint, .. }
+            | RuneError::TypeError { hint, .. }
+            | RuneError::UnclosedString { hint, .. }
+            | RuneError::UnexpectedCharacter { hint, .. }
+            | RuneError::FileError { hint, .. }
+            | RuneError::RuntimeError { hint, .. }
+            | RuneError::ValidationError { hint, .. } => hint.as_deref(),
+            RuneError::InFile { source, .. } => source.hint(),
+        }
+    }
+
+    /// The file this error is in, when it was read from one.
+    pub fn file(&self) -> Option<&str> {
+        match self {
+            RuneError::InFile { file, .. } => Some(file),
+            _ => None,
+        }
+    }
+
+    /// This error tagged with the file it is in. Errors that already name a file
+    /// are returned as they are.
+    pub(crate) fn in_file(self, path: &std::path::Path) -> RuneError {
+        match self {
+            RuneError::InFile { .. } | RuneError::FileError { .. } => self,
+            error => RuneError::InFile {
+                file: path.to_string_lossy().to_string(),
+                source: Box::new(error),
+            },
+        }
+    }
+
+    /// The code of this error by name, for matching without magic numbers.
+    ///
+    /// # Example
+    /// ```
+    /// # use rune_cfg::{RuneConfig, RuneErrorCode};
+    /// let config = RuneConfig::from_str("port 8080\n").unwrap();
+    /// let err = config.get::<u16>("host").unwrap_err();
+    /// assert_eq!(err.error_code(), Some(RuneErrorCode::PathNotFound));
+    /// ```
+    pub fn error_code(&self) -> Option<RuneErrorCode> {
+        self.code().and_then(RuneErrorCode::from_code)
+    }
+}
+
+/// The I/O error behind a [`RuneError::FileError`], shared so errors stay cheap to
+/// clone. Two sources are equal when their error kinds are.
+#[derive(Debug, Clone)]
+pub struct IoSource(Arc<std::io::Error>);
+
+impl IoSource {
+    pub fn error(&self) -> &std::io::Error {
+        &self.0
+    }
+}
+
+impl From<std::io::Error> for IoSource {
+    fn from(error: std::io::Error) -> Self {
+        Self(Arc::new(error))
+    }
+}
+
+impl PartialEq for IoSource {
+    fn eq(&self, other: &Self) -> bool {
+        self.0.kind() == other.0.kind()
+    }
+}
+
+/// Names for the numeric codes carried by [`RuneError`]. The numbers are stable and
+/// match the `Code:` shown in error messages.
+///
+/// Codes are grouped by stage: 1xx lexing, 2xx parsing, 3xx loading and resolving,
+/// 4xx type conversion and validation, 5xx export, 6xx schemas.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+#[non_exhaustive]
+pub enum RuneErrorCode {
+    /// A number literal that does not parse.
+    InvalidNumber = 102,
+    /// A string, regex, or prefixed literal with no closing quote.
+    UnclosedString = 103,
+    /// A character that starts no token.
+    UnexpectedCharacter = 104,
+    /// A malformed `\u{...}` escape.
+    InvalidUnicodeEscape = 105,
+    /// An integer literal beyond `i64::MAX`.
+    IntegerOverflow = 106,
+
+    /// The input ended in the middle of a statement.
+    UnexpectedEof = 201,
+    /// A token other than the one the grammar requires.
+    UnexpectedToken = 202,
+    /// Metadata (`@key`) without a name.
+    InvalidMetadata = 203,
+    /// A token that cannot start a top-level statement.
+    InvalidTopLevel = 205,
+    /// A token that cannot appear in a block.
+    InvalidBlockEntry = 207,
+    /// An assignment without a key.
+    InvalidKey = 208,
+    /// A malformed `$` variable, such as `$env` with no name.
+    InvalidDollarVariable = 209,
+    /// A token that cannot start a value.
+    InvalidValue = 210,
+    /// A `$sys` reference with no key.
+    MissingSysKey = 211,
+    /// An unknown `$sys` key.
+    UnknownSysKey = 212,
+    /// A `$sys` value that cannot be read here.
+    SysValueUnavailable = 213,
+    /// A malformed `if` condition.
+    InvalidCondition = 214,
+    /// A block or inline object with no closing `end` or `}`.
+    UnclosedBlock = 215,
+    /// A `b64"..."` literal that is not valid base64.
+    InvalidBase64 = 216,
+    /// A `t"..."` literal that is not a timestamp.
+    InvalidTimestampLiteral = 217,
+    /// A bad `$date`/`$time`/`$datetime` format.
+    InvalidDateFormat = 218,
+    /// A malformed `const:` block.
+    InvalidConst = 219,
+    /// A key set twice at the same level.
+    DuplicateKey = 220,
+    /// A `gather` without a quoted file name.
+    MissingGatherPath = 221,
+    /// An `r"..."` literal that does not compile.
+    InvalidRegexLiteral = 222,
+    /// A `gather ... as` without an alias.
+    InvalidGatherAlias = 223,
+    /// A `gather ... as *` inside a block.
+    ScopedWildcardGather = 224,
+    /// A `$` variable written where a key goes.
+    DollarVariableAsKey = 225,
+    /// A `$sys.load_average` window other than one, five or fifteen.
+    UnknownLoadAverageWindow = 226,
+    /// An `else` with no `if` before it.
+    UnexpectedElse = 227,
+    /// A reference with an empty segment, such as `app.` or `$var.`.
+    InvalidReference = 228,
+    /// A timestamp literal or clock variable used without the `chrono` feature.
+    ChronoDisabled = 229,
+    /// A `:-default` or `!` on a variable other than `$env`.
+    UnsupportedEnvModifier = 230,
+
+    /// A file path that cannot be resolved or read.
+    FileUnavailable = 300,
+    /// A config or `$file` that cannot be read.
+    FileRead = 301,
+    /// A gathered file that cannot be read.
+    ImportRead = 302,
+    /// An internal parser could not be created.
+    ParserUnavailable = 303,
+    /// A path that is not in the config.
+    PathNotFound = 304,
+    /// The config has no main document.
+    NoMainDocument = 305,
+    /// Keys were asked for at a path that is not a block.
+    NotAnObject = 306,
+    /// An `$env` variable that is not set.
+    EnvVarNotSet = 308,
+    /// A `$var` that names no key.
+    VariableNotFound = 309,
+    /// A `$file` over the inline size limit.
+    FileTooLarge = 310,
+    /// A `$runtime` value that was never set.
+    RuntimeValueNotSet = 311,
+    /// A `$const` that is not defined.
+    UndefinedConstant = 312,
+    /// References that refer back to themselves.
+    CircularReference = 313,
+    /// An environment override that matches no key, in strict mode.
+    UnknownEnvOverride = 314,
+    /// A path that `set` cannot store a value at.
+    SetFailed = 315,
+    /// A config file that cannot be watched.
+    WatchFailed = 316,
+    /// Gathered files that import each other in a loop.
+    ImportCycle = 317,
+    /// An `$env.NAME!` variable that is not set.
+    RequiredEnvVarNotSet = 318,
+    /// Config bytes that are not UTF-8.
+    InvalidUtf8 = 319,
+
+    /// A value that is not a string.
+    ExpectedString = 401,
+    /// A value that is not a number or an integer.
+    ExpectedNumber = 402,
+    /// A value that is not a boolean.
+    ExpectedBool = 404,
+    /// A value that is not an array.
+    ExpectedArray = 405,
+    /// A number that does not fit the requested type.
+    OutOfRange = 407,
+    /// A value that is not a block of plain assignments.
+    ExpectedObject = 410,
+    /// An array that is not a key/value pair.
+    ExpectedPair = 411,
+    /// A value that is not a timestamp.
+    ExpectedTimestamp = 412,
+    /// A value that does not fit the serde target type.
+    DeserializeFailed = 413,
+    /// An environment override that does not convert to the type it replaces.
+    InvalidEnvOverride = 414,
+    /// A value that is not a duration.
+    InvalidDuration = 415,
+    /// A value that is not an IP or socket address.
+    InvalidAddress = 416,
+    /// A value that is not a regex.
+    ExpectedRegex = 417,
+    /// A regex pattern that does not compile.
+    InvalidPattern = 418,
+    /// An array or block interpolated into a string.
+    NonScalarInterpolation = 419,
+    /// A value rejected by a validator.
+    InvalidValueForKey = 450,
+    /// A string that is not one of the allowed values.
+    NotAllowedValue = 451,
+    /// A number outside the range `get_in_range` allows.
+    NotInRange = 452,
+    /// A string the pattern given to `get_matching` does not match.
+    NoPatternMatch = 453,
+    /// Required metadata that is missing.
+    MissingMetadata = 455,
+    /// Metadata of the wrong type.
+    InvalidMetadataType = 456,
+
+    /// A document that cannot be written in the requested format.
+    ExportFailed = 500,
+    /// A value the requested format has no way to represent.
+    UnsupportedExport = 501,
+    /// JSON input that does not parse.
+    InvalidJson = 502,
+
+    /// A schema that does not parse.
+    InvalidSchema = 600,
+    /// A schema file that cannot be read.
+    SchemaFileRead = 601,
+}
+
+impl RuneErrorCode {
+    /// The stable numeric code.
+    pub fn code(self) -> u32 {
+        self as u32
+    }
+
+    /// The name for a numeric code, or `None` for a code this version does not know.
+    pub fn from_code(code: u32) -> Option<Self> {
+        use RuneErrorCode::*;
+        Some(match code {
+            102 => InvalidNumber,
+            103 => UnclosedString,
+            104 => UnexpectedCharacter,
+            105 => InvalidUnicodeEscape,
+            106 => IntegerOverflow,
+            201 => UnexpectedEof,
+            202 => UnexpectedToken,
+            203 => InvalidMetadata,
+            205 => InvalidTopLevel,
+            207 => InvalidBlockEntry,
+            208 => InvalidKey,
+            209 => InvalidDollarVariable,
+            210 => InvalidValue,
+            211 => MissingSysKey,
+            212 => UnknownSysKey,
+            213 => SysValueUnavailable,
+            214 => InvalidCondition,
+            215 => UnclosedBlock,
+            216 => InvalidBase64,
+            217 => InvalidTimestampLiteral,
+            218 => InvalidDateFormat,
+            219 => InvalidConst,
+            220 => DuplicateKey,
+            221 => MissingGatherPath,
+            222 => InvalidRegexLiteral,
+            223 => InvalidGatherAlias,
+            224 => ScopedWildcardGather,
+            225 => DollarVariableAsKey,
+            226 => UnknownLoadAverageWindow,
+            227 => UnexpectedElse,
+            228 => InvalidReference,
+            229 => ChronoDisabled,
+            230 => UnsupportedEnvModifier,
+            300 => FileUnavailable,
+            301 => FileRead,
+            302 => ImportRead,
+            303 => ParserUnavailable,
+            304 => PathNotFound,
+            305 => NoMainDocument,
+            306 => NotAnObject,
+            308 => EnvVarNotSet,
+            309 => VariableNotFound,
+            310 => FileTooLarge,
+            311 => RuntimeValueNotSet,
+            312 => UndefinedConstant,
+            313 => CircularReference,
+            314 => UnknownEnvOverride,
+            315 => SetFailed,
+            316 => WatchFailed,
+            317 => ImportCycle,
+            318 => RequiredEnvVarNotSet,
+            319 => InvalidUtf8,
+            401 => ExpectedString,
+            402 => ExpectedNumber,
+            404 => ExpectedBool,
+            405 => ExpectedArray,
+            407 => OutOfRange,
+            410 => ExpectedObject,
+            411 => ExpectedPair,
+            412 => ExpectedTimestamp,
+            413 => DeserializeFailed,
+            414 => InvalidEnvOverride,
+            415 => InvalidDuration,
+            416 => InvalidAddress,
+            417 => ExpectedRegex,
+            418 => InvalidPattern,
+            419 => NonScalarInterpolation,
+            450 => InvalidValueForKey,
+            451 => NotAllowedValue,
+            452 => NotInRange,
+            453 => NoPatternMatch,
+            455 => MissingMetadata,
+            456 => InvalidMetadataType,
+            500 => ExportFailed,
+            501 => UnsupportedExport,
+            502 => InvalidJson,
+            600 => InvalidSchema,
+            601 => SchemaFileRead,
+            _ => return None,
+        })
+    }
+}
@@ -1,20 +1,48 @@
 // Author: Dustin Pilgrim
 // License: MIT
 
-use serde_json::json;
+use std::collections::HashSet;
 use std::fs;
 
-use crate::RuneError;
-use crate::ast::Document;
+use base64::Engine;
+use base64::engine::general_purpose::STANDARD as BASE64;
+
+use crate::ast::{Document, ObjectItem, Value};
 use crate::parser::Parser;
+use crate::ser::Resolved;
+use crate::{ResolverContext, RuneConfig, RuneError, RuneErrorCode};
+
+/// Write a document back out as RUNE source, e.g. after building or editing it.
+///
+/// Metadata comes first as `@key value` lines, then constants, globals and blocks in
+/// source order, with arrays one element per line. Strings are quoted and escaped so
+/// the lexer reads them back unchanged, and parsing the output gives an equal
+/// document. Comments are not kept, as the document does not hold them.
+///
+/// # Examples
+/// ```
+/// use rune_cfg::{export::to_rune_string, parser::Parser};
+///
+/// let doc = Parser::new("app:\n  tags [\"a\", \"b\"]\nend\n")
+///     .unwrap()
+///     .parse_document()
+///     .unwrap();
+/// assert_eq!(to_rune_string(&doc), "app:\n  tags [\n    \"a\"\n    \"b\"\n  ]\nend\n");
+/// ```
+pub fn to_rune_string(doc: &Document) -> String {
+    format!("{:#}", doc)
+}
 
 /// Export a RUNE document to JSON format.
 ///
-/// Converts all RUNE values to their JSON equivalents:
+/// Uses the document's [`serde::Serialize`] impl, which writes values as they were
+/// parsed:
 /// - Strings, numbers, booleans → direct mapping
 /// - Arrays, objects → nested JSON structures
 /// - Regex → `{"regex": "pattern"}`
-/// - References → dotted string path
+/// - Bytes → base64 string
+/// - DateTime → RFC 3339 string
+/// - References → dotted string path, `$`-prefixed for `$namespace` ones
 /// - Conditionals → structured object with condition/then/else
 /// - Null → JSON null
 ///
@@ -34,101 +62,270 @@ use crate::parser::Parser;
 /// # }
 /// ```
 pub fn export_document_to_json(doc: &Document) -> Result<String, RuneError> {
-    fn value_to_json(v: &crate::ast::Value) -> serde_json::Value {
-        match v {
-            crate::ast::Value::String(s) => json!(s),
-            crate::ast::Value::Number(n) => json!(n),
-            crate::ast::Value::Bool(b) => json!(b),
-            crate::ast::Value::Array(arr) => {
-                json!(arr.iter().map(value_to_json).collect::<Vec<_>>())
-            }
-            crate::ast::Value::Object(obj) => object_items_to_json(obj),
-            crate::ast::Value::Reference(path) => json!(path.join(".")),
-            crate::ast::Value::Interpolated(parts) => {
-                json!(parts.iter().map(value_to_json).collect::<Vec<_>>())
+    serde_json::to_string_pretty(doc).map_err(|e| RuneError::RuntimeError {
+        message: format!("Failed to export document to JSON: {}", e),
+        hint: None,
+        code: Some(RuneErrorCode::ExportFailed.code()),
+    })
+}
+
+/// Export a config to JSON with every value resolved.
+///
+/// References, conditionals, `$env` and `$sys` are expanded as `RuneConfig::get_value`
+/// would, so the output holds only concrete values. Globals and top-level blocks
+/// become the keys of one JSON object, and blocks are written as plain objects in
+/// the order of their keys.
+///
+/// # Examples
+/// ```
+/// use rune_cfg::{RuneConfig, export::export_resolved_to_json};
+///
+/// let config = RuneConfig::from_str("host \"localhost\"\nserver:\n  host host\nend\n").unwrap();
+/// let json = export_resolved_to_json(&config).unwrap();
+///
+/// let value: serde_json::Value = serde_json::from_str(&json).unwrap();
+/// assert_eq!(value["server"]["host"], "localhost");
+/// ```
+pub fn export_resolved_to_json(config: &RuneConfig) -> Result<String, RuneError> {
+    let resolved = config.get_value("")?;
+    serde_json::to_string_pretty(&Resolved(&resolved)).map_err(|e| RuneError::RuntimeError {
+        message: format!("Failed to export config to JSON: {}", e),
+        hint: None,
+        code: Some(RuneErrorCode::ExportFailed.code()),
+    })
+}
+
+/// Export a RUNE document to TOML format.
+///
+/// Globals become top-level keys, written first as TOML requires, and blocks become
+/// tables after them. Values are written unresolved, as by [`export_document_to_json`]:
+/// references as their dotted path, regexes as `{ regex = "pattern" }` and bytes as
+/// base64. Timestamps become TOML datetimes. Metadata and constants are not written.
+///
+/// # Errors
+/// Returns a `RuntimeError` naming the key for what TOML cannot hold: `null`, arrays
+/// mixing types, and conditionals or `if` blocks, which need the config resolved first.
+///
+/// # Examples
+/// ```
+/// use rune_cfg::{export::export_document_to_toml, parser::Parser};
+///
+/// let doc = Parser::new("name \"demo\"\napp:\n  port 8080\nend\n")
+///     .unwrap()
+///     .parse_document()
+///     .unwrap();
+/// let toml = export_document_to_toml(&doc).unwrap();
+/// assert!(toml.contains("[app]"));
+/// ```
+#[cfg(feature = "toml")]
+pub fn export_document_to_toml(doc: &Document) -> Result<String, RuneError> {
+    let mut table = toml::Table::new();
+    for (key, value) in doc.globals.iter().chain(&doc.items) {
+        table.insert(key.clone(), value_to_toml(value, key)?);
+    }
+    toml::to_string(&table).map_err(|e| RuneError::RuntimeError {
+        message: format!("Failed to export document to TOML: {}", e),
+        hint: None,
+        code: Some(RuneErrorCode::ExportFailed.code()),
+    })
+}
+
+/// The TOML form of `value`, found at `path`, which is only used for errors.
+#[cfg(feature = "toml")]
+fn value_to_toml(value: &Value, path: &str) -> Result<toml::Value, RuneError> {
+    let unsupported = |what: &str, hint: &str| RuneError::RuntimeError {
+        message: format!("Cannot export '{}' to TOML: {}", path, what),
+        hint: Some(hint.into()),
+        code: Some(RuneErrorCode::UnsupportedExport.code()),
+    };
+    let resolve_first = "Export the resolved config, which evaluates conditionals";
+
+    Ok(match value {
+        Value::String(s) => toml::Value::String(s.clone()),
+        Value::Int(n) => toml::Value::Integer(*n),
+        Value::Float(n) => toml::Value::Float(*n),
+        Value::Bool(b) => toml::Value::Boolean(*b),
+        Value::Reference(segs) => toml::Value::String(segs.join(".")),
+        Value::Namespaced(segs) => toml::Value::String(format!("${}", segs.join("."))),
+        Value::Regex(re) => {
+            let mut table = toml::Table::new();
+            table.insert("regex".into(), toml::Value::String(re.as_str().into()));
+            toml::Value::Table(table)
+        }
+        Value::Bytes(bytes) => toml::Value::String(BASE64.encode(bytes)),
+        #[cfg(feature = "chrono")]
+        Value::DateTime(timestamp) => timestamp
+            .to_rfc3339()
+            .parse()
+            .map(toml::Value::Datetime)
+            .map_err(|_| unsupported("timestamp out of TOML's range", "Use a string instead"))?,
+        Value::Interpolated(parts) => toml::Value::String(crate::ast::interpolated_text(parts)),
+        Value::Array(values) => {
+            let values = values
+                .iter()
+                .enumerate()
+                .map(|(i, v)| value_to_toml(v, &format!("{}.{}", path, i)))
+                .collect::<Result<Vec<_>, _>>()?;
+            if let Some(first) = values.first()
+                && let Some(other) = values.iter().find(|v| v.type_str() != first.type_str())
+            {
+                return Err(unsupported(
+                    &format!(
+                        "array mixes {} and {} values",
+                        first.type_str(),
+                        other.type_str()
+                    ),
+                    "Use values of one type in each array",
+                ));
             }
-            crate::ast::Value::Regex(r) => json!({ "regex": r.as_str() }),
-            crate::ast::Value::Conditional(c) => {
-                json!({
-                    "conditional": {
-                        "condition": format!("{:?}", c.condition),
-                        "then": value_to_json(&c.then_value),
-                        "else": c.else_value.as_ref().map(value_to_json)
-                    }
-                })
+            toml::Value::Array(values)
+        }
+        Value::Object(items) => {
+            let mut table = toml::Table::new();
+            for item in items {
+                let ObjectItem::Assign(key, v) = item else {
+                    return Err(unsupported("block contains an `if` block", resolve_first));
+                };
+                table.insert(key.clone(), value_to_toml(v, &format!("{}.{}", path, key))?);
             }
-            crate::ast::Value::Null => serde_json::Value::Null,
+            toml::Value::Table(table)
+        }
+        Value::Conditional(_) => return Err(unsupported("value is a conditional", resolve_first)),
+        Value::Null => {
+            return Err(unsupported(
+                "TOML has no null",
+                "Remove the key or give it a value",
+            ));
+        }
+    })
+}
+
+/// Export a RUNE document as `.env` lines, `KEY=VALUE`, for container deployments.
+///
+/// References and conditionals are resolved first. Each leaf becomes one line keyed
+/// by its path in `UPPER_SNAKE` case, joined by `_`, so `app.server.host` becomes
+/// `APP_SERVER_HOST` and `log-level` becomes `LOG_LEVEL`. Arrays of plain values are
+/// joined with commas, `hosts ["a" "b"]` giving `HOSTS=a,b`, which is the form
+/// `RuneConfig::apply_env_overrides` reads back. `null` gives an empty value.
+///
+/// Values with anything but letters, digits and `_ . / : , @ % + -` are single-quoted
+/// for the shell, e.g. `GREETING='hello world'`.
+///
+/// # Errors
+/// Returns a `RuntimeError` naming the key for arrays holding objects or arrays, array
+/// elements containing a comma, and two paths giving the same variable name.
+///
+/// # Examples
+/// ```
+/// use rune_cfg::{export::export_document_to_env, parser::Parser};
+///
+/// let doc = Parser::new("host \"localhost\"\napp:\n  server:\n    host host\n  end\nend\n")
+///     .unwrap()
+///     .parse_document()
+///     .unwrap();
+/// let env = export_document_to_env(&doc).unwrap();
+/// assert_eq!(env, "HOST=localhost\nAPP_SERVER_HOST=localhost\n");
+/// ```
+pub fn export_document_to_env(doc: &Document) -> Result<String, RuneError> {
+    let config = RuneConfig::from_document(doc.clone(), ResolverContext::new());
+    let resolved = config.get_value("")?;
+
+    let mut lines = Vec::new();
+    flatten_to_env(&resolved, "", "", &mut lines)?;
+
+    let mut names = HashSet::new();
+    let mut out = String::new();
+    for (name, path, text) in lines {
+        if !names.insert(name.clone()) {
+            return Err(RuneError::RuntimeError {
+                message: format!(
+                    "Cannot export '{}' to .env: another key also becomes {}",
+                    path, name
+                ),
+                hint: Some("Rename one of the keys so their variable names differ".into()),
+                code: Some(RuneErrorCode::UnsupportedExport.code()),
+            });
         }
+        out.push_str(&format!("{}={}\n", name, shell_quote(&text)));
     }
+    Ok(out)
+}
 
-    fn object_items_to_json(items: &[crate::ast::ObjectItem]) -> serde_json::Value {
-        use crate::ast::ObjectItem;
-
-        // Keep ordering stable & preserve if-blocks.
-        // Represent as an array of entries so we don't lose duplicates or structure.
-        //
-        // Example:
-        // [
-        //   {"key":"timeout","value":60},
-        //   {"if":{...}}
-        // ]
-        let mut out: Vec<serde_json::Value> = Vec::new();
-
-        for item in items {
-            match item {
-                ObjectItem::Assign(k, v) => {
-                    out.push(json!({
-                        "key": k,
-                        "value": value_to_json(v)
-                    }));
+/// Collect `(NAME, path, text)` for each leaf under `value`, found at `path` and named
+/// `name` so far.
+fn flatten_to_env(
+    value: &Value,
+    name: &str,
+    path: &str,
+    out: &mut Vec<(String, String, String)>,
+) -> Result<(), RuneError> {
+    let unsupported = |what: String| RuneError::RuntimeError {
+        message: format!("Cannot export '{}' to .env: {}", path, what),
+        hint: Some("Only plain values and arrays of them can be flattened".into()),
+        code: Some(RuneErrorCode::UnsupportedExport.code()),
+    };
+
+    let text = match value {
+        Value::Object(items) => {
+            for item in items {
+                if let ObjectItem::Assign(key, v) = item {
+                    let part = key.to_uppercase().replace(['-', '.'], "_");
+                    let name = if name.is_empty() {
+                        part
+                    } else {
+                        format!("{}_{}", name, part)
+                    };
+                    flatten_to_env(v, &name, &crate::utils::join_path(path, key), out)?;
                 }
-                ObjectItem::IfBlock(block) => {
-                    out.push(json!({
-                        "if": if_block_to_json(block)
-                    }));
+            }
+            return Ok(());
+        }
+        Value::Array(values) => {
+            let mut parts = Vec::with_capacity(values.len());
+            for v in values {
+                if matches!(v, Value::Object(_) | Value::Array(_)) {
+                    return Err(unsupported("array holds objects or arrays".into()));
                 }
+                let part = env_text(v);
+                if part.contains(',') {
+                    return Err(unsupported(format!(
+                        "array element '{}' contains a comma",
+                        part
+                    )));
+                }
+                parts.push(part);
             }
+            parts.join(",")
         }
+        other => env_text(other),
+    };
+    out.push((name.to_string(), path.to_string(), text));
+    Ok(())
+}
 
-        serde_json::Value::Array(out)
-    }
-
-    fn if_block_to_json(block: &crate::ast::IfBlock) -> serde_json::Value {
-        json!({
-            "condition": format!("{:?}", block.condition),
-            "then": object_items_to_json(&block.then_items),
-            "else": block.else_items.as_ref().map(|items| object_items_to_json(items)),
-        })
-    }
-
-    let mut top = serde_json::Map::new();
-
-    let metadata = doc
-        .metadata
-        .iter()
-        .map(|(k, v)| (k.clone(), value_to_json(v)))
-        .collect::<serde_json::Map<_, _>>();
-    if !metadata.is_empty() {
-        top.insert("metadata".into(), serde_json::Value::Object(metadata));
+/// The text of a resolved plain value.
+fn env_text(value: &Value) -> String {
+    match value {
+        Value::String(s) => s.clone(),
+        Value::Regex(re) => re.as_str().to_string(),
+        Value::Bytes(bytes) => BASE64.encode(bytes),
+        #[cfg(feature = "chrono")]
+        Value::DateTime(timestamp) => timestamp.to_rfc3339(),
+        Value::Null => String::new(),
+        other => other.to_string(),
     }
+}
 
-    let globals = doc
-        .globals
-        .iter()
-        .map(|(k, v)| (k.clone(), value_to_json(v)))
-        .collect::<serde_json::Map<_, _>>();
-    if !globals.is_empty() {
-        top.insert("globals".into(), serde_json::Value::Object(globals));
+/// `text` as is when the shell reads it back unchanged, single-quoted otherwise.
+fn shell_quote(text: &str) -> String {
+    let plain = text
+        .chars()
+        .all(|c| c.is_ascii_alphanumeric() || "_./:,@%+-".contains(c));
+    if plain {
+        text.to_string()
+    } else {
+        format!("'{}'", text.replace('\'', "'\\''"))
     }
-
-    let items = doc
-        .items
-        .iter()
-        .map(|(k, v)| (k.clone(), value_to_json(v)))
-        .collect::<serde_json::Map<_, _>>();
-    top.insert("items".into(), serde_json::Value::Object(items));
-
-    Ok(serde_json::to_string_pretty(&serde_json::Value::Object(top)).unwrap())
 }
 
 /// Export a RUNE file directly to JSON.
@@ -149,17 +346,56 @@ pub fn export_document_to_json(doc: &Document) -> Result<String, RuneError> {
 /// # Errors
 /// Returns error if file doesn't exist or contains invalid RUNE syntax.
 pub fn export_rune_file(path: &str) -> Result<String, RuneError> {
+    export_document_to_json(&parse_rune_file(path)?)
+}
+
+/// Export a RUNE document to YAML format.
+///
+/// The YAML has the same shape as [`export_document_to_json`]: `metadata`,
+/// `constants` and `globals` when not empty, then an `items` mapping. Regexes are
+/// written as `regex: pattern` mappings and `null` as YAML `null`.
+///
+/// # Examples
+/// ```
+/// use rune_cfg::{export::export_document_to_yaml, parser::Parser};
+///
+/// let doc = Parser::new("app:\n  port 8080\nend\n")
+///     .unwrap()
+///     .parse_document()
+///     .unwrap();
+/// let yaml = export_document_to_yaml(&doc).unwrap();
+/// assert!(yaml.starts_with("items:"));
+/// ```
+#[cfg(feature = "yaml")]
+pub fn export_document_to_yaml(doc: &Document) -> Result<String, RuneError> {
+    serde_yaml::to_string(doc).map_err(|e| RuneError::RuntimeError {
+        message: format!("Failed to export document to YAML: {}", e),
+        hint: None,
+        code: Some(RuneErrorCode::ExportFailed.code()),
+    })
+}
+
+/// Export a RUNE file directly to YAML, like [`export_rune_file`] does to JSON.
+///
+/// # Errors
+/// Returns error if file doesn't exist or contains invalid RUNE syntax.
+#[cfg(feature = "yaml")]
+pub fn export_rune_file_yaml(path: &str) -> Result<String, RuneError> {
+    export_document_to_yaml(&parse_rune_file(path)?)
+}
+
+fn parse_rune_file(path: &str) -> Result<Document, RuneError> {
     let input = fs::read_to_string(path).map_err(|e| RuneError::SyntaxError {
         message: format!("Failed to read file: {}", e),
         line: 0,
         column: 0,
+        span: None,
         hint: None,
-        code: Some(500),
+        code: Some(RuneErrorCode::FileRead.code()),
     })?;
 
     let mut parser = Parser::new(&input)?;
-    let doc = parser.parse_document()?;
-    export_document_to_json(&doc)
+    parser.parse_document()
 }
 
 #[cfg(test)]
@@ -198,6 +434,210 @@ mod tests {
         assert!(deserialized.get("metadata").is_some());
     }
 
+    #[test]
+    fn test_export_resolved_expands_references() {
+        let config = RuneConfig::from_file("examples/example.rune").unwrap();
+
+        let json_output = export_resolved_to_json(&config).unwrap();
+        let v: serde_json::Value = serde_json::from_str(&json_output).unwrap();
+
+        assert_eq!(v["app"]["server"]["host"], "localhost");
+        assert_eq!(v["app"]["name"], "RuneApp");
+        assert_eq!(v["app"]["plugins"][1], "logger");
+        assert_eq!(v["app"]["server"]["port"], 8080);
+        assert!(v["path"].is_string());
+
+        // The unresolved export keeps the reference path.
+        assert!(
+            export_document_to_json(config.document().unwrap())
+                .unwrap()
+                .contains("defaults.server.host")
+        );
+    }
+
+    #[cfg(feature = "toml")]
+    #[test]
+    fn test_export_toml_puts_tables_after_globals() {
+        let mut parser = Parser::new(
+            "app:\n  port 8080\n  host name\n  server:\n    tags [\"a\" \"b\"]\n  end\nend\nname \"demo\"\nratio 0.5\n",
+        )
+        .unwrap();
+        let doc = parser.parse_document().unwrap();
+
+        let toml_output = export_document_to_toml(&doc).unwrap();
+        assert!(toml_output.find("name").unwrap() < toml_output.find("[app]").unwrap());
+
+        let v: toml::Table = toml_output.parse().unwrap();
+        assert_eq!(v["name"].as_str(), Some("demo"));
+        assert_eq!(v["ratio"].as_float(), Some(0.5));
+        assert_eq!(v["app"]["port"].as_integer(), Some(8080));
+        // References are written as their path, as in JSON.
+        assert_eq!(v["app"]["host"].as_str(), Some("name"));
+        assert_eq!(v["app"]["server"]["tags"][1].as_str(), Some("b"));
+    }
+
+    #[cfg(feature = "toml")]
+    #[test]
+    fn test_export_toml_rejects_unsupported_values() {
+        for (input, fragment) in [
+            ("app:\n  mixed [1 \"two\"]\nend\n", "'app.mixed'"),
+            ("app:\n  proxy null\nend\n", "no null"),
+            (
+                "mode \"dev\"\napp:\n  if mode = \"dev\":\n    debug true\n  endif\nend\n",
+                "`if` block",
+            ),
+        ] {
+            let doc = Parser::new(input).unwrap().parse_document().unwrap();
+            match export_document_to_toml(&doc) {
+                Err(RuneError::RuntimeError { message, code, .. }) => {
+                    assert_eq!(code, Some(501));
+                    assert!(message.contains(fragment), "{}", message);
+                }
+                other => panic!("expected an export error for {:?}, got {:?}", input, other),
+            }
+        }
+    }
+
+    #[cfg(feature = "yaml")]
+    #[test]
+    fn test_export_rune_file_to_yaml() {
+        let yaml_output = export_rune_file_yaml("examples/example.rune").unwrap();
+        let v: serde_yaml::Value = serde_yaml::from_str(&yaml_output).unwrap();
+
+        let items = v["items"].as_mapping().expect("items mapping");
+        assert!(items.contains_key("app"));
+        assert_eq!(v["globals"]["name"], "RuneApp");
+        assert_eq!(v["metadata"]["description"], "Simple app using RUNE config");
+
+        let doc = Parser::new("pattern r\"^a+$\"\nproxy null\n")
+            .unwrap()
+            .parse_document()
+            .unwrap();
+        let v: serde_yaml::Value =
+            serde_yaml::from_str(&export_document_to_yaml(&doc).unwrap()).unwrap();
+        assert_eq!(v["globals"]["pattern"]["regex"], "^a+$");
+        assert!(v["globals"]["proxy"].is_null());
+    }
+
+    fn parse(input: &str) -> Document {
+        Parser::new(input)
+            .expect("lexer")
+            .parse_document()
+            .expect("valid RUNE")
+    }
+
+    #[test]
+    fn test_to_rune_string_round_trips() {
+        let source = r#"
+@description "Round trip"
+const:
+  base 8000
+end
+name "demo"
+app:
+  port 8080
+  ratio 0.5
+  owner name
+  pattern r"^[a-z]+\d$"
+  server:
+    tls:
+      enabled true
+      cert null
+    end
+  end
+  hosts [ "a" "b" ]
+  empty []
+  backends [
+    { host "a.example.com" port 1 }
+    { host "b" tags [1 2] nested { deep true } }
+  ]
+  message "say \"hi\"\n\ttab \\ back \$literal \u{263A}"
+end
+"#;
+        let doc = parse(source);
+        let rendered = to_rune_string(&doc);
+
+        assert!(rendered.starts_with("@description \"Round trip\"\n"));
+        assert!(rendered.contains("  server:\n    tls:\n      enabled true\n"));
+        assert!(rendered.contains("  hosts [\n    \"a\"\n    \"b\"\n  ]\n"));
+        assert!(rendered.contains("  empty []\n"));
+        assert!(rendered.contains("    { host \"b\" tags [1, 2] nested { deep true } }\n"));
+        assert!(rendered.contains(r#"message "say \"hi\"\n\ttab \\ back \$literal ☺""#));
+
+        assert_eq!(parse(&rendered), doc);
+        // Rendering is stable once round-tripped.
+        assert_eq!(to_rune_string(&parse(&rendered)), rendered);
+    }
+
+    #[test]
+    fn test_to_rune_string_escapes_dollars_in_code_built_strings() {
+        let doc = crate::DocumentBuilder::new()
+            .global("price", "$5")
+            .global("template", "$var.name and $env.HOME")
+            .build();
+        let rendered = to_rune_string(&doc);
+
+        assert!(rendered.contains(r#"price "\$5""#), "{}", rendered);
+        assert_eq!(parse(&rendered), doc);
+
+        let mut config = RuneConfig::from_str("").unwrap();
+        config.set_value("price", Value::from("$5")).unwrap();
+        let reparsed = RuneConfig::from_str(&to_rune_string(config.document().unwrap())).unwrap();
+        assert_eq!(reparsed.get::<String>("price").unwrap(), "$5");
+    }
+
+    #[test]
+    fn test_export_env_flattens_and_quotes() {
+        let doc = parse(
+            r#"
+name "demo app"
+app:
+  log-level "info"
+  server:
+    host "localhost"
+    port 8080
+    owner name
+  end
+  hosts ["a.example.com" "b.example.com"]
+  quote "it's"
+  proxy null
+  debug false
+end
+"#,
+        );
+
+        let env = export_document_to_env(&doc).unwrap();
+        assert_eq!(
+            env,
+            "NAME='demo app'\n\
+             APP_LOG_LEVEL=info\n\
+             APP_SERVER_HOST=localhost\n\
+             APP_SERVER_PORT=8080\n\
+             APP_SERVER_OWNER='demo app'\n\
+             APP_HOSTS=a.example.com,b.example.com\n\
+             APP_QUOTE='it'\\''s'\n\
+             APP_PROXY=\n\
+             APP_DEBUG=false\n"
+        );
+    }
+
+    #[test]
+    fn test_export_env_rejects_what_does_not_flatten() {
+        for (input, fragment) in [
+            ("app:\n  servers [{ host \"a\" }]\nend\n", "'app.servers'"),
+            ("tags [\"a,b\" \"c\"]\n", "contains a comma"),
+            ("app:\n  log-level 1\n  log_level 2\nend\n", "APP_LOG_LEVEL"),
+        ] {
+            match export_document_to_env(&parse(input)) {
+                Err(RuneError::RuntimeError { message, code, .. }) => {
+                    assert_eq!(code, Some(501));
+                    assert!(message.contains(fragment), "{}", message);
+                }
+                other => panic!("expected an export error for {:?}, got {:?}", input, other),
+            }
+        }
+    }
+
     #[test]
     fn test_export_regex() {
         let doc = Document {
@@ -207,6 +647,8 @@ mod tests {
             )],
             metadata: vec![],
             globals: vec![],
+            constants: vec![],
+            order: vec![],
         };
 
         let json_output = export_document_to_json(&doc).unwrap();
@@ -221,10 +663,12 @@ mod tests {
         let doc = Document {
             items: vec![(
                 "root".to_string(),
-                Value::Object(vec![ObjectItem::Assign("a".into(), Value::Number(1.0))]),
+                Value::Object(vec![ObjectItem::Assign("a".into(), Value::Int(1))]),
             )],
             metadata: vec![],
             globals: vec![],
+            constants: vec![],
+            order: vec![],
         };
 
         let json_output = export_document_to_json(&doc).unwrap();
@@ -234,4 +678,29 @@ mod tests {
         assert_eq!(v["items"]["root"][0]["key"], "a");
         assert_eq!(v["items"]["root"][0]["value"], 1.0);
     }
+
+    #[test]
+    fn test_export_keeps_integers_integral() {
+        let mut parser = Parser::new("port 8080\nratio 0.5\ntimeout 30.0\n").unwrap();
+        let doc = parser.parse_document().unwrap();
+
+        let json_output = export_document_to_json(&doc).unwrap();
+        assert!(!json_output.contains("8080.0"));
+
+        let v: serde_json::Value = serde_json::from_str(&json_output).unwrap();
+        assert!(v["globals"]["port"].is_i64());
+        assert!(v["globals"]["ratio"].is_f64());
+        assert!(v["globals"]["timeout"].is_f64());
+    }
+
+    #[test]
+    fn test_export_bytes_as_base64_string() {
+        let mut parser = Parser::new("icon b64\"SGVsbG8=\"\n").unwrap();
+        let doc = parser.parse_document().unwrap();
+
+        let json_output = export_document_to_json(&doc).unwrap();
+        let v: serde_json::Value = serde_json::from_str(&json_output).unwrap();
+
+        assert_eq!(v["globals"]["icon"], "SGVsbG8=");
+    }
 }
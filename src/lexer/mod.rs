@@ -1,7 +1,7 @@
 // Author: Dustin Pilgrim
 // License: MIT
 
-use crate::RuneError;
+use crate::{RuneError, RuneErrorCode};
 use std::str::Chars;
 
 mod scanner;
@@ -11,17 +11,31 @@ mod tokenizer;
 pub enum Token {
     // --- literals ---
     Ident(String),
+    /// A string in which every `$` is plain text.
     String(String),
+    /// A string with an unescaped `$`: the text before the first one and after each,
+    /// so `"a $env.X \$b"` is `["a ", "env.X $b"]`. An escaped `\$` is plain text.
+    Template(Vec<String>),
     Regex(String),
-    Number(f64),
+    /// Raw text of a `b64"..."` literal; decoded by the parser.
+    Bytes(String),
+    /// Raw text of a `t"..."` timestamp literal; parsed by the parser.
+    DateTime(String),
+    Int(i64),
+    Float(f64),
     Bool(bool),
     Null,
 
     // --- structure ---
     Colon,
+    /// `:-`, introducing a fallback in `$env.NAME:-default`.
+    ColonDash,
+    Bang,
     Equals,
     LBracket,
     RBracket,
+    LBrace,
+    RBrace,
 
     End,
     EndIf,
@@ -30,6 +44,7 @@ pub enum Token {
     Dollar,
     Dot,
     At,
+    Star,
 
     // --- keywords ---
     Gather,
@@ -40,45 +55,95 @@ pub enum Token {
 
     // --- layout ---
     Newline,
+    /// A `#` comment, only produced by `Lexer::new_preserving_comments`. `text` is
+    /// everything after the `#` up to the end of the line; `trailing` is true when the
+    /// comment follows a token on the same line rather than standing on its own.
+    Comment {
+        text: String,
+        trailing: bool,
+    },
     Eof,
 }
 
 impl Token {
+    /// The text of a `String` or `Template` token with every `$` as written, for
+    /// places that never interpolate, such as keys and file names.
+    pub(crate) fn into_text(self) -> Option<String> {
+        match self {
+            Token::String(text) => Some(text),
+            Token::Template(pieces) => Some(pieces.join("$")),
+            _ => None,
+        }
+    }
+
     /// Human-readable label for diagnostics, avoiding `Debug` output like
     /// `String("..")` leaking into user-facing error messages.
     pub(crate) fn describe(&self) -> String {
         match self {
             Token::Ident(name) => format!("identifier '{}'", name),
             Token::String(value) => format!("string \"{}\"", value),
+            Token::Template(pieces) => format!("string \"{}\"", pieces.join("$")),
             Token::Regex(value) => format!("regex r\"{}\"", value),
-            Token::Number(number) => format!("number {}", number),
+            Token::Bytes(value) => format!("bytes b64\"{}\"", value),
+            Token::DateTime(value) => format!("timestamp t\"{}\"", value),
+            Token::Int(number) => format!("number {}", number),
+            Token::Float(number) => format!("number {}", number),
             Token::Bool(value) => format!("boolean {}", value),
             Token::Null => "null".into(),
             Token::Colon => "':'".into(),
+            Token::ColonDash => "':-'".into(),
+            Token::Bang => "'!'".into(),
             Token::Equals => "'='".into(),
             Token::LBracket => "'['".into(),
             Token::RBracket => "']'".into(),
+            Token::LBrace => "'{'".into(),
+            Token::RBrace => "'}'".into(),
             Token::End => "'end'".into(),
             Token::EndIf => "'endif'".into(),
             Token::Dollar => "'$'".into(),
             Token::Dot => "'.'".into(),
             Token::At => "'@'".into(),
+            Token::Star => "'*'".into(),
             Token::Gather => "'gather'".into(),
             Token::As => "'as'".into(),
             Token::If => "'if'".into(),
             Token::Else => "'else'".into(),
             Token::ElseIf => "'elseif'".into(),
             Token::Newline => "newline".into(),
+            Token::Comment { .. } => "comment".into(),
             Token::Eof => "end of input".into(),
         }
     }
 }
 
+/// Where a token sits in the source: `start..end` are byte offsets, and `line` and
+/// `column` (both 1-based, columns counted in characters) are those of its first character.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub struct Span {
+    pub start: usize,
+    pub end: usize,
+    pub line: usize,
+    pub column: usize,
+}
+
+/// A token together with its span.
+#[derive(Debug, Clone, PartialEq)]
+pub struct Spanned<T> {
+    pub node: T,
+    pub span: Span,
+}
+
 pub struct Lexer<'a> {
     input: Chars<'a>,
     peek: Option<char>,
     line: usize,
     column: usize,
+    /// Byte offset of `peek` in the input.
+    offset: usize,
+    /// Emit `Token::Comment` instead of skipping comments.
+    preserve_comments: bool,
+    /// Line on which the last non-layout token ended, to tell trailing comments apart.
+    last_token_line: Option<usize>,
 }
 
 impl<'a> Lexer<'a> {
@@ -88,11 +153,24 @@ impl<'a> Lexer<'a> {
             peek: None,
             line: 1,
             column: 0,
+            offset: 0,
+            preserve_comments: false,
+            last_token_line: None,
         };
         lexer.peek = lexer.input.next();
         lexer
     }
 
+    /// Like `new`, but comments come out as `Token::Comment` for tools such as
+    /// formatters. The newline ending a comment is then its own `Token::Newline`,
+    /// whereas `new` drops it along with the comment.
+    pub fn new_preserving_comments(input: &'a str) -> Self {
+        Lexer {
+            preserve_comments: true,
+            ..Self::new(input)
+        }
+    }
+
     pub fn line(&self) -> usize {
         self.line
     }
@@ -101,16 +179,55 @@ impl<'a> Lexer<'a> {
         self.column
     }
 
+    /// Byte offset of the next character to be read.
+    pub fn offset(&self) -> usize {
+        self.offset
+    }
+
     /// Normal tokenization (newlines are significant)
     pub fn next_token(&mut self) -> Result<Token, RuneError> {
-        tokenizer::next_token_with_flag(self, false)
+        self.next_spanned().map(|token| token.node)
     }
 
     /// Tokenization inside arrays (newlines ignored)
     pub fn next_token_in_array(&mut self) -> Result<Token, RuneError> {
+        self.next_spanned_in_array().map(|token| token.node)
+    }
+
+    /// Like `next_token`, also returning where the token is in the source.
+    pub fn next_spanned(&mut self) -> Result<Spanned<Token>, RuneError> {
+        tokenizer::next_token_with_flag(self, false)
+    }
+
+    /// Like `next_token_in_array`, also returning where the token is in the source.
+    pub fn next_spanned_in_array(&mut self) -> Result<Spanned<Token>, RuneError> {
         tokenizer::next_token_with_flag(self, true)
     }
 }
 
+/// Yields tokens as `next_token` does, stopping at the end of input instead of
+/// returning `Token::Eof`. An error is yielded in place of the bad token and lexing
+/// carries on after it.
+///
+/// # Example
+/// ```
+/// # use rune_cfg::lexer::{Lexer, Token};
+/// let tokens = Lexer::new("port 8080").collect::<Result<Vec<_>, _>>().unwrap();
+/// assert_eq!(tokens, vec![Token::Ident("port".into()), Token::Int(8080)]);
+/// ```
+impl Iterator for Lexer<'_> {
+    type Item = Result<Token, RuneError>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        match self.next_token() {
+            Ok(Token::Eof) => None,
+            result => Some(result),
+        }
+    }
+}
+
+// Once at the end of input the lexer keeps returning `Token::Eof`.
+impl std::iter::FusedIterator for Lexer<'_> {}
+
 #[cfg(test)]
 mod tests;
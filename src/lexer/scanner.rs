@@ -7,6 +7,7 @@ use super::*;
 pub(super) fn bump(lexer: &mut Lexer) -> Option<char> {
     let curr = lexer.peek;
     if let Some(c) = curr {
+        lexer.offset += c.len_utf8();
         if c == '\n' {
             lexer.line += 1;
             lexer.column = 0;
@@ -29,6 +30,7 @@ pub(super) fn skip_whitespace_and_comments(lexer: &mut Lexer, skip_newlines: boo
                 bump(lexer);
             }
             '\n' => break,
+            '#' if lexer.preserve_comments => break,
             '#' => {
                 // Skip comment until end of line
                 while let Some(ch) = bump(lexer) {
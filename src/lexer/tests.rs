@@ -229,3 +229,209 @@ endif
         assert_eq!(tok, Ok(expected));
     }
 }
+
+#[test]
+fn test_conditional_keywords_match_whole_identifiers() {
+    let tokens: Result<Vec<_>, _> =
+        Lexer::new("if iffy else elsewhere endif end endif-x").collect();
+    assert_eq!(
+        tokens,
+        Ok(vec![
+            Token::If,
+            Token::Ident("iffy".into()),
+            Token::Else,
+            Token::Ident("elsewhere".into()),
+            Token::EndIf,
+            Token::End,
+            Token::Ident("endif-x".into()),
+        ])
+    );
+}
+
+#[test]
+fn test_number_literal_form_picks_int_or_float() {
+    let mut lexer = Lexer::new("8080 0.5 30.0");
+
+    assert_eq!(lexer.next_token(), Ok(Token::Int(8080)));
+    assert_eq!(lexer.next_token(), Ok(Token::Float(0.5)));
+    assert_eq!(lexer.next_token(), Ok(Token::Float(30.0)));
+}
+
+#[test]
+fn test_integer_beyond_i64_is_reported() {
+    let mut lexer = Lexer::new("9223372036854775807 18446744073709551615");
+
+    assert_eq!(lexer.next_token(), Ok(Token::Int(i64::MAX)));
+    match lexer.next_token() {
+        Err(RuneError::TypeError {
+            message,
+            hint: Some(hint),
+            code: Some(106),
+            ..
+        }) => {
+            assert!(
+                message.contains("does not fit a 64-bit integer"),
+                "{}",
+                message
+            );
+            assert!(hint.contains("\"18446744073709551615\""), "{}", hint);
+        }
+        other => panic!("expected an overflow error, got {:?}", other),
+    }
+}
+
+#[test]
+fn test_escaped_dollar_is_told_apart_from_a_backslash() {
+    let mut lexer = Lexer::new(concat!(
+        r#""price is \$env" "C:\\$env.PX \$5" "\"quoted\"" "#,
+        "\"\u{E000}\"\n"
+    ));
+
+    assert_eq!(
+        lexer.next_token(),
+        Ok(Token::String("price is $env".into()))
+    );
+    assert_eq!(
+        lexer.next_token(),
+        Ok(Token::Template(vec![r"C:\".into(), "env.PX $5".into()]))
+    );
+    assert_eq!(lexer.next_token(), Ok(Token::String("\"quoted\"".into())));
+    assert_eq!(lexer.next_token(), Ok(Token::String("\u{E000}".into())));
+}
+
+#[test]
+fn test_unicode_escapes() {
+    let mut lexer = Lexer::new(concat!(
+        r#""\u{263A}" 'smile \u{1F600}!' "\u{41}\u{0042}""#,
+        "\n"
+    ));
+
+    assert_eq!(lexer.next_token(), Ok(Token::String("☺".into())));
+    assert_eq!(lexer.next_token(), Ok(Token::String("smile 😀!".into())));
+    assert_eq!(lexer.next_token(), Ok(Token::String("AB".into())));
+}
+
+#[test]
+fn test_invalid_unicode_escapes() {
+    for input in [
+        r#""\u{}""#,
+        r#""\u{12G4}""#,
+        r#""\u{D800}""#,
+        r#""\u{110000}""#,
+        r#""\u{1234567}""#,
+        r#""\u{263A"#,
+    ] {
+        match Lexer::new(input).next_token() {
+            Err(RuneError::SyntaxError { code, hint, .. }) => {
+                assert_eq!(code, Some(105), "{}", input);
+                assert!(hint.is_some_and(|h| h.ends_with(r"e.g. \u{263A}")));
+            }
+            other => panic!(
+                "expected unicode escape error for {}, got {:?}",
+                input, other
+            ),
+        }
+    }
+}
+
+#[test]
+fn test_comments_are_skipped_by_default() {
+    let mut lexer = Lexer::new("# header\nport 8080 # trailing\n");
+
+    assert_eq!(lexer.next_token(), Ok(Token::Ident("port".into())));
+    assert_eq!(lexer.next_token(), Ok(Token::Int(8080)));
+    assert_eq!(lexer.next_token(), Ok(Token::Eof));
+}
+
+#[test]
+fn test_preserved_comments() {
+    let mut lexer =
+        Lexer::new_preserving_comments("# header\nport 8080 # trailing\n  # leading\nend\n");
+
+    let mut tokens = Vec::new();
+    loop {
+        match lexer.next_token().unwrap() {
+            Token::Eof => break,
+            token => tokens.push(token),
+        }
+    }
+
+    let comment = |text: &str, trailing| Token::Comment {
+        text: text.into(),
+        trailing,
+    };
+    assert_eq!(
+        tokens,
+        vec![
+            comment(" header", false),
+            Token::Newline,
+            Token::Ident("port".into()),
+            Token::Int(8080),
+            comment(" trailing", true),
+            Token::Newline,
+            comment(" leading", false),
+            Token::Newline,
+            Token::End,
+            Token::Newline,
+        ]
+    );
+}
+
+#[test]
+fn test_token_spans() {
+    let mut lexer = Lexer::new("name \"héllo\"\n  port 80\n");
+
+    let spans: Vec<(Token, Span)> = std::iter::from_fn(|| match lexer.next_spanned().unwrap() {
+        Spanned {
+            node: Token::Eof, ..
+        } => None,
+        token => Some((token.node, token.span)),
+    })
+    .collect();
+
+    let span = |start, end, line, column| Span {
+        start,
+        end,
+        line,
+        column,
+    };
+    assert_eq!(
+        spans,
+        vec![
+            (Token::Ident("name".into()), span(0, 4, 1, 1)),
+            // 'é' is two bytes but one column.
+            (Token::String("héllo".into()), span(5, 13, 1, 6)),
+            (Token::Newline, span(13, 14, 1, 13)),
+            (Token::Ident("port".into()), span(16, 20, 2, 3)),
+            (Token::Int(80), span(21, 23, 2, 8)),
+            (Token::Newline, span(23, 24, 2, 10)),
+        ]
+    );
+}
+
+#[test]
+fn test_lexer_iterator() {
+    let tokens: Result<Vec<_>, _> = Lexer::new("app:\n  port 8080\nend").collect();
+    assert_eq!(
+        tokens,
+        Ok(vec![
+            Token::Ident("app".into()),
+            Token::Colon,
+            Token::Newline,
+            Token::Ident("port".into()),
+            Token::Int(8080),
+            Token::Newline,
+            Token::End,
+        ])
+    );
+
+    let mut lexer = Lexer::new("a ^ b");
+    assert_eq!(lexer.next(), Some(Ok(Token::Ident("a".into()))));
+    assert!(matches!(
+        lexer.next(),
+        Some(Err(RuneError::UnexpectedCharacter { character: '^', .. }))
+    ));
+    assert_eq!(lexer.next(), Some(Ok(Token::Ident("b".into()))));
+    assert_eq!(lexer.next(), None);
+    assert_eq!(lexer.next(), None);
+}
@@ -1,21 +1,32 @@
 // Author: Dustin Pilgrim
 // License: MIT
 
+use std::num::IntErrorKind;
+
 use super::scanner::{bump, skip_whitespace_and_comments};
 use super::*;
 
 pub(super) fn next_token_with_flag(
     lexer: &mut Lexer,
     skip_newlines: bool,
-) -> Result<Token, RuneError> {
+) -> Result<Spanned<Token>, RuneError> {
     skip_whitespace_and_comments(lexer, skip_newlines);
 
+    let (start, line, column) = (lexer.offset, lexer.line, lexer.column + 1);
     let token = match lexer.peek {
         Some('\n') => tokenize_newline(lexer),
+        Some('#') => Ok(tokenize_comment(lexer)),
+        Some(':') if lexer.input.clone().next() == Some('-') => {
+            bump(lexer); // consume ':'
+            tokenize_symbol(lexer, Token::ColonDash)
+        }
         Some(':') => tokenize_symbol(lexer, Token::Colon),
         Some('=') => tokenize_symbol(lexer, Token::Equals),
+        Some('!') => tokenize_symbol(lexer, Token::Bang),
         Some('[') => tokenize_symbol(lexer, Token::LBracket),
         Some(']') => tokenize_symbol(lexer, Token::RBracket),
+        Some('{') => tokenize_symbol(lexer, Token::LBrace),
+        Some('}') => tokenize_symbol(lexer, Token::RBrace),
         Some(',') => {
             bump(lexer);
             return next_token_with_flag(lexer, skip_newlines); // skip commas
@@ -23,7 +34,10 @@ pub(super) fn next_token_with_flag(
         Some('$') => tokenize_symbol(lexer, Token::Dollar),
         Some('.') => tokenize_symbol(lexer, Token::Dot),
         Some('@') => tokenize_symbol(lexer, Token::At),
+        Some('*') => tokenize_symbol(lexer, Token::Star),
         Some('r') => tokenize_regex_or_ident(lexer),
+        Some('b') => tokenize_bytes_or_ident(lexer),
+        Some('t') => tokenize_datetime_or_ident(lexer),
         Some('"') | Some('\'') => tokenize_string(lexer),
         Some(c) if c.is_digit(10) => tokenize_number(lexer),
         Some(c) if c.is_alphabetic() => tokenize_identifier_or_keyword(lexer),
@@ -31,7 +45,36 @@ pub(super) fn next_token_with_flag(
         None => Ok(Token::Eof),
     };
 
-    token
+    let token = token?;
+    if !matches!(token, Token::Newline | Token::Comment { .. }) {
+        lexer.last_token_line = Some(lexer.line);
+    }
+    Ok(Spanned {
+        node: token,
+        span: Span {
+            start,
+            end: lexer.offset,
+            line,
+            column,
+        },
+    })
+}
+
+/// A comment up to, not including, the end of the line. Only reached when
+/// comments are preserved; otherwise they are skipped as whitespace.
+fn tokenize_comment(lexer: &mut Lexer) -> Token {
+    let trailing = lexer.last_token_line == Some(lexer.line);
+    bump(lexer); // consume '#'
+
+    let mut text = String::new();
+    while let Some(ch) = lexer.peek {
+        if ch == '\n' {
+            break;
+        }
+        text.push(ch);
+        bump(lexer);
+    }
+    Token::Comment { text, trailing }
 }
 
 fn tokenize_newline(lexer: &mut Lexer) -> Result<Token, RuneError> {
@@ -77,7 +120,7 @@ fn tokenize_regex_literal(lexer: &mut Lexer) -> Result<Token, RuneError> {
                     line: lexer.line,
                     column: lexer.column,
                     hint: Some("Trailing backslash in regex".into()),
-                    code: Some(103),
+                    code: Some(RuneErrorCode::UnclosedString.code()),
                 });
             }
         } else {
@@ -88,6 +131,58 @@ fn tokenize_regex_literal(lexer: &mut Lexer) -> Result<Token, RuneError> {
     Ok(Token::Regex(content))
 }
 
+fn tokenize_bytes_or_ident(lexer: &mut Lexer) -> Result<Token, RuneError> {
+    // Check if this is a base64 literal b64"..."
+    let mut clone_iter = lexer.input.clone();
+    let prefix: String = clone_iter.by_ref().take(3).collect();
+
+    if prefix == "64\"" {
+        tokenize_bytes_literal(lexer)
+    } else {
+        tokenize_identifier_or_keyword(lexer)
+    }
+}
+
+fn tokenize_bytes_literal(lexer: &mut Lexer) -> Result<Token, RuneError> {
+    bump(lexer); // consume 'b'
+    bump(lexer); // consume '6'
+    bump(lexer); // consume '4'
+    bump(lexer); // consume opening '"'
+
+    read_raw_literal(lexer, "base64").map(Token::Bytes)
+}
+
+fn tokenize_datetime_or_ident(lexer: &mut Lexer) -> Result<Token, RuneError> {
+    // Check if this is a timestamp literal t"..."
+    if lexer.input.clone().next() == Some('"') {
+        bump(lexer); // consume 't'
+        bump(lexer); // consume opening '"'
+        read_raw_literal(lexer, "timestamp").map(Token::DateTime)
+    } else {
+        tokenize_identifier_or_keyword(lexer)
+    }
+}
+
+/// Read up to the closing '"' with no escape handling, for prefixed literals.
+fn read_raw_literal(lexer: &mut Lexer, kind: &str) -> Result<String, RuneError> {
+    let mut content = String::new();
+    loop {
+        match bump(lexer) {
+            Some('"') => return Ok(content),
+            Some(ch) => content.push(ch),
+            None => {
+                return Err(RuneError::UnclosedString {
+                    quote: '"',
+                    line: lexer.line,
+                    column: lexer.column,
+                    hint: Some(format!("{} literal not closed", kind)),
+                    code: Some(RuneErrorCode::UnclosedString.code()),
+                });
+            }
+        }
+    }
+}
+
 fn tokenize_identifier_starting_with_r(lexer: &mut Lexer) -> Result<Token, RuneError> {
     let mut ident = String::new();
     ident.push(bump(lexer).unwrap()); // consume 'r'
@@ -106,7 +201,11 @@ fn tokenize_identifier_starting_with_r(lexer: &mut Lexer) -> Result<Token, RuneE
 
 fn tokenize_string(lexer: &mut Lexer) -> Result<Token, RuneError> {
     let quote = bump(lexer).unwrap();
+    // Unclosed strings are reported at the opening quote, not where the input ran out.
+    let (line, column) = (lexer.line, lexer.column);
     let mut content = String::new();
+    // Earlier pieces, each ended by an unescaped `$`; `content` is the current one.
+    let mut pieces = Vec::new();
 
     while let Some(ch) = lexer.peek {
         if ch == quote {
@@ -114,9 +213,19 @@ fn tokenize_string(lexer: &mut Lexer) -> Result<Token, RuneError> {
             break;
         }
 
+        if ch == '$' {
+            bump(lexer);
+            pieces.push(std::mem::take(&mut content));
+            continue;
+        }
+
         if ch == '\\' {
             bump(lexer); // consume '\'
             if let Some(next_ch) = bump(lexer) {
+                if next_ch == 'u' && lexer.peek == Some('{') {
+                    content.push(read_unicode_escape(lexer)?);
+                    continue;
+                }
                 let escaped = match next_ch {
                     'n' => '\n',
                     't' => '\t',
@@ -136,7 +245,7 @@ fn tokenize_string(lexer: &mut Lexer) -> Result<Token, RuneError> {
                     line: lexer.line,
                     column: lexer.column,
                     hint: Some("Trailing backslash in string".into()),
-                    code: Some(103),
+                    code: Some(RuneErrorCode::UnclosedString.code()),
                 });
             }
         } else {
@@ -149,14 +258,65 @@ fn tokenize_string(lexer: &mut Lexer) -> Result<Token, RuneError> {
     if lexer.peek.is_none() && !content.ends_with(quote) {
         return Err(RuneError::UnclosedString {
             quote,
-            line: lexer.line,
-            column: lexer.column,
+            line,
+            column,
             hint: Some("String literal not closed".into()),
-            code: Some(103),
+            code: Some(RuneErrorCode::UnclosedString.code()),
         });
     }
 
-    Ok(Token::String(content))
+    if pieces.is_empty() {
+        Ok(Token::String(content))
+    } else {
+        pieces.push(content);
+        Ok(Token::Template(pieces))
+    }
+}
+
+/// The character of a `\u{...}` escape, with the lexer on the opening brace: one to
+/// six hex digits naming a Unicode scalar value, as in Rust.
+fn read_unicode_escape(lexer: &mut Lexer) -> Result<char, RuneError> {
+    bump(lexer); // consume '{'
+
+    let mut digits = String::new();
+    loop {
+        match bump(lexer) {
+            Some('}') => break,
+            Some(ch) if ch.is_ascii_hexdigit() && digits.len() < 6 => digits.push(ch),
+            found => {
+                let message = match found {
+                    Some(ch) if ch.is_ascii_hexdigit() => {
+                        "Unicode escape has more than 6 hex digits".to_string()
+                    }
+                    Some(ch) => format!("Invalid character '{}' in unicode escape", ch),
+                    None => "Unclosed unicode escape".to_string(),
+                };
+                return Err(unicode_escape_error(lexer, message));
+            }
+        }
+    }
+
+    if digits.is_empty() {
+        return Err(unicode_escape_error(lexer, "Empty unicode escape".into()));
+    }
+    let code = u32::from_str_radix(&digits, 16).expect("validated hex digits");
+    char::from_u32(code).ok_or_else(|| {
+        unicode_escape_error(
+            lexer,
+            format!("Unicode escape {:X} is not a valid code point", code),
+        )
+    })
+}
+
+fn unicode_escape_error(lexer: &Lexer, message: String) -> RuneError {
+    RuneError::SyntaxError {
+        message,
+        line: lexer.line,
+        column: lexer.column,
+        span: None,
+        hint: Some("Write 1 to 6 hex digits naming a character, e.g. \\u{263A}".into()),
+        code: Some(RuneErrorCode::InvalidUnicodeEscape.code()),
+    }
 }
 
 fn tokenize_number(lexer: &mut Lexer) -> Result<Token, RuneError> {
@@ -171,15 +331,35 @@ fn tokenize_number(lexer: &mut Lexer) -> Result<Token, RuneError> {
         }
     }
 
-    num.parse::<f64>()
-        .map(Token::Number)
-        .map_err(|_| RuneError::TypeError {
-            message: format!("Invalid number '{}'", num),
-            line: lexer.line,
-            column: lexer.column,
-            hint: None,
-            code: Some(102),
-        })
+    // The literal form decides the type: a decimal point makes it a float.
+    let parsed = if num.contains('.') {
+        num.parse::<f64>().map(Token::Float).ok()
+    } else {
+        match num.parse::<i64>() {
+            Ok(n) => Some(Token::Int(n)),
+            Err(e) if *e.kind() == IntErrorKind::PosOverflow => {
+                return Err(RuneError::TypeError {
+                    message: format!("Integer '{}' does not fit a 64-bit integer", num),
+                    line: lexer.line,
+                    column: lexer.column,
+                    hint: Some(format!(
+                        "Quote it (\"{0}\") to read it with get::<u64>() or get::<u128>(), or write {0}.0 for a float",
+                        num
+                    )),
+                    code: Some(RuneErrorCode::IntegerOverflow.code()),
+                });
+            }
+            Err(_) => None,
+        }
+    };
+
+    parsed.ok_or_else(|| RuneError::TypeError {
+        message: format!("Invalid number '{}'", num),
+        line: lexer.line,
+        column: lexer.column,
+        hint: None,
+        code: Some(RuneErrorCode::InvalidNumber.code()),
+    })
 }
 
 fn tokenize_identifier_or_keyword(lexer: &mut Lexer) -> Result<Token, RuneError> {
@@ -221,6 +401,6 @@ fn tokenize_unexpected_char(lexer: &mut Lexer, ch: char) -> Result<Token, RuneEr
         line: lexer.line,
         column: lexer.column,
         hint: Some("Unexpected character in input".into()),
-        code: Some(104),
+        code: Some(RuneErrorCode::UnexpectedCharacter.code()),
     })
 }
@@ -147,19 +147,39 @@
 //! ```
 
 pub mod ast;
+pub mod builder;
 pub mod config;
+#[cfg(feature = "serde")]
+pub mod de;
 pub mod diagnostic;
 pub mod error;
 pub mod export;
 pub mod lexer;
 pub mod lsp;
 pub mod parser;
+#[cfg(feature = "miette")]
+pub mod report;
 pub mod resolver;
 pub mod schema;
+mod ser;
 pub mod utils;
 
-pub use ast::{Document, Value};
-pub use config::RuneConfig;
+pub use ast::{
+    Condition, ConditionalValue, Document, EntryKind, IfBlock, MergeStrategy, ObjectItem, Value,
+};
+pub use builder::{DocumentBuilder, ObjectBuilder};
+#[cfg(feature = "notify")]
+pub use config::ConfigWatcher;
+pub use config::{ResolutionMode, RuneConfig};
+#[cfg(feature = "serde")]
+pub use de::from_value;
 pub use diagnostic::{DiagnosticSeverity, RuneDiagnostic, SourcePosition, SourceRange};
-pub use error::RuneError;
-pub use schema::{SchemaBlock, SchemaDocument, SchemaField, SchemaType};
+pub use error::{IoSource, RuneError, RuneErrorCode};
+#[cfg(feature = "miette")]
+pub use report::RuneReport;
+#[cfg(feature = "tokio")]
+pub use resolver::{AsyncResolverProvider, ResolveFuture};
+pub use resolver::{ResolverContext, ResolverProvider};
+pub use schema::{
+    MetadataField, MetadataSchema, SchemaBlock, SchemaDocument, SchemaField, SchemaType,
+};
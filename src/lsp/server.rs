@@ -1354,13 +1354,18 @@ fn sample_value_for_field(field: &SchemaField) -> String {
 fn value_literal(value: &crate::Value) -> String {
     match value {
         crate::Value::String(value) => format!("\"{}\"", value),
-        crate::Value::Number(value) => value.to_string(),
+        crate::Value::Int(value) => value.to_string(),
+        crate::Value::Float(value) => value.to_string(),
         crate::Value::Bool(value) => value.to_string(),
         crate::Value::Null => "null".into(),
         crate::Value::Array(_) => "[]".into(),
         crate::Value::Object(_) => "".into(),
         crate::Value::Regex(pattern) => format!("r\"{}\"", pattern.as_str()),
+        crate::Value::Bytes(_) => value.to_string(),
+        #[cfg(feature = "chrono")]
+        crate::Value::DateTime(_) => value.to_string(),
         crate::Value::Reference(reference) => reference.join("."),
+        crate::Value::Namespaced(reference) => format!("${}", reference.join(".")),
         crate::Value::Interpolated(_) => "\"\"".into(),
         crate::Value::Conditional(_) => "null".into(),
     }
@@ -1374,6 +1379,7 @@ fn diagnostic_from_error(error: RuneError) -> RuneDiagnostic {
             column,
             hint,
             code,
+            ..
         }
         | RuneError::InvalidToken {
             token: message,
@@ -1428,6 +1434,7 @@ fn diagnostic_from_error(error: RuneError) -> RuneDiagnostic {
             path,
             hint,
             code,
+            ..
         } => {
             let mut diagnostic = RuneDiagnostic::error(format!("{}: {}", path, message));
             if let Some(code) = code {
@@ -1438,6 +1445,15 @@ fn diagnostic_from_error(error: RuneError) -> RuneDiagnostic {
             }
             diagnostic
         }
+        // Its location is in another file, so it cannot be placed in this one.
+        RuneError::InFile { file, source } => {
+            let inner = diagnostic_from_error(*source);
+            RuneDiagnostic {
+                message: format!("{}: {}", file, inner.message),
+                range: None,
+                ..inner
+            }
+        }
         RuneError::RuntimeError {
             message,
             hint,
@@ -51,13 +51,11 @@ pub(super) fn parse_if_block(parser: &mut Parser) -> Result<ObjectItem, RuneErro
     match parser.bump()? {
         Token::Colon => {}
         other => {
-            return Err(RuneError::SyntaxError {
-                message: format!("Expected ':' after if condition, got {:?}", other),
-                line: parser.line(),
-                column: parser.column(),
-                hint: Some("Use: if condition:".into()),
-                code: Some(214),
-            });
+            return Err(parser.syntax_error(
+                format!("Expected ':' after if condition, got {:?}", other),
+                Some("Use: if condition:".into()),
+                RuneErrorCode::InvalidCondition,
+            ));
         }
     }
 
@@ -72,13 +70,11 @@ pub(super) fn parse_if_block(parser: &mut Parser) -> Result<ObjectItem, RuneErro
         match parser.bump()? {
             Token::Colon => {}
             other => {
-                return Err(RuneError::SyntaxError {
-                    message: format!("Expected ':' after else, got {:?}", other),
-                    line: parser.line(),
-                    column: parser.column(),
-                    hint: Some("Use: else:".into()),
-                    code: Some(214),
-                });
+                return Err(parser.syntax_error(
+                    format!("Expected ':' after else, got {:?}", other),
+                    Some("Use: else:".into()),
+                    RuneErrorCode::InvalidCondition,
+                ));
             }
         }
 
@@ -91,13 +87,11 @@ pub(super) fn parse_if_block(parser: &mut Parser) -> Result<ObjectItem, RuneErro
     match parser.bump()? {
         Token::EndIf => {}
         other => {
-            return Err(RuneError::SyntaxError {
-                message: format!("Expected 'endif', got {:?}", other),
-                line: parser.line(),
-                column: parser.column(),
-                hint: Some("Close if-blocks with 'endif'".into()),
-                code: Some(214),
-            });
+            return Err(parser.syntax_error(
+                format!("Expected 'endif', got {:?}", other),
+                Some("Close if-blocks with 'endif'".into()),
+                RuneErrorCode::InvalidCondition,
+            ));
         }
     }
 
@@ -119,6 +113,7 @@ fn parse_object_items_until(
     stop: StopAt,
 ) -> Result<Vec<ObjectItem>, RuneError> {
     let mut items: Vec<ObjectItem> = Vec::new();
+    let mut seen = HashMap::new();
 
     while let Some(tok) = parser.peek() {
         match tok {
@@ -126,9 +121,8 @@ fn parse_object_items_until(
                 parser.bump()?;
             }
 
-            Token::Ident(_) | Token::String(_) => {
-                let (k, v) = value::parse_assignment(parser)?;
-                items.push(ObjectItem::Assign(k, v));
+            Token::Ident(_) | Token::String(_) | Token::Template(_) => {
+                value::parse_object_assignment(parser, &mut items, &mut seen)?;
             }
 
             Token::If => {
@@ -145,7 +139,7 @@ fn parse_object_items_until(
                     line: parser.line(),
                     column: parser.column(),
                     hint: Some("Unexpected 'else' (no matching 'if'?)".into()),
-                    code: Some(207),
+                    code: Some(RuneErrorCode::UnexpectedElse.code()),
                 });
             }
 
@@ -154,13 +148,12 @@ fn parse_object_items_until(
             }
 
             Token::End => {
-                return Err(RuneError::SyntaxError {
-                    message: "Found 'end' while parsing an if-block; did you mean 'endif'?".into(),
-                    line: parser.line(),
-                    column: parser.column(),
-                    hint: Some("Use 'endif' to close if-blocks".into()),
-                    code: Some(214),
-                });
+                parser.bump()?; // so the error points at the 'end'
+                return Err(parser.syntax_error(
+                    "Found 'end' while parsing an if-block; did you mean 'endif'?",
+                    Some("Use 'endif' to close if-blocks".into()),
+                    RuneErrorCode::InvalidCondition,
+                ));
             }
 
             _ => {
@@ -169,7 +162,7 @@ fn parse_object_items_until(
                     line: parser.line(),
                     column: parser.column(),
                     hint: Some("Expected assignment, nested block, 'else', or 'endif'".into()),
-                    code: Some(207),
+                    code: Some(RuneErrorCode::InvalidBlockEntry.code()),
                 });
             }
         }
@@ -179,18 +172,14 @@ fn parse_object_items_until(
     Ok(items)
 }
 
+/// `path`, `not path`, `path = value` or `path != value`.
 fn parse_condition(parser: &mut Parser) -> Result<Condition, RuneError> {
-    let path = if let Token::Ident(name) = parser.bump()? {
-        name
-    } else {
-        return Err(RuneError::SyntaxError {
-            message: "Expected identifier in condition".into(),
-            line: parser.line(),
-            column: parser.column(),
-            hint: None,
-            code: Some(214),
-        });
-    };
+    let path = expect_condition_path(parser)?;
+
+    // `not` only negates when a path follows, so `if not:` still tests a key named `not`.
+    if path == "not" && matches!(parser.peek(), Some(Token::Ident(_))) {
+        return Ok(Condition::NotExists(expect_condition_path(parser)?));
+    }
 
     match parser.peek() {
         Some(Token::Equals) => {
@@ -198,6 +187,30 @@ fn parse_condition(parser: &mut Parser) -> Result<Condition, RuneError> {
             let value = value::parse_value(parser)?;
             Ok(Condition::Equals(path, value))
         }
+        Some(Token::Bang) => {
+            parser.bump()?;
+            if !matches!(parser.bump()?, Token::Equals) {
+                return Err(parser.syntax_error(
+                    "Expected '=' after '!' in condition",
+                    Some("Use: if key != value".into()),
+                    RuneErrorCode::InvalidCondition,
+                ));
+            }
+            let value = value::parse_value(parser)?;
+            Ok(Condition::NotEquals(path, value))
+        }
         _ => Ok(Condition::Exists(path)),
     }
 }
+
+fn expect_condition_path(parser: &mut Parser) -> Result<String, RuneError> {
+    if let Token::Ident(name) = parser.bump()? {
+        Ok(name)
+    } else {
+        Err(parser.syntax_error(
+            "Expected identifier in condition",
+            None,
+            RuneErrorCode::InvalidCondition,
+        ))
+    }
+}
@@ -2,12 +2,14 @@
 // License: MIT
 
 use super::*;
+use crate::ast::{EntryKind, ObjectItem};
+use crate::lexer::Span;
 
 /// NOTE:
 /// `gather` statements are handled in the config loader (`RuneConfig::from_file_with_base`)
 /// which reads the file(s) from disk and injects imported documents.
-/// The parser only needs to record the alias so references like `alias.key.path` can resolve
-/// after imports are injected.
+/// The parser records each statement in `gather_specs` for the loader, and the alias so
+/// references like `alias.key.path` can resolve after imports are injected.
 ///
 /// This parser intentionally inserts a placeholder Document for the alias. The loader will
 /// overwrite it with the real parsed Document.
@@ -15,6 +17,9 @@ pub(super) fn parse_document(parser: &mut Parser) -> Result<Document, RuneError>
     let mut metadata = Vec::new();
     let mut globals = Vec::new();
     let mut items = Vec::new();
+    let mut constants = Vec::new();
+    let mut order = Vec::new();
+    let mut seen = HashMap::new();
 
     while let Some(tok) = parser.peek() {
         match tok {
@@ -27,25 +32,25 @@ pub(super) fn parse_document(parser: &mut Parser) -> Result<Document, RuneError>
             Token::At => {
                 parse_metadata(parser, &mut metadata)?;
             }
-            Token::Ident(_) | Token::String(_) => {
-                parse_top_level_item(parser, &mut globals, &mut items)?;
+            Token::Ident(name) if name == "const" => {
+                parse_const_block(parser, &mut constants)?;
+            }
+            Token::Ident(_) | Token::String(_) | Token::Template(_) => {
+                parse_top_level_item(parser, &mut globals, &mut items, &mut order, &mut seen)?;
             }
             Token::Gather => {
                 parse_gather_statement(parser)?;
             }
             Token::Dollar => {
-                return Err(RuneError::SyntaxError {
-                    message:
-                        "Dollar variables ($env, $sys, $runtime) cannot be assigned at top level"
-                            .into(),
-                    line: parser.line(),
-                    column: parser.column(),
-                    hint: Some(
+                parser.bump()?; // so the error points at the '$'
+                return Err(parser.syntax_error(
+                    "Dollar variables ($env, $sys, $runtime) cannot be assigned at top level",
+                    Some(
                         "Dollar variables can only be used as values, not as top-level definitions"
                             .into(),
                     ),
-                    code: Some(213),
-                });
+                    RuneErrorCode::DollarVariableAsKey,
+                ));
             }
             _ => {
                 return Err(RuneError::InvalidToken {
@@ -53,7 +58,7 @@ pub(super) fn parse_document(parser: &mut Parser) -> Result<Document, RuneError>
                     line: parser.line(),
                     column: parser.column(),
                     hint: Some("Unexpected token at top-level".into()),
-                    code: Some(205),
+                    code: Some(RuneErrorCode::InvalidTopLevel.code()),
                 });
             }
         }
@@ -63,9 +68,45 @@ pub(super) fn parse_document(parser: &mut Parser) -> Result<Document, RuneError>
         metadata,
         globals,
         items,
+        constants,
+        order,
     })
 }
 
+/// `const:` ... `end` declares constants for `$const.NAME`. Repeated blocks add to
+/// the same set; conditionals are not allowed since constants are fixed.
+fn parse_const_block(
+    parser: &mut Parser,
+    constants: &mut Vec<(String, Value)>,
+) -> Result<(), RuneError> {
+    parser.bump()?; // const
+
+    match parser.bump()? {
+        Token::Colon => {}
+        other => {
+            return Err(parser.syntax_error(
+                format!("Expected ':' after const, got {}", other.describe()),
+                Some("Declare constants in a `const:` ... `end` block".into()),
+                RuneErrorCode::InvalidConst,
+            ));
+        }
+    }
+
+    for item in value::parse_object_block(parser, "const")? {
+        match item {
+            ObjectItem::Assign(key, value) => constants.push((key, value)),
+            ObjectItem::IfBlock(_) => {
+                return Err(parser.syntax_error(
+                    "Conditionals are not allowed in a const block",
+                    Some("Give constants fixed values".into()),
+                    RuneErrorCode::InvalidConst,
+                ));
+            }
+        }
+    }
+    Ok(())
+}
+
 fn parse_metadata(
     parser: &mut Parser,
     metadata: &mut Vec<(String, Value)>,
@@ -73,17 +114,21 @@ fn parse_metadata(
     parser.bump()?;
 
     if let Token::Ident(key) = parser.bump()? {
-        let value = value::parse_value(parser)?;
+        // `@key:` ... `end` gives structured metadata, parsed like any object block.
+        let value = if let Some(Token::Colon) = parser.peek() {
+            parser.bump()?;
+            Value::Object(value::parse_object_block(parser, &key)?)
+        } else {
+            value::parse_value(parser)?
+        };
         metadata.push((key, value));
         Ok(())
     } else {
-        Err(RuneError::SyntaxError {
-            message: "Expected identifier after @".into(),
-            line: parser.line(),
-            column: parser.column(),
-            hint: None,
-            code: Some(203),
-        })
+        Err(parser.syntax_error(
+            "Expected identifier after @",
+            None,
+            RuneErrorCode::InvalidMetadata,
+        ))
     }
 }
 
@@ -91,70 +136,36 @@ fn parse_top_level_item(
     parser: &mut Parser,
     globals: &mut Vec<(String, Value)>,
     items: &mut Vec<(String, Value)>,
+    order: &mut Vec<(EntryKind, usize)>,
+    seen: &mut HashMap<String, Span>,
 ) -> Result<(), RuneError> {
     let key = match parser.bump()? {
-        Token::Ident(k) | Token::String(k) => k,
+        Token::Ident(k) => k,
+        token @ (Token::String(_) | Token::Template(_)) => token.into_text().unwrap(),
         _ => unreachable!("parse_top_level_item is only entered on an identifier or string key"),
     };
+    if value::check_duplicate_key(parser, seen, &key, parser.span())? {
+        remove_top_level_entry(globals, items, order, &key);
+    }
 
     match parser.peek() {
         Some(Token::Colon) => {
             parser.bump()?;
-            let mut object_items: Vec<crate::ast::ObjectItem> = Vec::new();
-            let mut closed = false;
-
-            while let Some(tok) = parser.peek() {
-                match tok {
-                    Token::Ident(_) | Token::String(_) => {
-                        let (k, v) = value::parse_assignment(parser)?;
-                        object_items.push(crate::ast::ObjectItem::Assign(k, v));
-                    }
-                    Token::If => {
-                        // block if: if condition: ... endif
-                        object_items.push(conditional::parse_if_block(parser)?);
-                    }
-                    Token::End => {
-                        parser.bump()?;
-                        closed = true;
-                        break;
-                    }
-                    Token::Eof => break,
-                    Token::Newline => {
-                        parser.bump()?;
-                    }
-                    _ => {
-                        return Err(RuneError::InvalidToken {
-                            token: tok.describe(),
-                            line: parser.line(),
-                            column: parser.column(),
-                            hint: Some("Expected key, 'if', or 'end'".into()),
-                            code: Some(207),
-                        });
-                    }
-                }
-            }
-
-            if !closed {
-                return Err(RuneError::UnexpectedEof {
-                    message: format!("Unclosed object block '{}'; expected 'end'", key),
-                    line: parser.line(),
-                    column: parser.column(),
-                    hint: Some(format!("Add another 'end' to close the '{}' block", key)),
-                    code: Some(215),
-                });
-            }
-
+            let object_items = value::parse_object_block(parser, &key)?;
+            order.push((EntryKind::Item, items.len()));
             items.push((key, Value::Object(object_items)));
         }
         Some(Token::Equals) => {
             // Explicit assignment with =
             parser.bump()?;
             let value = value::parse_value(parser)?;
+            order.push((EntryKind::Global, globals.len()));
             globals.push((key, value));
         }
         _ => {
             // Implicit assignment (no = needed)
             let value = value::parse_value(parser)?;
+            order.push((EntryKind::Global, globals.len()));
             globals.push((key, value));
         }
     }
@@ -162,56 +173,123 @@ fn parse_top_level_item(
     Ok(())
 }
 
+/// Drop the global or item named `key`, shifting the `order` entries after it.
+fn remove_top_level_entry(
+    globals: &mut Vec<(String, Value)>,
+    items: &mut Vec<(String, Value)>,
+    order: &mut Vec<(EntryKind, usize)>,
+    key: &str,
+) {
+    for (kind, list) in [(EntryKind::Global, globals), (EntryKind::Item, items)] {
+        let Some(index) = list.iter().position(|(k, _)| k == key) else {
+            continue;
+        };
+        list.remove(index);
+        order.retain(|entry| *entry != (kind, index));
+        for (entry_kind, i) in order.iter_mut() {
+            if *entry_kind == kind && *i > index {
+                *i -= 1;
+            }
+        }
+    }
+}
+
 fn parse_gather_statement(parser: &mut Parser) -> Result<(), RuneError> {
+    let spec = parse_gather_target(parser)?;
+    let alias = spec.alias.clone();
+    parser.gather_specs.push(spec);
+
+    // `as *` hoists the imported keys into this document; there is no alias to resolve.
+    if alias == WILDCARD_ALIAS {
+        return Ok(());
+    }
+
+    // IMPORTANT:
+    // Insert a placeholder import so reference resolution can treat the first segment as an alias.
+    // The loader (RuneConfig::from_file_with_base) will overwrite this with the real document.
+    parser.imports.entry(alias).or_default();
+
+    Ok(())
+}
+
+/// `gather` inside an object block: the import is registered under an alias qualified
+/// by the enclosing block path, and only references inside that block are rewritten to it.
+///
+/// Returns `(alias, qualified alias)` so the block parser can rewrite its references.
+pub(super) fn parse_scoped_gather_statement(
+    parser: &mut Parser,
+) -> Result<(String, String), RuneError> {
+    let spec = parse_gather_target(parser)?;
+    let alias = spec.alias;
+    if alias == WILDCARD_ALIAS {
+        return Err(parser.syntax_error(
+            "Wildcard imports ('as *') are only allowed at top level",
+            Some("Use an alias for block-scoped gathers: gather \"file.rune\" as name".into()),
+            RuneErrorCode::ScopedWildcardGather,
+        ));
+    }
+    let qualified = format!("{}::{}", parser.scope.join("."), alias);
+
+    parser.imports.entry(qualified.clone()).or_default();
+    parser.scoped_imports.push(GatherSpec {
+        alias: qualified.clone(),
+        ..spec
+    });
+
+    Ok((alias, qualified))
+}
+
+/// Alias recorded for `gather "file" as *`.
+pub(crate) const WILDCARD_ALIAS: &str = "*";
+
+/// Parse `gather [optional] "file" [as alias]`.
+///
+/// `optional` is only a keyword here, so it can still be used as a key.
+fn parse_gather_target(parser: &mut Parser) -> Result<GatherSpec, RuneError> {
     parser.bump()?; // consume `gather`
 
-    let filename = if let Token::String(f) = parser.bump()? {
+    let optional = matches!(parser.peek(), Some(Token::Ident(word)) if word == "optional");
+    if optional {
+        parser.bump()?; // consume `optional`
+    }
+
+    let filename = if let Some(f) = parser.bump()?.into_text() {
         f
     } else {
-        return Err(RuneError::SyntaxError {
-            message: "Expected string after gather".into(),
-            line: parser.line(),
-            column: parser.column(),
-            hint: None,
-            code: Some(211),
-        });
+        return Err(parser.syntax_error(
+            "Expected string after gather",
+            None,
+            RuneErrorCode::MissingGatherPath,
+        ));
     };
 
-    let alias = if let Some(Token::As) = parser.peek() {
+    let (alias, explicit_alias) = if let Some(Token::As) = parser.peek() {
         parser.bump()?; // consume `as`
-        if let Token::Ident(a) = parser.bump()? {
-            a
-        } else {
-            return Err(RuneError::SyntaxError {
-                message: "Expected identifier after 'as'".into(),
-                line: parser.line(),
-                column: parser.column(),
-                hint: None,
-                code: Some(212),
-            });
-        }
+        let alias = match parser.bump()? {
+            Token::Ident(a) => a,
+            Token::Star => WILDCARD_ALIAS.to_string(),
+            _ => {
+                return Err(parser.syntax_error(
+                    "Expected identifier or '*' after 'as'",
+                    None,
+                    RuneErrorCode::InvalidGatherAlias,
+                ));
+            }
+        };
+        (alias, true)
     } else {
         // Default alias: file stem (no extension)
         let stem = std::path::Path::new(&filename)
             .file_stem()
             .and_then(|s| s.to_str())
             .unwrap_or("imported");
-        stem.to_string()
+        (stem.to_string(), false)
     };
 
-    // IMPORTANT:
-    // Insert a placeholder import so reference resolution can treat the first segment as an alias.
-    // The loader (RuneConfig::from_file_with_base) will overwrite this with the real document.
-    if !parser.imports.contains_key(&alias) {
-        parser.imports.insert(
-            alias,
-            Document {
-                metadata: vec![],
-                globals: vec![],
-                items: vec![],
-            },
-        );
-    }
-
-    Ok(())
+    Ok(GatherSpec {
+        alias,
+        raw_path: filename,
+        explicit_alias,
+        optional,
+    })
 }
@@ -3,29 +3,77 @@
 
 use std::collections::HashMap;
 
-use crate::RuneError;
 use crate::ast::{Document, Value};
-use crate::lexer::{Lexer, Token};
+use crate::lexer::{Lexer, Span, Token};
+use crate::resolver::ResolverContext;
+use crate::{RuneError, RuneErrorCode};
 
 mod conditional;
 mod document;
 mod reference;
 mod value;
 
+pub(crate) use document::WILDCARD_ALIAS;
+
+/// A `gather` statement, recorded by the parser for the config loader.
+#[derive(Debug, Clone, PartialEq)]
+pub struct GatherSpec {
+    /// The alias the import is reachable under: the `as` alias, or the file stem.
+    /// Block-scoped gathers use the qualified alias, e.g. `app::db`.
+    pub alias: String,
+    /// The path as written in the statement.
+    pub raw_path: String,
+    /// True if the statement used `as alias`.
+    pub explicit_alias: bool,
+    /// True for `gather optional`, which skips a missing file instead of failing.
+    pub optional: bool,
+}
+
 pub struct Parser<'a> {
     lexer: Lexer<'a>,
     peek: Option<Token>,
+    peek_span: Span,
+    /// Span of the token most recently returned by `bump`.
+    last_span: Span,
     pub imports: HashMap<String, Document>,
+    /// Top-level `gather` statements, in source order.
+    pub gather_specs: Vec<GatherSpec>,
+    /// Block-scoped `gather` statements under their qualified alias, in source order.
+    /// References inside the block are rewritten to the qualified alias, so the
+    /// import is only reachable from within that block.
+    pub scoped_imports: Vec<GatherSpec>,
+    /// Keys of the object blocks currently being parsed (outermost first).
+    scope: Vec<String>,
+    /// What `$env` and `$sys` values are resolved against while parsing, holding
+    /// this parse's memory snapshot.
+    context: ResolverContext,
+    /// Let a key repeated at the same level replace the earlier value instead of
+    /// being a syntax error.
+    pub allow_duplicate_keys: bool,
 }
 
 impl<'a> Parser<'a> {
     pub fn new(input: &'a str) -> Result<Self, RuneError> {
+        Self::with_context(input, ResolverContext::global())
+    }
+
+    /// Like `new`, resolving `$env` and `$sys` against `context` instead of the
+    /// process environment. The parser keeps its own copy, so `context` need not
+    /// outlive it.
+    pub fn with_context(input: &'a str, context: &ResolverContext) -> Result<Self, RuneError> {
         let mut lexer = Lexer::new(input);
-        let peek = Some(lexer.next_token()?);
+        let first = lexer.next_spanned()?;
         Ok(Self {
             lexer,
-            peek,
+            peek: Some(first.node),
+            peek_span: first.span,
+            last_span: Span::default(),
             imports: HashMap::new(),
+            gather_specs: Vec::new(),
+            scoped_imports: Vec::new(),
+            scope: Vec::new(),
+            context: context.for_load(),
+            allow_duplicate_keys: false,
         })
     }
 
@@ -39,12 +87,42 @@ impl<'a> Parser<'a> {
             line: self.lexer.line(),
             column: self.lexer.column(),
             hint: None,
-            code: Some(201),
+            code: Some(RuneErrorCode::UnexpectedEof.code()),
         })?;
-        self.peek = Some(self.lexer.next_token()?);
+        let next = self.lexer.next_spanned()?;
+        self.peek = Some(next.node);
+        self.last_span = std::mem::replace(&mut self.peek_span, next.span);
         Ok(curr)
     }
 
+    /// Where the token last returned by `bump` is in the source.
+    pub(crate) fn span(&self) -> Span {
+        self.last_span
+    }
+
+    /// Where the next token is in the source.
+    pub(crate) fn peek_span(&self) -> Span {
+        self.peek_span
+    }
+
+    /// A syntax error pointing at the token last returned by `bump`.
+    pub(crate) fn syntax_error(
+        &self,
+        message: impl Into<String>,
+        hint: Option<String>,
+        code: RuneErrorCode,
+    ) -> RuneError {
+        let span = self.span();
+        RuneError::SyntaxError {
+            message: message.into(),
+            line: span.line,
+            column: span.column,
+            span: Some(span),
+            hint,
+            code: Some(code.code()),
+        }
+    }
+
     pub(crate) fn peek(&self) -> Option<&Token> {
         self.peek.as_ref()
     }
@@ -53,13 +131,11 @@ impl<'a> Parser<'a> {
     pub(crate) fn expect(&mut self, expected: Token) -> Result<Token, RuneError> {
         let token = self.bump()?;
         if token != expected {
-            return Err(RuneError::SyntaxError {
-                message: format!("Expected {:?}, got {:?}", expected, token),
-                line: self.lexer.line(),
-                column: self.lexer.column(),
-                hint: Some("Check your syntax".into()),
-                code: Some(202),
-            });
+            return Err(self.syntax_error(
+                format!("Expected {:?}, got {:?}", expected, token),
+                Some("Check your syntax".into()),
+                RuneErrorCode::UnexpectedToken,
+            ));
         }
         Ok(token)
     }
@@ -2,6 +2,7 @@
 // License: MIT
 
 use super::*;
+use crate::ast::ObjectItem;
 
 pub(super) fn resolve_reference<'b>(
     parser: &'b Parser,
@@ -12,16 +13,18 @@ pub(super) fn resolve_reference<'b>(
         return None;
     }
 
-    // Check if first segment is an import alias
-    let (current_doc, remaining_path): (&Document, &[String]) = {
-        if let Some(import_doc) = parser.imports.get(&path[0]) {
-            // First segment is an import alias, use imported doc and skip first segment
-            (import_doc, &path[1..])
-        } else {
-            // Not an import alias, use current doc and full path
-            (doc, path)
-        }
-    };
+    // Check if the path starts with an import alias. Nested imports are registered as
+    // `outer.inner`, so the longest matching prefix wins.
+    let (current_doc, remaining_path): (&Document, &[String]) = (1..path.len())
+        .rev()
+        .find_map(|n| {
+            parser
+                .imports
+                .get(&path[..n].join("."))
+                .map(|import_doc| (import_doc, &path[n..]))
+        })
+        // Not an import alias, use current doc and full path
+        .unwrap_or((doc, path));
 
     if remaining_path.is_empty() {
         return None;
@@ -47,28 +50,60 @@ pub(super) fn resolve_reference<'b>(
 
     // Traverse the remaining path segments
     for seg in &remaining_path[1..] {
-        match current {
-            Value::Object(items) => {
-                // NOTE:
-                // Objects now contain `ObjectItem` (assignments + if-blocks).
-                // Reference resolution does NOT attempt to evaluate if-blocks here.
-                // The config layer resolves/flatten objects before typical access.
-                //
-                // For references, we only traverse explicit assignments present in the AST.
-                if let Some(v) = items.iter().find_map(|item| match item {
-                    crate::ast::ObjectItem::Assign(k, v) if k == seg => Some(v),
-                    _ => None,
-                }) {
-                    current = v;
-                } else {
-                    return None;
+        // NOTE:
+        // Objects now contain `ObjectItem` (assignments + if-blocks).
+        // Reference resolution does NOT attempt to evaluate if-blocks here.
+        // The config layer resolves/flatten objects before typical access.
+        //
+        // For references, we only traverse explicit assignments present in the AST,
+        // and array elements by numeric segment.
+        current = current.child(seg)?;
+    }
+
+    Some(current)
+}
+
+/// Rewrite references whose first segment is `alias` so they point at `qualified` instead.
+///
+/// Used for block-scoped `gather`: only references lexically inside the block see the import.
+pub(super) fn requalify_items(items: &mut [ObjectItem], alias: &str, qualified: &str) {
+    for item in items {
+        match item {
+            ObjectItem::Assign(_, value) => requalify_value(value, alias, qualified),
+            ObjectItem::IfBlock(block) => {
+                requalify_items(&mut block.then_items, alias, qualified);
+                if let Some(else_items) = block.else_items.as_mut() {
+                    requalify_items(else_items, alias, qualified);
                 }
             }
-            _ => {
-                return None;
-            }
         }
     }
+}
 
-    Some(current)
+fn requalify_value(value: &mut Value, alias: &str, qualified: &str) {
+    match value {
+        Value::Reference(path) => {
+            if path.first().is_some_and(|first| first == alias) {
+                path[0] = qualified.to_string();
+            } else if path.len() > 1 && path[0] == "var" && path[1] == alias {
+                path[1] = qualified.to_string();
+            }
+        }
+        Value::Namespaced(path) if path.len() > 1 && path[0] == "var" && path[1] == alias => {
+            path[1] = qualified.to_string();
+        }
+        Value::Array(values) | Value::Interpolated(values) => {
+            for v in values {
+                requalify_value(v, alias, qualified);
+            }
+        }
+        Value::Object(items) => requalify_items(items, alias, qualified),
+        Value::Conditional(cond) => {
+            requalify_value(&mut cond.then_value, alias, qualified);
+            if let Some(else_value) = cond.else_value.as_mut() {
+                requalify_value(else_value, alias, qualified);
+            }
+        }
+        _ => {}
+    }
 }
@@ -4,7 +4,7 @@
 #[cfg(test)]
 use super::*;
 #[cfg(test)]
-use crate::ast::{ObjectItem, Value};
+use crate::ast::{EntryKind, ObjectItem, Value};
 
 #[test]
 fn test_parser_basic_document() {
@@ -450,9 +450,504 @@ fn test_invalid_key_reports_readable_token() {
 
     match error {
         RuneError::InvalidToken { token, .. } => {
-            // Readable label, not the `Number(123.0)` Debug form.
+            // Readable label, not the `Int(123)` Debug form.
             assert_eq!(token, "number 123");
         }
         other => panic!("Expected InvalidToken, got {:?}", other),
     }
 }
+
+#[test]
+fn test_top_level_source_order_is_recoverable() {
+    let input = r#"
+name "RuneApp"
+app:
+  port 8080
+end
+debug true
+server:
+  host "localhost"
+end
+"#;
+
+    let mut parser = Parser::new(input).expect("Failed to create parser");
+    let doc = parser.parse_document().expect("Failed to parse document");
+
+    assert_eq!(doc.globals.len(), 2);
+    assert_eq!(doc.items.len(), 2);
+
+    let ordered: Vec<(EntryKind, &str)> = doc
+        .entries()
+        .into_iter()
+        .map(|(kind, key, _)| (kind, key))
+        .collect();
+    assert_eq!(
+        ordered,
+        vec![
+            (EntryKind::Global, "name"),
+            (EntryKind::Item, "app"),
+            (EntryKind::Global, "debug"),
+            (EntryKind::Item, "server"),
+        ]
+    );
+}
+
+#[test]
+fn test_parse_base64_literal() {
+    let input = "icon b64\"SGVsbG8=\"\n";
+
+    let mut parser = Parser::new(input).expect("Failed to create parser");
+    let doc = parser.parse_document().expect("Failed to parse doc");
+
+    let value = doc.globals[0].1.clone();
+    assert_eq!(value, Value::Bytes(b"Hello".to_vec()));
+
+    // Display writes the literal back in the same form.
+    assert_eq!(value.to_string(), "b64\"SGVsbG8=\"");
+
+    let bytes: Vec<u8> = value.try_into().expect("bytes should convert to Vec<u8>");
+    assert_eq!(bytes, b"Hello");
+}
+
+#[test]
+fn test_invalid_base64_literal_reports_error() {
+    let input = "icon b64\"not base64!\"\n";
+
+    let mut parser = Parser::new(input).expect("Failed to create parser");
+    let error = parser
+        .parse_document()
+        .expect_err("invalid base64 should fail");
+
+    match error {
+        RuneError::TypeError { message, code, .. } => {
+            assert!(message.starts_with("Invalid base64 literal"));
+            assert_eq!(code, Some(216));
+        }
+        other => panic!("Expected TypeError, got {:?}", other),
+    }
+}
+
+#[test]
+fn test_identifiers_starting_with_b_still_lex() {
+    let input = "b64 \"plain\"\nbackground \"blue\"\n";
+
+    let mut parser = Parser::new(input).expect("Failed to create parser");
+    let doc = parser.parse_document().expect("Failed to parse doc");
+
+    assert_eq!(doc.globals[0].0, "b64");
+    assert_eq!(doc.globals[1].0, "background");
+}
+
+#[cfg(feature = "chrono")]
+#[test]
+fn test_parse_utc_timestamp_literal() {
+    let input = "starts_at t\"2024-01-01T00:00:00Z\"\n";
+
+    let mut parser = Parser::new(input).expect("Failed to create parser");
+    let doc = parser.parse_document().expect("Failed to parse doc");
+
+    let value = doc.globals[0].1.clone();
+    assert_eq!(value.to_string(), "t\"2024-01-01T00:00:00+00:00\"");
+
+    let timestamp: chrono::DateTime<chrono::Utc> =
+        value.try_into().expect("timestamp should convert");
+    assert_eq!(timestamp.timestamp(), 1_704_067_200);
+}
+
+#[cfg(feature = "chrono")]
+#[test]
+fn test_malformed_timestamp_reports_offending_text() {
+    let input = "starts_at t\"2024-13-01 noon\"\n";
+
+    let mut parser = Parser::new(input).expect("Failed to create parser");
+    let error = parser
+        .parse_document()
+        .expect_err("malformed timestamp should fail");
+
+    match error {
+        RuneError::TypeError { message, code, .. } => {
+            assert!(message.contains("'2024-13-01 noon'"));
+            assert_eq!(code, Some(217));
+        }
+        other => panic!("Expected TypeError, got {:?}", other),
+    }
+}
+
+#[cfg(not(feature = "chrono"))]
+#[test]
+fn test_timestamp_literal_requires_chrono_feature() {
+    let mut parser = Parser::new("starts_at t\"2024-01-01T00:00:00Z\"\n").unwrap();
+    let error = parser
+        .parse_document()
+        .expect_err("timestamps need the chrono feature");
+
+    assert!(matches!(
+        error,
+        RuneError::TypeError {
+            code: Some(229),
+            ..
+        }
+    ));
+}
+
+#[test]
+fn test_if_block_structure_is_inspectable_from_crate_root() {
+    use crate::{Condition, IfBlock, ObjectItem as RootObjectItem};
+
+    let input = r#"
+app:
+  if environment = "production":
+    workers 8
+  else:
+    workers 2
+  endif
+end
+"#;
+
+    let mut parser = Parser::new(input).expect("Failed to create parser");
+    let doc = parser.parse_document().expect("Failed to parse doc");
+
+    let Value::Object(items) = &doc.items[0].1 else {
+        panic!("Expected 'app' to be an Object");
+    };
+    let RootObjectItem::IfBlock(block) = &items[0] else {
+        panic!("Expected the first item to be an if-block");
+    };
+    let block: &IfBlock = block;
+
+    assert_eq!(
+        block.condition,
+        Condition::Equals("environment".into(), Value::String("production".into()))
+    );
+    assert_eq!(
+        block.then_items,
+        vec![RootObjectItem::Assign("workers".into(), Value::Int(8))]
+    );
+    assert_eq!(
+        block.else_items,
+        Some(vec![RootObjectItem::Assign(
+            "workers".into(),
+            Value::Int(2)
+        )])
+    );
+}
+
+#[test]
+fn test_env_default_in_value_position() {
+    unsafe {
+        std::env::remove_var("RUNE_PARSER_TEST_UNSET");
+        std::env::set_var("RUNE_PARSER_TEST_SET", "db.internal");
+    }
+
+    let input = r#"
+port $env.RUNE_PARSER_TEST_UNSET:-8080
+host $env.RUNE_PARSER_TEST_SET:-"localhost"
+"#;
+
+    let mut parser = Parser::new(input).expect("Failed to create parser");
+    let doc = parser.parse_document().expect("Failed to parse doc");
+
+    assert_eq!(doc.globals[0].1, Value::Int(8080));
+    assert_eq!(doc.globals[1].1, Value::String("db.internal".into()));
+}
+
+#[test]
+fn test_const_block_is_kept_apart_from_globals() {
+    let input = r#"
+const:
+  RATE 0.25
+  GREETING "hello"
+end
+
+radius 2
+"#;
+
+    let mut parser = Parser::new(input).expect("Failed to create parser");
+    let doc = parser.parse_document().expect("Failed to parse doc");
+
+    assert_eq!(
+        doc.constants,
+        vec![
+            ("RATE".to_string(), Value::Float(0.25)),
+            ("GREETING".to_string(), Value::String("hello".into())),
+        ]
+    );
+    assert_eq!(doc.globals, vec![("radius".to_string(), Value::Int(2))]);
+    assert!(doc.items.is_empty());
+
+    let source = doc.to_string();
+    let mut reparsed = Parser::new(&source).expect("Failed to create parser");
+    assert_eq!(reparsed.parse_document().expect("Failed to reparse"), doc);
+}
+
+#[test]
+fn test_quoted_const_key_is_an_ordinary_block() {
+    let input = "\"const\":\n  retries 3\nend\n";
+
+    let mut parser = Parser::new(input).expect("Failed to create parser");
+    let doc = parser.parse_document().expect("Failed to parse doc");
+
+    assert!(doc.constants.is_empty());
+    assert_eq!(doc.items.len(), 1);
+    assert_eq!(doc.items[0].0, "const");
+
+    let source = doc.to_string();
+    let mut reparsed = Parser::new(&source).expect("Failed to create parser");
+    assert_eq!(reparsed.parse_document().expect("Failed to reparse"), doc);
+}
+
+#[test]
+fn test_syntax_error_points_at_offending_token() {
+    let input = "name \"app\"\ngather 42\n";
+
+    let mut parser = Parser::new(input).expect("Failed to create parser");
+    let error = parser
+        .parse_document()
+        .expect_err("gather needs a file name");
+
+    match error {
+        RuneError::SyntaxError {
+            line, column, span, ..
+        } => {
+            assert_eq!((line, column), (2, 8));
+            let span = span.expect("parser errors carry a span");
+            assert_eq!(&input[span.start..span.end], "42");
+        }
+        other => panic!("Expected SyntaxError, got {:?}", other),
+    }
+}
+
+#[test]
+fn test_inline_objects() {
+    let input = r#"
+servers [ { host "a" port 1 } { host "b" port 2 } ]
+point { x 1 y 2 }
+window {
+  size { width 800 height 600 }
+  title "main"
+}
+"#;
+
+    let mut parser = Parser::new(input).expect("Failed to create parser");
+    let doc = parser.parse_document().expect("Failed to parse document");
+
+    let object = |pairs: Vec<(&str, Value)>| {
+        Value::Object(
+            pairs
+                .into_iter()
+                .map(|(k, v)| ObjectItem::Assign(k.into(), v))
+                .collect(),
+        )
+    };
+    let server = |host: &str, port| {
+        object(vec![
+            ("host", Value::String(host.into())),
+            ("port", Value::Int(port)),
+        ])
+    };
+
+    assert_eq!(
+        doc.globals,
+        vec![
+            (
+                "servers".to_string(),
+                Value::Array(vec![server("a", 1), server("b", 2)])
+            ),
+            (
+                "point".to_string(),
+                object(vec![("x", Value::Int(1)), ("y", Value::Int(2))])
+            ),
+            (
+                "window".to_string(),
+                object(vec![
+                    (
+                        "size",
+                        object(vec![
+                            ("width", Value::Int(800)),
+                            ("height", Value::Int(600))
+                        ])
+                    ),
+                    ("title", Value::String("main".into())),
+                ])
+            ),
+        ]
+    );
+}
+
+#[test]
+fn test_unclosed_inline_object() {
+    let mut parser = Parser::new("point { x 1 y 2\n").expect("Failed to create parser");
+    match parser.parse_document() {
+        Err(RuneError::UnexpectedEof { code, .. }) => assert_eq!(code, Some(215)),
+        other => panic!("Expected UnexpectedEof, got {:?}", other),
+    }
+}
+
+#[test]
+fn test_duplicate_keys_are_rejected() {
+    for (input, lines) in [
+        ("server:\n  port 80\n  host \"a\"\n  port 81\nend\n", (4, 2)),
+        ("name \"a\"\n\nname \"b\"\n", (3, 1)),
+        ("point { x 1\n  x 2 }\n", (2, 1)),
+    ] {
+        let mut parser = Parser::new(input).expect("Failed to create parser");
+        match parser.parse_document() {
+            Err(RuneError::SyntaxError {
+                message,
+                line,
+                code,
+                ..
+            }) => {
+                assert_eq!(code, Some(220), "{}", input);
+                assert_eq!(line, lines.0, "{}", input);
+                assert!(
+                    message.contains(&format!("line {}", lines.0))
+                        && message.contains(&format!("line {}", lines.1)),
+                    "{}",
+                    message
+                );
+            }
+            other => panic!(
+                "Expected duplicate key error for {:?}, got {:?}",
+                input, other
+            ),
+        }
+    }
+}
+
+#[test]
+fn test_allow_duplicate_keys_keeps_last_value() {
+    let input = "name \"a\"\nserver:\n  port 80\n  port 81\nend\nname \"b\"\n";
+
+    let mut parser = Parser::new(input).expect("Failed to create parser");
+    parser.allow_duplicate_keys = true;
+    let doc = parser.parse_document().expect("Failed to parse document");
+
+    assert_eq!(
+        doc.globals,
+        vec![("name".to_string(), Value::String("b".into()))]
+    );
+    assert_eq!(
+        doc.items,
+        vec![(
+            "server".to_string(),
+            Value::Object(vec![ObjectItem::Assign("port".into(), Value::Int(81))])
+        )]
+    );
+    assert_eq!(
+        doc.order,
+        vec![(EntryKind::Item, 0), (EntryKind::Global, 0)]
+    );
+}
+
+#[test]
+fn test_parser_records_top_level_gathers() {
+    use super::GatherSpec;
+
+    let input = "gather \"gather.rune\"\ngather optional \"lib/base.rune\" as base\noptional true\napp:\n  gather \"db.rune\" as db\nend\n";
+    let mut parser = Parser::new(input).unwrap();
+    parser.parse_document().unwrap();
+
+    assert_eq!(
+        parser.gather_specs,
+        vec![
+            GatherSpec {
+                alias: "gather".into(),
+                raw_path: "gather.rune".into(),
+                explicit_alias: false,
+                optional: false,
+            },
+            GatherSpec {
+                alias: "base".into(),
+                raw_path: "lib/base.rune".into(),
+                explicit_alias: true,
+                optional: true,
+            },
+        ]
+    );
+    // Block-scoped gathers are recorded separately, under their qualified alias.
+    assert_eq!(
+        parser.scoped_imports,
+        vec![GatherSpec {
+            alias: "app::db".into(),
+            raw_path: "db.rune".into(),
+            explicit_alias: true,
+            optional: false,
+        }]
+    );
+}
+
+#[test]
+fn test_parse_regex_null_and_conditional_values() {
+    use crate::ast::{Condition, ConditionalValue};
+
+    let input = r#"
+mode "dev"
+pattern r"^app-\d+$"
+proxy null
+level if mode = "dev" "debug" else "info"
+"#;
+
+    let mut parser = Parser::new(input).expect("Failed to create parser");
+    let doc = parser.parse_document().expect("Failed to parse document");
+    let global = |key: &str| {
+        doc.globals
+            .iter()
+            .find(|(k, _)| k == key)
+            .map(|(_, v)| v)
+            .unwrap()
+    };
+
+    let Value::Regex(re) = global("pattern") else {
+        panic!("Expected a regex, got {:?}", global("pattern"));
+    };
+    assert!(re.is_match("app-42"));
+    assert_eq!(global("proxy"), &Value::Null);
+    assert_eq!(
+        global("level"),
+        &Value::Conditional(Box::new(ConditionalValue {
+            condition: Condition::Equals("mode".into(), Value::String("dev".into())),
+            then_value: Value::String("debug".into()),
+            else_value: Some(Value::String("info".into())),
+        }))
+    );
+
+    // Each new variant has a JSON form.
+    let json: serde_json::Value =
+        serde_json::from_str(&crate::export::export_document_to_json(&doc).unwrap()).unwrap();
+    assert_eq!(json["globals"]["pattern"]["regex"], r"^app-\d+$");
+    assert!(json["globals"]["proxy"].is_null());
+    assert_eq!(json["globals"]["level"]["conditional"]["then"], "debug");
+}
+
+#[test]
+fn test_parse_negated_conditions_and_round_trip_them() {
+    use crate::ast::Condition;
+
+    let input = r#"
+mode "dev"
+cache if mode != "prod" 0 else 300
+quiet if not verbose true else false
+bare if not "x" else "y"
+"#;
+
+    let mut parser = Parser::new(input).expect("Failed to create parser");
+    let doc = parser.parse_document().expect("Failed to parse document");
+    let condition = |key: &str| match doc.globals.iter().find(|(k, _)| k == key) {
+        Some((_, Value::Conditional(c))) => c.condition.clone(),
+        other => panic!("Expected a conditional for {key}, got {other:?}"),
+    };
+
+    assert_eq!(
+        condition("cache"),
+        Condition::NotEquals("mode".into(), Value::String("prod".into()))
+    );
+    assert_eq!(condition("quiet"), Condition::NotExists("verbose".into()));
+    // Without a path after it, `not` is just a key name.
+    assert_eq!(condition("bare"), Condition::Exists("not".into()));
+
+    let reparsed = Parser::new(&doc.to_string())
+        .and_then(|mut p| p.parse_document())
+        .expect("Display output should parse");
+    assert_eq!(reparsed, doc);
+}
@@ -3,73 +3,34 @@
 
 use super::*;
 use crate::ast::ObjectItem;
-use crate::resolver::{expand_dollar_string, parse_dollar_reference};
+use crate::lexer::Span;
+use crate::resolver::{
+    expand_template, parse_dollar_reference, resolve_env_or, resolve_env_strict,
+};
+use base64::Engine;
+use base64::engine::general_purpose::STANDARD as BASE64;
 use regex::Regex;
 
-pub(super) fn parse_assignment(parser: &mut Parser) -> Result<(String, Value), RuneError> {
+fn parse_assignment(parser: &mut Parser) -> Result<(String, Value), RuneError> {
     let key = match parser.bump()? {
         // Bare identifier keys (`name "value"`) and quoted-string keys
         // (`"$var.mod+r" "reload"`) are both accepted; string keys are stored
         // literally with no interpolation.
-        Token::Ident(k) | Token::String(k) => k,
+        Token::Ident(k) => k,
+        token @ (Token::String(_) | Token::Template(_)) => token.into_text().unwrap(),
         _ => {
-            return Err(RuneError::SyntaxError {
-                message: "Expected identifier or string for assignment".into(),
-                line: parser.line(),
-                column: parser.column(),
-                hint: None,
-                code: Some(208),
-            });
+            return Err(parser.syntax_error(
+                "Expected identifier or string for assignment",
+                None,
+                RuneErrorCode::InvalidKey,
+            ));
         }
     };
 
     match parser.peek() {
         Some(Token::Colon) => {
             parser.bump()?;
-            let mut items: Vec<ObjectItem> = Vec::new();
-            let mut closed = false;
-
-            while let Some(tok) = parser.peek() {
-                match tok {
-                    Token::Ident(_) | Token::String(_) => {
-                        let (k, v) = parse_assignment(parser)?;
-                        items.push(ObjectItem::Assign(k, v));
-                    }
-                    Token::If => {
-                        // block if: if condition: ... endif
-                        items.push(conditional::parse_if_block(parser)?);
-                    }
-                    Token::End => {
-                        parser.bump()?;
-                        closed = true;
-                        break;
-                    }
-                    Token::Eof => break,
-                    Token::Newline => {
-                        parser.bump()?;
-                    }
-                    _ => {
-                        return Err(RuneError::InvalidToken {
-                            token: tok.describe(),
-                            line: parser.line(),
-                            column: parser.column(),
-                            hint: Some("Expected key, 'if', or 'end'".into()),
-                            code: Some(207),
-                        });
-                    }
-                }
-            }
-
-            if !closed {
-                return Err(RuneError::UnexpectedEof {
-                    message: format!("Unclosed object block '{}'; expected 'end'", key),
-                    line: parser.line(),
-                    column: parser.column(),
-                    hint: Some(format!("Add another 'end' to close the '{}' block", key)),
-                    code: Some(215),
-                });
-            }
-
+            let items = parse_object_block(parser, &key)?;
             return Ok((key, Value::Object(items)));
         }
         Some(Token::Equals) => {
@@ -82,15 +43,131 @@ pub(super) fn parse_assignment(parser: &mut Parser) -> Result<(String, Value), R
     Ok((key, value))
 }
 
+/// Parse an assignment into the object `items`. A key already assigned in `items` is
+/// an error, or replaces the earlier assignment when duplicates are allowed.
+pub(super) fn parse_object_assignment(
+    parser: &mut Parser,
+    items: &mut Vec<ObjectItem>,
+    seen: &mut HashMap<String, Span>,
+) -> Result<(), RuneError> {
+    let span = parser.peek_span();
+    let (k, v) = parse_assignment(parser)?;
+    if check_duplicate_key(parser, seen, &k, span)? {
+        items.retain(|item| !matches!(item, ObjectItem::Assign(key, _) if *key == k));
+    }
+    items.push(ObjectItem::Assign(k, v));
+    Ok(())
+}
+
+/// Record `key`, written at `span`, among the keys `seen` at one level. Returns whether
+/// it was seen before, which is only `Ok` when the parser allows duplicate keys.
+pub(super) fn check_duplicate_key(
+    parser: &Parser,
+    seen: &mut HashMap<String, Span>,
+    key: &str,
+    span: Span,
+) -> Result<bool, RuneError> {
+    let Some(first) = seen.insert(key.to_string(), span) else {
+        return Ok(false);
+    };
+    if parser.allow_duplicate_keys {
+        return Ok(true);
+    }
+    Err(RuneError::SyntaxError {
+        message: format!(
+            "Duplicate key '{}' on line {}; it is already set on line {}",
+            key, span.line, first.line
+        ),
+        line: span.line,
+        column: span.column,
+        span: Some(span),
+        hint: Some("Remove one of them, or merge their values".into()),
+        code: Some(RuneErrorCode::DuplicateKey.code()),
+    })
+}
+
+/// Parse the body of a `key:` ... `end` block (the colon is already consumed).
+///
+/// Shared by top-level blocks and nested blocks so both accept the same items:
+/// assignments, block ifs, and block-scoped `gather` statements.
+pub(super) fn parse_object_block(
+    parser: &mut Parser,
+    key: &str,
+) -> Result<Vec<ObjectItem>, RuneError> {
+    parser.scope.push(key.to_string());
+    let result = parse_object_block_items(parser, key);
+    parser.scope.pop();
+    result
+}
+
+fn parse_object_block_items(parser: &mut Parser, key: &str) -> Result<Vec<ObjectItem>, RuneError> {
+    let mut items: Vec<ObjectItem> = Vec::new();
+    let mut seen = HashMap::new();
+    let mut scoped_aliases: Vec<(String, String)> = Vec::new();
+    let mut closed = false;
+
+    while let Some(tok) = parser.peek() {
+        match tok {
+            Token::Ident(_) | Token::String(_) | Token::Template(_) => {
+                parse_object_assignment(parser, &mut items, &mut seen)?;
+            }
+            Token::If => {
+                // block if: if condition: ... endif
+                items.push(conditional::parse_if_block(parser)?);
+            }
+            Token::Gather => {
+                scoped_aliases.push(document::parse_scoped_gather_statement(parser)?);
+            }
+            Token::End => {
+                parser.bump()?;
+                closed = true;
+                break;
+            }
+            Token::Eof => break,
+            Token::Newline => {
+                parser.bump()?;
+            }
+            _ => {
+                return Err(RuneError::InvalidToken {
+                    token: tok.describe(),
+                    line: parser.line(),
+                    column: parser.column(),
+                    hint: Some("Expected key, 'if', 'gather', or 'end'".into()),
+                    code: Some(RuneErrorCode::InvalidBlockEntry.code()),
+                });
+            }
+        }
+    }
+
+    if !closed {
+        return Err(RuneError::UnexpectedEof {
+            message: format!("Unclosed object block '{}'; expected 'end'", key),
+            line: parser.line(),
+            column: parser.column(),
+            hint: Some(format!("Add another 'end' to close the '{}' block", key)),
+            code: Some(RuneErrorCode::UnclosedBlock.code()),
+        });
+    }
+
+    for (alias, qualified) in &scoped_aliases {
+        reference::requalify_items(&mut items, alias, qualified);
+    }
+
+    Ok(items)
+}
+
 pub(super) fn parse_value(parser: &mut Parser) -> Result<Value, RuneError> {
     match parser.peek() {
-        Some(Token::String(_)) => parse_string_value(parser),
-        Some(Token::Number(_)) => parse_number_value(parser),
+        Some(Token::String(_) | Token::Template(_)) => parse_string_value(parser),
+        Some(Token::Int(_) | Token::Float(_)) => parse_number_value(parser),
         Some(Token::Bool(_)) => parse_bool_value(parser),
         Some(Token::Regex(_)) => parse_regex_value(parser),
+        Some(Token::Bytes(_)) => parse_bytes_value(parser),
+        Some(Token::DateTime(_)) => parse_datetime_value(parser),
         Some(Token::Dollar) => parse_dollar_reference_value(parser),
         Some(Token::Ident(_)) => parse_reference_value(parser),
         Some(Token::LBracket) => parse_array_value(parser),
+        Some(Token::LBrace) => parse_inline_object(parser),
         Some(Token::Null) => parse_null_value(parser),
         Some(Token::If) => {
             // IMPORTANT:
@@ -107,25 +184,25 @@ pub(super) fn parse_value(parser: &mut Parser) -> Result<Value, RuneError> {
                 line: parser.line(),
                 column: parser.column(),
                 hint: Some("Unexpected token in value position".into()),
-                code: Some(210),
+                code: Some(RuneErrorCode::InvalidValue.code()),
             })
         }
     }
 }
 
 fn parse_string_value(parser: &mut Parser) -> Result<Value, RuneError> {
-    if let Token::String(s) = parser.bump()? {
-        expand_dollar_string(&s)
-    } else {
-        unreachable!()
+    match parser.bump()? {
+        Token::String(s) => Ok(Value::String(s)),
+        Token::Template(pieces) => expand_template(&pieces, &parser.context),
+        _ => unreachable!(),
     }
 }
 
 fn parse_number_value(parser: &mut Parser) -> Result<Value, RuneError> {
-    if let Token::Number(n) = parser.bump()? {
-        Ok(Value::Number(n))
-    } else {
-        unreachable!()
+    match parser.bump()? {
+        Token::Int(n) => Ok(Value::Int(n)),
+        Token::Float(n) => Ok(Value::Float(n)),
+        _ => unreachable!(),
     }
 }
 
@@ -137,6 +214,52 @@ fn parse_bool_value(parser: &mut Parser) -> Result<Value, RuneError> {
     }
 }
 
+fn parse_bytes_value(parser: &mut Parser) -> Result<Value, RuneError> {
+    if let Token::Bytes(encoded) = parser.bump()? {
+        let bytes = BASE64
+            .decode(encoded.trim())
+            .map_err(|e| RuneError::TypeError {
+                message: format!("Invalid base64 literal: {}", e),
+                line: parser.line(),
+                column: parser.column(),
+                hint: Some("b64\"...\" expects standard base64 with '=' padding".into()),
+                code: Some(RuneErrorCode::InvalidBase64.code()),
+            })?;
+        Ok(Value::Bytes(bytes))
+    } else {
+        unreachable!()
+    }
+}
+
+#[cfg(feature = "chrono")]
+fn parse_datetime_value(parser: &mut Parser) -> Result<Value, RuneError> {
+    if let Token::DateTime(text) = parser.bump()? {
+        let timestamp =
+            chrono::DateTime::parse_from_rfc3339(&text).map_err(|e| RuneError::TypeError {
+                message: format!("Invalid timestamp '{}': {}", text, e),
+                line: parser.line(),
+                column: parser.column(),
+                hint: Some("Use an ISO-8601 timestamp such as t\"2024-01-01T00:00:00Z\"".into()),
+                code: Some(RuneErrorCode::InvalidTimestampLiteral.code()),
+            })?;
+        Ok(Value::DateTime(timestamp))
+    } else {
+        unreachable!()
+    }
+}
+
+#[cfg(not(feature = "chrono"))]
+fn parse_datetime_value(parser: &mut Parser) -> Result<Value, RuneError> {
+    let token = parser.bump()?;
+    Err(RuneError::TypeError {
+        message: format!("Unsupported {}", token.describe()),
+        line: parser.line(),
+        column: parser.column(),
+        hint: Some("Enable the `chrono` feature of rune-cfg to use t\"...\" timestamps".into()),
+        code: Some(RuneErrorCode::ChronoDisabled.code()),
+    })
+}
+
 fn parse_regex_value(parser: &mut Parser) -> Result<Value, RuneError> {
     if let Token::Regex(pattern) = parser.bump()? {
         let regex = Regex::new(&pattern).map_err(|e| RuneError::TypeError {
@@ -144,7 +267,7 @@ fn parse_regex_value(parser: &mut Parser) -> Result<Value, RuneError> {
             line: parser.line(),
             column: parser.column(),
             hint: Some("Check your regex syntax".into()),
-            code: Some(211),
+            code: Some(RuneErrorCode::InvalidRegexLiteral.code()),
         })?;
         Ok(Value::Regex(regex))
     } else {
@@ -160,45 +283,63 @@ fn parse_null_value(parser: &mut Parser) -> Result<Value, RuneError> {
 fn parse_dollar_reference_value(parser: &mut Parser) -> Result<Value, RuneError> {
     parser.bump()?; // $
 
+    // Namespaces other than env/sys/runtime/var are left for registered providers.
     let namespace = if let Token::Ident(name) = parser.bump()? {
-        if name != "env" && name != "sys" && name != "runtime" && name != "var" {
-            return Err(RuneError::SyntaxError {
-                message: format!("Unknown namespace ${}", name),
-                line: parser.line(),
-                column: parser.column(),
-                hint: Some("Use $env, $sys, $runtime, or $var".into()),
-                code: Some(209),
-            });
-        }
         name
     } else {
-        return Err(RuneError::SyntaxError {
-            message: "Expected identifier after $".into(),
-            line: parser.line(),
-            column: parser.column(),
-            hint: None,
-            code: Some(209),
-        });
+        return Err(parser.syntax_error(
+            "Expected identifier after $",
+            None,
+            RuneErrorCode::InvalidDollarVariable,
+        ));
     };
 
     let mut path = vec![namespace];
 
     while let Some(Token::Dot) = parser.peek() {
         parser.bump()?;
-        if let Token::Ident(name) = parser.bump()? {
+        // Quoted segments (`servers."east.1".host`) address keys containing dots.
+        let segment = match parser.bump()? {
+            Token::Ident(name) => Some(name),
+            token => token.into_text(),
+        };
+        if let Some(name) = segment {
             path.push(name);
         } else {
-            return Err(RuneError::SyntaxError {
-                message: "Expected identifier after '.'".into(),
-                line: parser.line(),
-                column: parser.column(),
-                hint: None,
-                code: Some(210),
-            });
+            return Err(parser.syntax_error(
+                "Expected identifier after '.'",
+                None,
+                RuneErrorCode::InvalidReference,
+            ));
+        }
+    }
+
+    if let Some(Token::ColonDash) = parser.peek() {
+        parser.bump()?;
+        if path[0] != "env" {
+            return Err(parser.syntax_error(
+                format!("Fallback defaults are not supported for ${}", path[0]),
+                Some("Only $env.NAME:-default takes a default".into()),
+                RuneErrorCode::UnsupportedEnvModifier,
+            ));
         }
+        let default = parse_value(parser)?;
+        return resolve_env_or(&path, default, &parser.context);
     }
 
-    parse_dollar_reference(path)
+    if let Some(Token::Bang) = parser.peek() {
+        parser.bump()?;
+        if path[0] != "env" {
+            return Err(parser.syntax_error(
+                format!("'!' is not supported for ${}", path[0]),
+                Some("Only $env.NAME! requires its variable to be set".into()),
+                RuneErrorCode::UnsupportedEnvModifier,
+            ));
+        }
+        return resolve_env_strict(&path, &parser.context);
+    }
+
+    parse_dollar_reference(path, &parser.context)
 }
 
 fn parse_reference_value(parser: &mut Parser) -> Result<Value, RuneError> {
@@ -212,16 +353,19 @@ fn parse_reference_value(parser: &mut Parser) -> Result<Value, RuneError> {
 
     while let Some(Token::Dot) = parser.peek() {
         parser.bump()?;
-        if let Token::Ident(name) = parser.bump()? {
+        // Quoted segments (`servers."east.1".host`) address keys containing dots.
+        let segment = match parser.bump()? {
+            Token::Ident(name) => Some(name),
+            token => token.into_text(),
+        };
+        if let Some(name) = segment {
             path.push(name);
         } else {
-            return Err(RuneError::SyntaxError {
-                message: "Expected identifier after '.'".into(),
-                line: parser.line(),
-                column: parser.column(),
-                hint: None,
-                code: Some(210),
-            });
+            return Err(parser.syntax_error(
+                "Expected identifier after '.'",
+                None,
+                RuneErrorCode::InvalidReference,
+            ));
         }
     }
 
@@ -249,3 +393,44 @@ fn parse_array_value(parser: &mut Parser) -> Result<Value, RuneError> {
 
     Ok(Value::Array(arr))
 }
+
+/// An inline object, `{ host "a" port 1 }`: assignments separated by whitespace or
+/// newlines, up to the closing brace.
+fn parse_inline_object(parser: &mut Parser) -> Result<Value, RuneError> {
+    parser.bump()?; // '{'
+    let mut items = Vec::new();
+    let mut seen = HashMap::new();
+
+    while let Some(tok) = parser.peek() {
+        match tok {
+            Token::RBrace => {
+                parser.bump()?;
+                return Ok(Value::Object(items));
+            }
+            Token::Newline => {
+                parser.bump()?;
+            }
+            Token::Ident(_) | Token::String(_) | Token::Template(_) => {
+                parse_object_assignment(parser, &mut items, &mut seen)?;
+            }
+            Token::Eof => break,
+            _ => {
+                return Err(RuneError::InvalidToken {
+                    token: tok.describe(),
+                    line: parser.line(),
+                    column: parser.column(),
+                    hint: Some("Expected key or '}' in inline object".into()),
+                    code: Some(RuneErrorCode::InvalidBlockEntry.code()),
+                });
+            }
+        }
+    }
+
+    Err(RuneError::UnexpectedEof {
+        message: "Unclosed inline object; expected '}'".into(),
+        line: parser.line(),
+        column: parser.column(),
+        hint: Some("Add a '}' to close the object".into()),
+        code: Some(RuneErrorCode::UnclosedBlock.code()),
+    })
+}
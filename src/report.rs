@@ -0,0 +1,210 @@
+// Author: Dustin Pilgrim
+// License: MIT
+
+//! miette support: [`RuneError`] is a [`Diagnostic`], and [`RuneReport`] pairs an
+//! error with the source text it came from, so it renders with the offending line
+//! and the problem underlined.
+
+use std::fmt;
+
+use miette::{Diagnostic, LabeledSpan, NamedSource, SourceCode};
+
+use crate::RuneError;
+
+impl Diagnostic for RuneError {
+    fn code<'a>(&'a self) -> Option<Box<dyn fmt::Display + 'a>> {
+        RuneError::code(self).map(|code| Box::new(format!("rune::{}", code)) as _)
+    }
+
+    fn help<'a>(&'a self) -> Option<Box<dyn fmt::Display + 'a>> {
+        self.hint().map(|hint| Box::new(hint) as _)
+    }
+}
+
+/// A [`RuneError`] with the source it points into.
+///
+/// Get one from `RuneConfig::report` for errors from a loaded config, or with
+/// `RuneReport::new` for errors from loading, when the config is not there yet; for
+/// those, `RuneError::file` names the file whose text to pass.
+///
+/// # Example
+/// ```
+/// # use rune_cfg::{RuneConfig, RuneReport};
+/// let source = "name \"unclosed\n";
+/// let err = RuneConfig::from_str(source).err().unwrap();
+/// let report = miette::Report::new(RuneReport::new(err, "app.rune", source));
+/// ```
+#[derive(Debug)]
+pub struct RuneReport {
+    error: RuneError,
+    source: NamedSource<String>,
+    label: Option<LabeledSpan>,
+}
+
+impl RuneReport {
+    /// `error`, found in `source` read from the file `name`.
+    pub fn new(error: RuneError, name: impl AsRef<str>, source: impl Into<String>) -> Self {
+        let source = source.into();
+        let label = label_for(&error, &source);
+        Self {
+            error,
+            source: NamedSource::new(name, source),
+            label,
+        }
+    }
+
+    pub fn error(&self) -> &RuneError {
+        &self.error
+    }
+
+    pub fn into_error(self) -> RuneError {
+        self.error
+    }
+}
+
+impl fmt::Display for RuneReport {
+    /// The error without its hint and code, which miette shows on their own.
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        // The file is the report's source name, so it is not repeated here.
+        let mut error = match &self.error {
+            RuneError::InFile { source, .. } => (**source).clone(),
+            error => error.clone(),
+        };
+        match &mut error {
+            RuneError::SyntaxError { hint, code, .. }
+            | RuneError::InvalidToken { hint, code, .. }
+            | RuneError::UnexpectedEof { hint, code, .. }
+            | RuneError::TypeError { hint, code, .. }
+            | RuneError::UnclosedString { hint, code, .. }
+            | RuneError::UnexpectedCharacter { hint, code, .. }
+            | RuneError::FileError { hint, code, .. }
+            | RuneError::RuntimeError { hint, code, .. }
+            | RuneError::ValidationError { hint, code, .. } => {
+                *hint = None;
+                *code = None;
+            }
+            RuneError::InFile { .. } => {}
+        }
+        write!(f, "{}", error)
+    }
+}
+
+impl std::error::Error for RuneReport {
+    fn source(&self) -> Option<&(dyn std::error::Error + 'static)> {
+        self.error.source()
+    }
+}
+
+impl Diagnostic for RuneReport {
+    fn code<'a>(&'a self) -> Option<Box<dyn fmt::Display + 'a>> {
+        Diagnostic::code(&self.error)
+    }
+
+    fn help<'a>(&'a self) -> Option<Box<dyn fmt::Display + 'a>> {
+        self.error.help()
+    }
+
+    fn source_code(&self) -> Option<&dyn SourceCode> {
+        Some(&self.source)
+    }
+
+    fn labels(&self) -> Option<Box<dyn Iterator<Item = LabeledSpan> + '_>> {
+        self.label
+            .clone()
+            .map(|label| Box::new(std::iter::once(label)) as _)
+    }
+}
+
+/// Where `error` points in `source`: the parser's span when it has one, otherwise
+/// the character at its line and column, or the whole line when the column is unknown.
+fn label_for(error: &RuneError, source_text: &str) -> Option<LabeledSpan> {
+    let (line, column, text) = match error {
+        RuneError::InFile { source, .. } => return label_for(source, source_text),
+        RuneError::SyntaxError {
+            span: Some(span), ..
+        } => {
+            let len = span.end.saturating_sub(span.start);
+            return Some(LabeledSpan::new(Some("here".into()), span.start, len));
+        }
+        RuneError::UnclosedString { line, column, .. } => {
+            (*line, *column, "this string is never closed")
+        }
+        RuneError::UnexpectedCharacter { line, column, .. } => {
+            (*line, *column, "unexpected character")
+        }
+        RuneError::SyntaxError { line, column, .. }
+        | RuneError::InvalidToken { line, column, .. }
+        | RuneError::UnexpectedEof { line, column, .. }
+        | RuneError::TypeError { line, column, .. }
+        | RuneError::ValidationError { line, column, .. } => (*line, *column, "here"),
+        RuneError::FileError { .. } | RuneError::RuntimeError { .. } => return None,
+    };
+
+    // Lines and columns count from 1; 0 means unknown.
+    let (line_start, text_of_line) = source_text
+        .split_inclusive('\n')
+        .scan(0, |start, text| {
+            let line = (*start, text);
+            *start += text.len();
+            Some(line)
+        })
+        .nth(line.checked_sub(1)?)?;
+    let text_of_line = text_of_line.trim_end_matches(['\n', '\r']);
+
+    let (offset, len) = if column == 0 {
+        let trimmed = text_of_line.trim_start();
+        (text_of_line.len() - trimmed.len(), trimmed.trim_end().len())
+    } else {
+        let (offset, ch) = text_of_line.char_indices().nth(column - 1)?;
+        (offset, ch.len_utf8())
+    };
+    Some(LabeledSpan::new(
+        Some(text.into()),
+        line_start + offset,
+        len,
+    ))
+}
+
+#[cfg(test)]
+mod tests {
+    use miette::{Diagnostic, NarratableReportHandler};
+
+    use super::RuneReport;
+    use crate::RuneConfig;
+
+    #[test]
+    fn test_report_labels_unclosed_string() {
+        let source = "name \"demo\"\ngreeting \"hello\nport 8080\n";
+        let err = RuneConfig::from_str(source)
+            .err()
+            .expect("an unclosed string");
+        let report = RuneReport::new(err, "app.rune", source);
+
+        let labels: Vec<_> = report.labels().unwrap().collect();
+        assert_eq!(labels.len(), 1);
+        // The opening quote on line 2.
+        assert_eq!(labels[0].offset(), source.find("\"hello").unwrap());
+        assert_eq!(labels[0].len(), 1);
+        assert_eq!(labels[0].label(), Some("this string is never closed"));
+        assert_eq!(report.code().unwrap().to_string(), "rune::103");
+
+        let mut rendered = String::new();
+        NarratableReportHandler::new()
+            .render_report(&mut rendered, &report)
+            .unwrap();
+        assert!(rendered.contains("app.rune"));
+        assert!(rendered.contains("greeting \"hello"));
+        assert!(!rendered.contains("Code: 103"));
+    }
+
+    #[test]
+    fn test_config_report_points_at_the_key() {
+        let config = RuneConfig::from_str("app:\n  port \"eighty\"\nend\n").unwrap();
+        let err = config.get::<u16>("app.port").unwrap_err();
+        let report = config.report(err);
+
+        let label = report.labels().unwrap().next().unwrap();
+        assert_eq!(label.offset(), "app:\n  ".len());
+        assert_eq!(label.len(), "port \"eighty\"".len());
+    }
+}
@@ -1,196 +1,544 @@
 // Author: Dustin Pilgrim
 // License: MIT
 
+use std::collections::HashMap;
 use std::env;
-use std::sync::{Mutex, OnceLock};
+use std::ffi::OsString;
+use std::sync::{Arc, Mutex, OnceLock};
 use std::time::{Duration, Instant};
 
-use sysinfo::System;
+use sysinfo::{
+    CpuRefreshKind, Disks, MINIMUM_CPU_UPDATE_INTERVAL, Networks, ProcessRefreshKind,
+    ProcessesToUpdate, System,
+};
+
+use indexmap::IndexMap;
 
-use crate::RuneError;
 use crate::ast::Value;
-use crate::utils::{format_bytes, format_uptime};
+use crate::utils::{format_bytes, format_unix_timestamp, format_uptime};
+use crate::{RuneError, RuneErrorCode};
+
+/// Resolves references in an application-defined `$` namespace, e.g. `$vault.db_password`.
+///
+/// Register one with [`RuneConfig::register_provider`](crate::RuneConfig::register_provider).
+/// `path` holds the segments after the namespace, so `$vault.db.password` passes
+/// `["db", "password"]`.
+pub trait ResolverProvider: Send + Sync {
+    fn resolve(&self, path: &[String]) -> Result<Value, RuneError>;
+}
+
+/// A closure registered with `register_namespace`.
+struct FnProvider<F>(F);
+
+impl<F> ResolverProvider for FnProvider<F>
+where
+    F: Fn(&[String]) -> Result<Value, RuneError> + Send + Sync,
+{
+    fn resolve(&self, path: &[String]) -> Result<Value, RuneError> {
+        (self.0)(path)
+    }
+}
+
+/// Future returned by [`AsyncResolverProvider::resolve`].
+#[cfg(feature = "tokio")]
+pub type ResolveFuture<'a> =
+    std::pin::Pin<Box<dyn std::future::Future<Output = Result<Value, RuneError>> + Send + 'a>>;
+
+/// Like [`ResolverProvider`], for namespaces whose values come from slow or remote
+/// sources. Only [`RuneConfig::get_value_async`](crate::RuneConfig::get_value_async)
+/// and `get_async` consult these; the sync getters leave such references unresolved.
+#[cfg(feature = "tokio")]
+pub trait AsyncResolverProvider: Send + Sync {
+    fn resolve<'a>(&'a self, path: &'a [String]) -> ResolveFuture<'a>;
+}
 
 /// Cache for sysinfo::System to avoid allocating and refreshing on every $sys lookup.
 struct SysCache {
     sys: System,
     last_mem_refresh: Instant,
+    /// CPU usage is measured between two refreshes, so remember when the last one was.
+    last_cpu_refresh: Option<Instant>,
 }
 
-static SYS_CACHE: OnceLock<Mutex<SysCache>> = OnceLock::new();
+static SYS_CACHE: OnceLock<Arc<Mutex<SysCache>>> = OnceLock::new();
 
-fn sys_cache() -> &'static Mutex<SysCache> {
-    SYS_CACHE.get_or_init(|| {
-        Mutex::new(SysCache {
-            sys: System::new(), // NOT new_all()
-            // Force first access to refresh memory if requested.
-            last_mem_refresh: Instant::now() - Duration::from_secs(3600),
+/// Memory and swap figures read together, so `$sys.memory_free` and `memory_used`
+/// in one config describe the same moment.
+#[derive(Clone, Copy)]
+struct MemorySnapshot {
+    total_memory: u64,
+    free_memory: u64,
+    used_memory: u64,
+    total_swap: u64,
+    free_swap: u64,
+    used_swap: u64,
+}
+
+impl MemorySnapshot {
+    fn read(sys: &System) -> Self {
+        #[cfg(test)]
+        MEMORY_READS.with(|reads| reads.set(reads.get() + 1));
+        Self {
+            total_memory: sys.total_memory(),
+            free_memory: sys.free_memory(),
+            used_memory: sys.used_memory(),
+            total_swap: sys.total_swap(),
+            free_swap: sys.free_swap(),
+            used_swap: sys.used_swap(),
+        }
+    }
+}
+
+// How many memory snapshots this thread has read; parsing stays on one thread, so
+// tests can count the reads of their own parses.
+#[cfg(test)]
+thread_local! {
+    static MEMORY_READS: std::cell::Cell<usize> = const { std::cell::Cell::new(0) };
+}
+
+/// The process-wide System cache, shared by every context.
+fn sys_cache() -> Arc<Mutex<SysCache>> {
+    SYS_CACHE
+        .get_or_init(|| {
+            Arc::new(Mutex::new(SysCache {
+                sys: System::new(), // NOT new_all()
+                // Force first access to refresh memory if requested.
+                last_mem_refresh: Instant::now() - Duration::from_secs(3600),
+                last_cpu_refresh: None,
+            }))
         })
-    })
+        .clone()
+}
+
+/// Everything `$` resolution reads besides the config itself: environment overrides,
+/// the cached `System` behind `$sys`, `$runtime` values and namespace providers.
+///
+/// [`RuneConfig`](crate::RuneConfig) owns one. Build your own and load with
+/// [`RuneConfig::from_str_with_context`](crate::RuneConfig::from_str_with_context) to
+/// control what `$env` sees, e.g. in tests:
+///
+/// ```
+/// use rune_cfg::{ResolverContext, RuneConfig};
+///
+/// let context = ResolverContext::with_env([("PORT", "9000")]);
+/// let config = RuneConfig::from_str_with_context("port $env.PORT\n", context).unwrap();
+/// assert_eq!(config.get::<u16>("port").unwrap(), 9000);
+/// ```
+#[derive(Clone)]
+pub struct ResolverContext {
+    env: HashMap<String, String>,
+    inherit_env: bool, // Fall back to the process environment
+    sys: Arc<Mutex<SysCache>>,
+    memory: Option<Arc<OnceLock<MemorySnapshot>>>, // Set for a parse, see `for_load`
+    pub(crate) runtime: IndexMap<String, Value>,
+    pub(crate) providers: IndexMap<String, Arc<dyn ResolverProvider>>,
 }
 
-/// Expands a dollar if it refers to $env or $sys.
-/// Otherwise, keeps it as a Reference.
-pub fn expand_dollar_string(s: &str) -> Result<Value, RuneError> {
-    // Fast path: if no '$', return plain string
-    if !s.contains('$') {
-        return Ok(Value::String(s.to_string()));
+impl Default for ResolverContext {
+    fn default() -> Self {
+        Self::new()
     }
+}
 
-    // If the whole string looks like just a single $reference
-    if s.starts_with('$') && !s[1..].contains(' ') && !s[1..].contains('/') {
-        // Parse it as a reference
-        let mut chars = s.chars().peekable();
-        chars.next(); // consume '$'
+impl ResolverContext {
+    /// A context that reads the process environment and shares the process-wide
+    /// `System` cache.
+    pub fn new() -> Self {
+        Self {
+            env: HashMap::new(),
+            inherit_env: true,
+            sys: sys_cache(),
+            memory: None,
+            runtime: IndexMap::new(),
+            providers: IndexMap::new(),
+        }
+    }
 
-        let mut path = Vec::new();
-        let mut ns = String::new();
-        while let Some(&ch) = chars.peek() {
-            if ch.is_alphanumeric() || ch == '_' || ch == '-' {
-                ns.push(ch);
-                chars.next();
-            } else {
-                break;
-            }
+    /// A context whose `$env` sees only `vars`, never the process environment.
+    pub fn with_env<K, V>(vars: impl IntoIterator<Item = (K, V)>) -> Self
+    where
+        K: Into<String>,
+        V: Into<String>,
+    {
+        Self {
+            env: vars
+                .into_iter()
+                .map(|(k, v)| (k.into(), v.into()))
+                .collect(),
+            inherit_env: false,
+            ..Self::new()
         }
-        path.push(ns.clone());
-
-        while let Some(&ch) = chars.peek() {
-            if ch == '.' {
-                chars.next();
-                let mut seg = String::new();
-                while let Some(&ch2) = chars.peek() {
-                    if ch2.is_alphanumeric() || ch2 == '_' || ch2 == '-' {
-                        seg.push(ch2);
-                        chars.next();
-                    } else {
-                        break;
-                    }
-                }
-                if seg.is_empty() {
-                    return Err(RuneError::SyntaxError {
-                        message: "Expected identifier after '.'".into(),
-                        line: 0,
-                        column: 0,
-                        hint: None,
-                        code: Some(210),
-                    });
-                }
-                path.push(seg);
-            } else {
-                break;
-            }
+    }
+
+    /// Override one environment variable for `$env`, leaving the others as they are.
+    pub fn set_env(&mut self, name: impl Into<String>, value: impl Into<String>) {
+        self.env.insert(name.into(), value.into());
+    }
+
+    /// Register a value for `$runtime.<key>`.
+    pub fn set_runtime(&mut self, key: impl Into<String>, value: impl Into<Value>) {
+        self.runtime.insert(key.into(), value.into());
+    }
+
+    /// Register a provider for `$<namespace>.<path>` references.
+    pub fn register_provider(
+        &mut self,
+        namespace: impl Into<String>,
+        provider: impl ResolverProvider + 'static,
+    ) {
+        self.providers.insert(namespace.into(), Arc::new(provider));
+    }
+
+    /// Like `register_provider`, with a closure as the provider.
+    pub fn register_namespace<F>(&mut self, namespace: impl Into<String>, resolve: F)
+    where
+        F: Fn(&[String]) -> Result<Value, RuneError> + Send + Sync + 'static,
+    {
+        self.register_provider(namespace, FnProvider(resolve));
+    }
+
+    /// The variable `name` as `$env` sees it: an override if there is one, otherwise
+    /// the process environment unless this context was built `with_env`.
+    pub fn env_var(&self, name: &str) -> Option<String> {
+        match self.env.get(name) {
+            Some(value) => Some(value.clone()),
+            None if self.inherit_env => env::var(name).ok(),
+            None => None,
         }
+    }
 
-        // Only treat as pure reference when the whole string is exactly "$ns.path".
-        // Otherwise (e.g. "$var.mod+r"), fall through to inline interpolation logic.
-        if chars.peek().is_none() && !path.first().is_some_and(|p| p.is_empty()) {
-            return match path[0].as_str() {
-                "env" => Ok(Value::String(resolve_env(&path)?)),
-                "sys" => Ok(Value::String(resolve_sys(&path)?)),
-                _ => Ok(Value::Reference(path)),
-            };
+    /// Every variable `$env` can see, overrides included.
+    pub(crate) fn env_vars(&self) -> Vec<(String, String)> {
+        let mut vars: HashMap<String, String> = if self.inherit_env {
+            env::vars().collect()
+        } else {
+            HashMap::new()
+        };
+        vars.extend(self.env.clone());
+        vars.into_iter().collect()
+    }
+
+    fn env_var_os(&self, name: &str) -> Option<OsString> {
+        match self.env.get(name) {
+            Some(value) => Some(value.into()),
+            None if self.inherit_env => env::var_os(name),
+            None => None,
         }
     }
 
-    // Otherwise: do inline interpolation → replace $env/$sys in string
-    let mut result = String::new();
-    let mut chars = s.chars().peekable();
+    /// `self` with `higher`'s env overrides, `$runtime` values and providers added,
+    /// `higher` winning on conflicts.
+    pub(crate) fn overlay(&self, higher: &ResolverContext) -> ResolverContext {
+        let mut merged = self.clone();
+        merged.env.extend(higher.env.clone());
+        merged.runtime.extend(higher.runtime.clone());
+        merged.providers.extend(higher.providers.clone());
+        merged
+    }
 
-    while let Some(ch) = chars.next() {
-        if ch == '$' {
-            // Parse path after $
-            let mut ns = String::new();
-            while let Some(&c) = chars.peek() {
-                if c.is_alphanumeric() || c == '_' || c == '-' {
-                    ns.push(c);
-                    chars.next();
-                } else {
-                    break;
-                }
+    /// A copy of `self` for one parse: memory and swap keys all read a single
+    /// snapshot, taken on first use, instead of the live figures.
+    pub(crate) fn for_load(&self) -> ResolverContext {
+        ResolverContext {
+            memory: Some(Arc::new(OnceLock::new())),
+            ..self.clone()
+        }
+    }
+
+    /// The context used by parsers built without one.
+    pub(crate) fn global() -> &'static ResolverContext {
+        static GLOBAL: OnceLock<ResolverContext> = OnceLock::new();
+        GLOBAL.get_or_init(ResolverContext::new)
+    }
+}
+
+/// Expands `$env`, `$sys` and the clock namespaces in `s`, where every `$` starts an
+/// interpolation. A string that is exactly one other `$ns.path` becomes a `Namespaced`
+/// reference, and one that contains such references becomes `Value::Interpolated`.
+pub fn expand_dollar_string(s: &str, ctx: &ResolverContext) -> Result<Value, RuneError> {
+    let pieces: Vec<&str> = s.split('$').collect();
+    expand_template(&pieces, ctx)
+}
+
+/// Like `expand_dollar_string`, for a string given as the text before its first `$`
+/// and after each one, as in `Token::Template`, so a piece may hold an escaped `$`.
+pub(crate) fn expand_template<S: AsRef<str>>(
+    pieces: &[S],
+    ctx: &ResolverContext,
+) -> Result<Value, RuneError> {
+    let Some((first, rest)) = pieces.split_first() else {
+        return Ok(Value::String(String::new()));
+    };
+
+    // Only treat as pure reference when the whole string is exactly "$ns.path".
+    // Otherwise (e.g. "$var.mod+r"), fall through to inline interpolation logic.
+    if let [only] = rest
+        && first.as_ref().is_empty()
+    {
+        let (path, tail) = read_dollar_path(only.as_ref())?;
+        if tail.is_empty() && !path[0].is_empty() {
+            match path[0].as_str() {
+                "env" => return Ok(Value::String(resolve_env(&path, ctx)?)),
+                "sys" => return Ok(Value::String(resolve_sys(&path, ctx)?)),
+                // "$date.log" is a file name, handled by the interpolation below.
+                ns if is_clock_namespace(ns) => {}
+                _ => return Ok(Value::Namespaced(path)),
             }
-            let mut path = vec![ns.clone()];
-
-            while let Some(&c) = chars.peek() {
-                if c == '.' {
-                    chars.next();
-                    let mut seg = String::new();
-                    while let Some(&c2) = chars.peek() {
-                        if c2.is_alphanumeric() || c2 == '_' || c2 == '-' {
-                            seg.push(c2);
-                            chars.next();
-                        } else {
-                            break;
-                        }
-                    }
-                    if seg.is_empty() {
-                        return Err(RuneError::SyntaxError {
-                            message: "Expected identifier after '.'".into(),
-                            line: 0,
-                            column: 0,
-                            hint: None,
-                            code: Some(210),
-                        });
-                    }
-                    path.push(seg);
-                } else {
-                    break;
+        }
+    }
+
+    // Otherwise: do inline interpolation → replace $env/$sys in the text, keeping
+    // other references as parts
+    let mut parts = Vec::new();
+    let mut text = first.as_ref().to_string();
+    for piece in rest {
+        let piece = piece.as_ref();
+        let ns_end = piece.find(|c| !is_path_char(c)).unwrap_or(piece.len());
+        let ns = &piece[..ns_end];
+
+        // A `$` not followed by a name is kept as it is.
+        if ns.is_empty() {
+            text.push('$');
+            text.push_str(piece);
+            continue;
+        }
+
+        // Clock namespaces take no segments inside strings, so "$date.log" keeps ".log".
+        if is_clock_namespace(ns) {
+            text.push_str(&resolve_clock(&[ns.to_string()])?);
+            text.push_str(&piece[ns_end..]);
+            continue;
+        }
+
+        let (path, tail) = read_dollar_path(piece)?;
+
+        // `$env.NAME:-default` inside a string: the default runs to the next whitespace.
+        if path[0] == "env"
+            && let Some(after) = tail.strip_prefix(":-")
+        {
+            let (default, after) =
+                after.split_at(after.find(char::is_whitespace).unwrap_or(after.len()));
+            let value = lookup_env(&path, ctx)?.filter(|value| !value.is_empty());
+            text.push_str(value.as_deref().unwrap_or(default));
+            text.push_str(after);
+            continue;
+        }
+
+        match path[0].as_str() {
+            "env" => text.push_str(&resolve_env(&path, ctx)?),
+            "sys" => text.push_str(&resolve_sys(&path, ctx)?),
+            _ => {
+                if !text.is_empty() {
+                    parts.push(Value::String(std::mem::take(&mut text)));
                 }
+                parts.push(Value::Namespaced(path));
             }
+        }
+        text.push_str(tail);
+    }
 
-            let replacement = match path[0].as_str() {
-                "env" => resolve_env(&path)?,
-                "sys" => resolve_sys(&path)?,
-                _ => format!("${}", path.join(".")),
-            };
-            result.push_str(&replacement);
-        } else {
-            result.push(ch);
+    if parts.is_empty() {
+        return Ok(Value::String(text));
+    }
+    if !text.is_empty() {
+        parts.push(Value::String(text));
+    }
+    Ok(Value::Interpolated(parts))
+}
+
+fn is_path_char(c: char) -> bool {
+    c.is_alphanumeric() || c == '_' || c == '-'
+}
+
+/// The `ns.segment...` path at the start of `s` (just after a `$`), and the rest of `s`.
+pub(crate) fn read_dollar_path(s: &str) -> Result<(Vec<String>, &str), RuneError> {
+    let end = s.find(|c| !is_path_char(c)).unwrap_or(s.len());
+    let mut path = vec![s[..end].to_string()];
+    let mut rest = &s[end..];
+
+    while let Some(after_dot) = rest.strip_prefix('.') {
+        let end = after_dot
+            .find(|c| !is_path_char(c))
+            .unwrap_or(after_dot.len());
+        if end == 0 {
+            return Err(RuneError::SyntaxError {
+                message: "Expected identifier after '.'".into(),
+                line: 0,
+                column: 0,
+                span: None,
+                hint: None,
+                code: Some(RuneErrorCode::InvalidReference.code()),
+            });
         }
+        path.push(after_dot[..end].to_string());
+        rest = &after_dot[end..];
     }
 
-    Ok(Value::String(result))
+    Ok((path, rest))
 }
 
-/// Resolve a `Value::Reference` during evaluation
-pub fn resolve_reference_value(value: &Value) -> Result<Value, RuneError> {
+/// Resolve a `Value::Reference` or `Value::Namespaced` during evaluation
+pub fn resolve_reference_value(value: &Value, ctx: &ResolverContext) -> Result<Value, RuneError> {
     match value {
-        Value::Reference(path) if !path.is_empty() => match path[0].as_str() {
-            "env" => Ok(Value::String(resolve_env(path)?)),
-            "sys" => Ok(Value::String(resolve_sys(path)?)),
-            _ => Ok(value.clone()), // let globals handle later
-        },
+        Value::Reference(path) | Value::Namespaced(path) if !path.is_empty() => {
+            match path[0].as_str() {
+                "env" => Ok(Value::String(resolve_env(path, ctx)?)),
+                "sys" => resolve_sys_value(path, ctx),
+                "env_list" => resolve_env_list(path, ctx),
+                ns if is_clock_namespace(ns) => Ok(Value::String(resolve_clock(path)?)),
+                _ => Ok(value.clone()), // let globals handle later
+            }
+        }
         _ => Ok(value.clone()),
     }
 }
 
-/// Parse $ references into a resolved value (for $env/$sys) or Reference (for others)
+/// Parse $ references into a resolved value (for $env/$sys) or Namespaced (for others)
 /// This is called by the parser when it encounters a $ token outside of strings
-pub fn parse_dollar_reference(path: Vec<String>) -> Result<Value, RuneError> {
+pub fn parse_dollar_reference(
+    path: Vec<String>,
+    ctx: &ResolverContext,
+) -> Result<Value, RuneError> {
     if path.is_empty() {
-        return Ok(Value::Reference(path));
+        return Ok(Value::Namespaced(path));
     }
 
     match path[0].as_str() {
-        "env" => Ok(Value::String(resolve_env(&path)?)),
-        "sys" => Ok(Value::String(resolve_sys(&path)?)),
-        "runtime" => Ok(Value::Reference(path)), // runtime is resolved later
-        _ => Ok(Value::Reference(path)),
+        "env" => Ok(Value::String(resolve_env(&path, ctx)?)),
+        "sys" => resolve_sys_value(&path, ctx),
+        "env_list" => resolve_env_list(&path, ctx),
+        "date" | "time" | "datetime" => Ok(Value::String(resolve_clock(&path)?)),
+        "runtime" => Ok(Value::Namespaced(path)), // runtime is resolved later
+        _ => Ok(Value::Namespaced(path)),
     }
 }
 
+fn is_clock_namespace(ns: &str) -> bool {
+    matches!(ns, "date" | "time" | "datetime")
+}
+
+/// `$date`, `$time` and `$datetime`: the current local time, formatted as
+/// `2024-05-01`, `13:45:00` and RFC 3339 by default.
+///
+/// A further segment is a strftime format, quoted since `%` cannot start an
+/// identifier: `$date."%d.%m.%Y"`.
+#[cfg(feature = "chrono")]
+fn resolve_clock(path: &[String]) -> Result<String, RuneError> {
+    use chrono::format::{Item, StrftimeItems};
+
+    let format = if path.len() > 1 {
+        path[1..].join(".")
+    } else {
+        match path[0].as_str() {
+            "date" => "%Y-%m-%d".to_string(),
+            "time" => "%H:%M:%S".to_string(),
+            _ => "%Y-%m-%dT%H:%M:%S%:z".to_string(),
+        }
+    };
+
+    let items: Vec<Item> = StrftimeItems::new(&format).collect();
+    if items.iter().any(|item| matches!(item, Item::Error)) {
+        return Err(RuneError::SyntaxError {
+            message: format!("Invalid format '{}' for ${}", format, path[0]),
+            line: 0,
+            column: 0,
+            span: None,
+            hint: Some("Use strftime specifiers such as \"%Y-%m-%d\" or \"%H:%M\"".into()),
+            code: Some(RuneErrorCode::InvalidDateFormat.code()),
+        });
+    }
+
+    Ok(chrono::Local::now()
+        .format_with_items(items.into_iter())
+        .to_string())
+}
+
+#[cfg(not(feature = "chrono"))]
+fn resolve_clock(path: &[String]) -> Result<String, RuneError> {
+    Err(RuneError::SyntaxError {
+        message: format!("${} requires the chrono feature", path[0]),
+        line: 0,
+        column: 0,
+        span: None,
+        hint: Some(
+            "Enable the `chrono` feature of rune-cfg to use $date, $time and $datetime".into(),
+        ),
+        code: Some(RuneErrorCode::ChronoDisabled.code()),
+    })
+}
+
 /// $env resolver
-fn resolve_env(path: &[String]) -> Result<String, RuneError> {
+fn resolve_env(path: &[String], ctx: &ResolverContext) -> Result<String, RuneError> {
+    Ok(lookup_env(path, ctx)?.unwrap_or_default())
+}
+
+/// Resolve `$env.NAME:-default`: the variable's value when set and not empty,
+/// otherwise `default`.
+pub fn resolve_env_or(
+    path: &[String],
+    default: Value,
+    ctx: &ResolverContext,
+) -> Result<Value, RuneError> {
+    Ok(lookup_env(path, ctx)?
+        .filter(|value| !value.is_empty())
+        .map_or(default, Value::String))
+}
+
+/// Resolve `$env.NAME!`: the variable's value, or an error when it is unset.
+pub fn resolve_env_strict(path: &[String], ctx: &ResolverContext) -> Result<Value, RuneError> {
+    lookup_env(path, ctx)?
+        .map(Value::String)
+        .ok_or_else(|| RuneError::RuntimeError {
+            message: format!("Environment variable {} is not set", path[1]),
+            hint: Some(format!(
+                "Set {} before loading, or use $env.{}:-default for a fallback",
+                path[1], path[1]
+            )),
+            code: Some(RuneErrorCode::RequiredEnvVarNotSet.code()),
+        })
+}
+
+/// `$env_list.<NAME>`: the variable split on the platform path separator (`:`, or `;`
+/// on Windows) into an array of strings. Empty entries are dropped, so an unset or
+/// empty variable gives `[]`.
+fn resolve_env_list(path: &[String], ctx: &ResolverContext) -> Result<Value, RuneError> {
+    if path.len() != 2 {
+        return Err(RuneError::SyntaxError {
+            message: format!("Invalid $env_list path: {}", path.join(".")),
+            line: 0,
+            column: 0,
+            span: None,
+            hint: Some("Use $env_list.<VAR_NAME>".into()),
+            code: Some(RuneErrorCode::InvalidDollarVariable.code()),
+        });
+    }
+
+    let entries = ctx
+        .env_var_os(&path[1])
+        .map(|raw| {
+            env::split_paths(&raw)
+                .map(|p| p.to_string_lossy().into_owned())
+                .filter(|p| !p.is_empty())
+                .map(Value::String)
+                .collect()
+        })
+        .unwrap_or_default();
+    Ok(Value::Array(entries))
+}
+
+/// The variable named by an `$env.<NAME>` path, or `None` when it is unset.
+fn lookup_env(path: &[String], ctx: &ResolverContext) -> Result<Option<String>, RuneError> {
     if path.len() != 2 {
         return Err(RuneError::SyntaxError {
             message: format!("Invalid $env path: {}", path.join(".")),
             line: 0,
             column: 0,
+            span: None,
             hint: Some("Use $env.<VAR_NAME>".into()),
-            code: Some(209),
+            code: Some(RuneErrorCode::InvalidDollarVariable.code()),
         });
     }
-    Ok(env::var(&path[1]).unwrap_or_default())
+    Ok(ctx.env_var(&path[1]))
 }
 
 /// Helper: consistent "unresolved" error for $sys.<key>
@@ -199,45 +547,284 @@ fn sys_unresolved(key: &str) -> RuneError {
         message: format!("Unable to resolve $sys.{}", key),
         line: 0,
         column: 0,
+        span: None,
         hint: None,
-        code: Some(213),
+        code: Some(RuneErrorCode::SysValueUnavailable.code()),
     }
 }
 
-/// Run `f` with a cached System, refreshing memory at most once per second.
-fn with_sys_memory_refreshed<F>(f: F) -> Result<String, RuneError>
-where
-    F: FnOnce(&System) -> Result<String, RuneError>,
-{
-    let cache = sys_cache();
-    let mut guard = cache.lock().unwrap();
+/// Memory and swap figures: the parse's snapshot for a `for_load` context, otherwise
+/// read from the cached System, refreshing memory at most once per second.
+fn memory_snapshot(ctx: &ResolverContext) -> MemorySnapshot {
+    let read = || {
+        let mut guard = ctx.sys.lock().unwrap();
 
-    // Rate-limit memory refresh (tune as desired)
-    if guard.last_mem_refresh.elapsed() >= Duration::from_secs(1) {
-        guard.sys.refresh_memory();
-        guard.last_mem_refresh = Instant::now();
+        // Rate-limit memory refresh (tune as desired)
+        if guard.last_mem_refresh.elapsed() >= Duration::from_secs(1) {
+            guard.sys.refresh_memory();
+            guard.last_mem_refresh = Instant::now();
+        }
+
+        MemorySnapshot::read(&guard.sys)
+    };
+
+    match &ctx.memory {
+        Some(snapshot) => *snapshot.get_or_init(read),
+        None => read(),
     }
+}
 
+/// Disk space for `$sys.disk_*`, summed over all disks or for one mount point.
+///
+/// `mount` matches a mount point exactly (`$sys.disk_free."/home"`) or with a leading
+/// `/` added, so `$sys.disk_free.home` works too.
+fn resolve_disk(key: &str, mount: Option<&String>) -> Result<u64, RuneError> {
+    let disks = Disks::new_with_refreshed_list();
+    let selected: Vec<_> = match mount {
+        Some(mount) => {
+            let rooted = format!("/{}", mount);
+            let found: Vec<_> = disks
+                .list()
+                .iter()
+                .filter(|d| {
+                    d.mount_point()
+                        .to_str()
+                        .is_some_and(|p| p == mount || p == rooted)
+                })
+                .collect();
+            if found.is_empty() {
+                return Err(RuneError::SyntaxError {
+                    message: format!("No disk mounted at '{}' for $sys.{}", mount, key),
+                    line: 0,
+                    column: 0,
+                    span: None,
+                    hint: Some("Use a mount point such as $sys.disk_free.\"/home\"".into()),
+                    code: Some(RuneErrorCode::SysValueUnavailable.code()),
+                });
+            }
+            found
+        }
+        None => disks.list().iter().collect(),
+    };
+
+    let total: u64 = selected.iter().map(|d| d.total_space()).sum();
+    let free: u64 = selected.iter().map(|d| d.available_space()).sum();
+    Ok(match key {
+        "disk_total" | "disk-total" => total,
+        "disk_free" | "disk-free" => free,
+        _ => total.saturating_sub(free),
+    })
+}
+
+/// Load average for `$sys.load_average` ("1m 5m 15m") and `$sys.load1/5/15`.
+fn resolve_load_average(key: &str, window: Option<&String>) -> Result<String, RuneError> {
+    if cfg!(windows) {
+        return Err(RuneError::SyntaxError {
+            message: format!("$sys.{} is not available on this platform", key),
+            line: 0,
+            column: 0,
+            span: None,
+            hint: Some("Load average is only reported on Unix-like systems".into()),
+            code: Some(RuneErrorCode::SysValueUnavailable.code()),
+        });
+    }
+
+    let load = System::load_average();
+    // `$sys.load_average.five` is the same as `$sys.load5`.
+    Ok(match (key, window.map(String::as_str)) {
+        ("load1", _) | (_, Some("one")) => format!("{:.2}", load.one),
+        ("load5", _) | (_, Some("five")) => format!("{:.2}", load.five),
+        ("load15", _) | (_, Some("fifteen")) => format!("{:.2}", load.fifteen),
+        (_, Some(other)) => {
+            return Err(RuneError::SyntaxError {
+                message: format!("Unknown load average window: {}", other),
+                line: 0,
+                column: 0,
+                span: None,
+                hint: Some("Use $sys.load_average.one, .five or .fifteen".into()),
+                code: Some(RuneErrorCode::UnknownLoadAverageWindow.code()),
+            });
+        }
+        _ => format!("{:.2} {:.2} {:.2}", load.one, load.five, load.fifteen),
+    })
+}
+
+/// `$sys.ip`: the first non-loopback IPv4 address, interfaces taken in name order.
+/// `$sys.ip.<iface>`: that interface's IPv4 address, or its first address otherwise.
+fn resolve_ip(iface: Option<&String>) -> Result<String, RuneError> {
+    let networks = Networks::new_with_refreshed_list();
+    let mut interfaces: Vec<_> = networks.list().iter().collect();
+    interfaces.sort_by(|a, b| a.0.cmp(b.0));
+
+    let found = match iface {
+        Some(iface) => interfaces
+            .iter()
+            .find(|(name, _)| *name == iface)
+            .and_then(|(_, data)| {
+                let addrs: Vec<_> = data.ip_networks().iter().map(|n| n.addr).collect();
+                addrs
+                    .iter()
+                    .find(|addr| addr.is_ipv4())
+                    .or(addrs.first())
+                    .copied()
+            }),
+        None => interfaces
+            .iter()
+            .flat_map(|(_, data)| data.ip_networks())
+            .map(|n| n.addr)
+            .find(|addr| addr.is_ipv4() && !addr.is_loopback()),
+    };
+
+    found.map(|addr| addr.to_string()).ok_or_else(|| {
+        let target = iface.map_or("ip".to_string(), |i| format!("ip.{}", i));
+        RuneError::SyntaxError {
+            message: format!("No address found for $sys.{}", target),
+            line: 0,
+            column: 0,
+            span: None,
+            hint: Some("Check that the interface exists and has an address".into()),
+            code: Some(RuneErrorCode::SysValueUnavailable.code()),
+        }
+    })
+}
+
+/// Overall CPU usage in percent, as a string with one decimal.
+///
+/// sysinfo computes usage as the difference between two refreshes at least
+/// `MINIMUM_CPU_UPDATE_INTERVAL` apart (about 200 ms). The first lookup therefore
+/// blocks for that interval; later ones report usage since the previous lookup and
+/// only wait if it was too recent.
+fn resolve_cpu_usage(ctx: &ResolverContext) -> Result<String, RuneError> {
+    let mut guard = ctx.sys.lock().unwrap();
+
+    match guard.last_cpu_refresh {
+        None => {
+            guard.sys.refresh_cpu_usage();
+            std::thread::sleep(MINIMUM_CPU_UPDATE_INTERVAL);
+        }
+        Some(last) if last.elapsed() < MINIMUM_CPU_UPDATE_INTERVAL => {
+            std::thread::sleep(MINIMUM_CPU_UPDATE_INTERVAL - last.elapsed());
+        }
+        Some(_) => {}
+    }
+    guard.sys.refresh_cpu_usage();
+    guard.last_cpu_refresh = Some(Instant::now());
+
+    Ok(format!("{:.1}", guard.sys.global_cpu_usage()))
+}
+
+/// Number of running processes. Refreshes the process list without per-process details.
+fn resolve_process_count(ctx: &ResolverContext) -> Result<String, RuneError> {
+    let mut guard = ctx.sys.lock().unwrap();
+    guard.sys.refresh_processes_specifics(
+        ProcessesToUpdate::All,
+        true,
+        ProcessRefreshKind::nothing(),
+    );
+    Ok(guard.sys.processes().len().to_string())
+}
+
+/// The current user's login name, falling back to `$USER`/`$USERNAME`.
+fn resolve_user(ctx: &ResolverContext) -> Result<String, RuneError> {
+    whoami::fallible::username()
+        .ok()
+        .or_else(|| ctx.env_var("USER"))
+        .or_else(|| ctx.env_var("USERNAME"))
+        .filter(|name| !name.is_empty())
+        .ok_or_else(|| RuneError::SyntaxError {
+            message: "Unable to determine the current user for $sys.user".into(),
+            line: 0,
+            column: 0,
+            span: None,
+            hint: Some("Set $USER, or use $env.USER with a :- default".into()),
+            code: Some(RuneErrorCode::SysValueUnavailable.code()),
+        })
+}
+
+/// Run `f` with the cached System once its CPU list is filled. The list is read on
+/// first use; usage figures are not needed for counts or the brand.
+fn with_cpu_list<T>(ctx: &ResolverContext, f: impl FnOnce(&System) -> T) -> T {
+    let mut guard = ctx.sys.lock().unwrap();
+    if guard.sys.cpus().is_empty() {
+        guard.sys.refresh_cpu_list(CpuRefreshKind::nothing());
+    }
     f(&guard.sys)
 }
 
+/// Byte counts for the memory, swap and disk keys, or `None` for other keys.
+fn sys_byte_count(
+    key: &str,
+    path: &[String],
+    ctx: &ResolverContext,
+) -> Option<Result<u64, RuneError>> {
+    let bytes = match key {
+        // Memory needs refresh; we refresh memory only, and rate-limit it
+        "memory_total" | "memory-total" => memory_snapshot(ctx).total_memory,
+        "memory_free" | "memory-free" => memory_snapshot(ctx).free_memory,
+        "memory_used" | "memory-used" => memory_snapshot(ctx).used_memory,
+
+        // Swap is read together with memory; swap-less systems report 0.
+        "swap_total" | "swap-total" | "total_swap" | "total-swap" => {
+            memory_snapshot(ctx).total_swap
+        }
+        "swap_free" | "swap-free" | "free_swap" | "free-swap" => memory_snapshot(ctx).free_swap,
+        "swap_used" | "swap-used" | "used_swap" | "used-swap" => memory_snapshot(ctx).used_swap,
+
+        "disk_total" | "disk-total" | "disk_free" | "disk-free" | "disk_used" | "disk-used" => {
+            return Some(resolve_disk(key, path.get(2)));
+        }
+        _ => return None,
+    };
+    Some(Ok(bytes))
+}
+
+/// `$sys` in value position: counts and byte sizes are integers, `cpu_usage` and the
+/// single load averages are floats, and everything else is the string `resolve_sys`
+/// gives, which is also what string interpolation uses.
+fn resolve_sys_value(path: &[String], ctx: &ResolverContext) -> Result<Value, RuneError> {
+    let key = path.get(1).map_or("", String::as_str);
+    if let Some(bytes) = sys_byte_count(key, path, ctx) {
+        return bytes.map(Value::from);
+    }
+
+    let text = resolve_sys(path, ctx)?;
+    let typed = match key {
+        "cpu_count" | "cpu-count" | "cpu_physical" | "cpu-physical" | "process_count"
+        | "process-count" => text.parse::<i64>().ok().map(Value::Int),
+        "cpu_usage" | "cpu-usage" | "load1" | "load5" | "load15" => {
+            text.parse::<f64>().ok().map(Value::Float)
+        }
+        "load_average" | "load-average" if path.len() > 2 => {
+            text.parse::<f64>().ok().map(Value::Float)
+        }
+        _ => None,
+    };
+    Ok(typed.unwrap_or(Value::String(text)))
+}
+
 /// $sys resolver using sysinfo crate (cached, targeted refresh)
-fn resolve_sys(path: &[String]) -> Result<String, RuneError> {
+fn resolve_sys(path: &[String], ctx: &ResolverContext) -> Result<String, RuneError> {
     // Get the key and ensure it exists
     let key = path.get(1).ok_or_else(|| RuneError::SyntaxError {
         message: format!("Missing key in $sys path: {}", path.join(".")),
         line: 0,
         column: 0,
+        span: None,
         hint: Some("Use $sys.<KEY>".into()),
-        code: Some(211),
+        code: Some(RuneErrorCode::MissingSysKey.code()),
     })?;
 
+    if let Some(bytes) = sys_byte_count(key, path, ctx) {
+        return bytes.map(format_bytes);
+    }
+
     match key.as_str() {
         // These are static / cheap; no System instance needed
         "os" => System::name().ok_or_else(|| sys_unresolved(key)),
         "kernel_version" | "kernel-version" => System::kernel_version().ok_or_else(|| sys_unresolved(key)),
         "os_version" | "os-version" => System::os_version().ok_or_else(|| sys_unresolved(key)),
         "hostname" => System::host_name().ok_or_else(|| sys_unresolved(key)),
+        "user" | "username" => resolve_user(ctx),
         "cpu_arch" | "cpu-arch" => {
             let arch = System::cpu_arch();
             if arch.is_empty() {
@@ -247,33 +834,41 @@ fn resolve_sys(path: &[String]) -> Result<String, RuneError> {
             }
         }
         "uptime" => Ok(format_uptime(System::uptime())),
-
-        // Needs cached System; no refresh required for cpu list length
-        "cpu_count" | "cpu-count" => {
-            let cache = sys_cache();
-            let guard = cache.lock().unwrap();
-            Ok(guard.sys.cpus().len().to_string())
+        "boot_time" | "boot-time" => match System::boot_time() {
+            0 => Err(sys_unresolved(key)),
+            secs => Ok(format_unix_timestamp(secs)),
+        },
+        "load_average" | "load-average" | "load1" | "load5" | "load15" => {
+            resolve_load_average(key, path.get(2))
         }
+        "ip" => resolve_ip(path.get(2)),
+        "cpu_usage" | "cpu-usage" => resolve_cpu_usage(ctx),
+        "process_count" | "process-count" => resolve_process_count(ctx),
 
-        // Memory needs refresh; we refresh memory only, and rate-limit it
-        "memory_total" | "memory-total" => {
-            with_sys_memory_refreshed(|sys| Ok(format_bytes(sys.total_memory())))
-        }
-        "memory_free" | "memory-free" => {
-            with_sys_memory_refreshed(|sys| Ok(format_bytes(sys.free_memory())))
-        }
-        "memory_used" | "memory-used" => {
-            with_sys_memory_refreshed(|sys| Ok(format_bytes(sys.used_memory())))
+        "cpu_count" | "cpu-count" => Ok(with_cpu_list(ctx, |sys| sys.cpus().len()).to_string()),
+        "cpu_brand" | "cpu-brand" => {
+            let brand = with_cpu_list(ctx, |sys| {
+                sys.cpus()
+                    .first()
+                    .map(|cpu| cpu.brand().trim().to_string())
+            });
+            brand
+                .filter(|b| !b.is_empty())
+                .ok_or_else(|| sys_unresolved(key))
         }
+        "cpu_physical" | "cpu-physical" => System::physical_core_count()
+            .map(|n| n.to_string())
+            .ok_or_else(|| sys_unresolved(key)),
 
         other => Err(RuneError::SyntaxError {
             message: format!("Unknown $sys key: {}", other),
             line: 0,
             column: 0,
+            span: None,
             hint: Some(
-                "Available keys: os, kernel_version, os_version, hostname, cpu_arch, cpu_count, memory_total, memory_free, memory_used, uptime".into()
+                "Available keys: os, kernel_version, os_version, hostname, user, username, cpu_arch, cpu_brand, cpu_count, cpu_physical, memory_total, memory_free, memory_used, swap_total (total_swap), swap_free (free_swap), swap_used (used_swap), disk_total, disk_free, disk_used, load_average (.one, .five, .fifteen), load1, load5, load15, ip, cpu_usage, process_count, boot_time, uptime".into()
             ),
-            code: Some(212),
+            code: Some(RuneErrorCode::UnknownSysKey.code()),
         }),
     }
 }
@@ -285,6 +880,164 @@ mod tests {
     use super::*;
     use crate::ast::Value;
 
+    #[test]
+    fn test_load_context_reuses_one_memory_snapshot() {
+        let reads = || MEMORY_READS.with(|reads| reads.get());
+        let path = |key: &str| ["sys".to_string(), key.to_string()];
+
+        let ctx = ResolverContext::new().for_load();
+        let before = reads();
+        for key in [
+            "memory_total",
+            "memory_free",
+            "memory_used",
+            "swap_free",
+            "memory_used",
+        ] {
+            resolve_sys(&path(key), &ctx).unwrap();
+        }
+        assert_eq!(reads() - before, 1);
+
+        // Without `for_load`, every lookup reads memory afresh.
+        let live = ResolverContext::new();
+        let before = reads();
+        resolve_sys(&path("memory_free"), &live).unwrap();
+        resolve_sys(&path("memory_used"), &live).unwrap();
+        assert_eq!(reads() - before, 2);
+    }
+
+    #[test]
+    fn test_memory_keys_share_one_snapshot_within_a_load() {
+        let source = "total $sys.memory_total\nused $sys.memory_used\nfree $sys.memory_free\nused_again $sys.memory_used\nswap $sys.swap_free\n";
+        let reads = || MEMORY_READS.with(|reads| reads.get());
+        let before = reads();
+
+        let mut parser = crate::parser::Parser::new(source).unwrap();
+        let doc = parser.parse_document().unwrap();
+        assert_eq!(reads() - before, 1);
+
+        // A second parse takes its own snapshot.
+        let mut parser = crate::parser::Parser::new(source).unwrap();
+        parser.parse_document().unwrap();
+        assert_eq!(reads() - before, 2);
+
+        let value = |key: &str| {
+            doc.globals
+                .iter()
+                .find(|(k, _)| k == key)
+                .map(|(_, v)| v.clone())
+                .unwrap()
+        };
+        assert_eq!(value("used"), value("used_again"));
+    }
+
+    fn sys(key: &str) -> String {
+        resolve_sys(
+            &["sys".to_string(), key.to_string()],
+            ResolverContext::global(),
+        )
+        .unwrap()
+    }
+
+    /// Parse a `format_bytes` string such as "15.5 GB" back to approximate bytes.
+    fn approx_bytes(s: &str) -> f64 {
+        let (n, unit) = s.split_once(' ').unwrap();
+        let scale = match unit {
+            "B" => 1.0,
+            "KB" => 1024.0,
+            "MB" => 1024.0 * 1024.0,
+            "GB" => 1024.0 * 1024.0 * 1024.0,
+            _ => 1024.0 * 1024.0 * 1024.0 * 1024.0,
+        };
+        n.parse::<f64>().unwrap() * scale
+    }
+
+    #[cfg(feature = "chrono")]
+    #[test]
+    fn test_clock_namespaces_resolve() {
+        let parse = |source: &str| {
+            let mut parser = crate::parser::Parser::new(source).unwrap();
+            let doc = parser.parse_document().unwrap();
+            match &doc.globals[0].1 {
+                Value::String(s) => s.clone(),
+                other => panic!("expected string, got {:?}", other),
+            }
+        };
+
+        let date = parse("d $date\n");
+        assert_eq!(date.len(), 10);
+        assert_eq!(date.matches('-').count(), 2);
+
+        let time = parse("t $time.\"%H:%M\"\n");
+        assert_eq!(time.len(), 5);
+        assert_eq!(&time[2..3], ":");
+
+        let stamp = parse("s $datetime\n");
+        assert!(chrono::DateTime::parse_from_rfc3339(&stamp).is_ok());
+
+        let custom = parse("c $date.\"%d.%m.%Y\"\n");
+        assert_eq!(custom.len(), 10);
+        assert_eq!(custom.matches('.').count(), 2);
+
+        let file = parse("f \"app-$date.log\"\n");
+        assert!(file.starts_with("app-") && file.ends_with(".log"));
+        assert_eq!(file.len(), "app-2024-01-01.log".len());
+    }
+
+    #[cfg(feature = "chrono")]
+    #[test]
+    fn test_clock_invalid_format_errors() {
+        let mut parser = crate::parser::Parser::new("d $date.\"%Q\"\n").unwrap();
+        let err = parser.parse_document().unwrap_err();
+        assert!(matches!(
+            err,
+            RuneError::SyntaxError {
+                code: Some(218),
+                ..
+            }
+        ));
+    }
+
+    #[test]
+    fn test_cpu_count_refreshes_cpu_list() {
+        assert!(sys("cpu_count").parse::<usize>().unwrap() > 0);
+        assert_eq!(sys("cpu-count"), sys("cpu_count"));
+    }
+
+    #[test]
+    fn test_cpu_brand_and_physical_cores() {
+        assert!(!sys("cpu_brand").is_empty());
+
+        let logical = sys("cpu_count").parse::<usize>().unwrap();
+        let physical = sys("cpu_physical").parse::<usize>().unwrap();
+        assert!(physical > 0);
+        assert!(physical <= logical);
+        assert!(matches!(
+            parse_dollar_reference(vec!["sys".into(), "cpu_physical".into()], ResolverContext::global()),
+            Ok(Value::Int(n)) if n as usize == physical
+        ));
+    }
+
+    #[test]
+    fn test_memory_keys_resolve_with_memory_refresh_only() {
+        let total = approx_bytes(&sys("memory_total"));
+        let free = approx_bytes(&sys("memory_free"));
+        let used = approx_bytes(&sys("memory_used"));
+        assert!(total > 0.0);
+        assert!(free <= total * 1.01);
+        assert!(used <= total * 1.01);
+
+        let swap_total = approx_bytes(&sys("swap_total"));
+        assert!(approx_bytes(&sys("swap_used")) <= swap_total * 1.01 + 1.0);
+    }
+
+    #[test]
+    fn test_static_keys_resolve_without_system_refresh() {
+        for key in ["os", "kernel_version", "hostname", "cpu_arch", "uptime"] {
+            assert!(!sys(key).is_empty(), "$sys.{} should resolve", key);
+        }
+    }
+
     #[test]
     fn test_sys_expansion() {
         // List of keys we want to test
@@ -293,17 +1046,25 @@ mod tests {
             "kernel_version",
             "os_version",
             "hostname",
+            "user",
             "cpu_arch",
             "cpu_count",
             "memory_total",
             "memory_free",
             "memory_used",
+            "swap_total",
+            "swap_free",
+            "swap_used",
+            "disk_total",
+            "disk_free",
+            "disk_used",
             "uptime",
         ];
 
         for &key in &keys {
             let input = format!("$sys.{}", key);
-            let result = expand_dollar_string(&input).expect(&format!("Failed on key: {}", key));
+            let result = expand_dollar_string(&input, ResolverContext::global())
+                .expect(&format!("Failed on key: {}", key));
 
             match result {
                 Value::String(s) => {
@@ -318,7 +1079,7 @@ mod tests {
     #[test]
     fn test_sys_unknown_key() {
         let input = "$sys.unknown_key";
-        let err = expand_dollar_string(input).unwrap_err();
+        let err = expand_dollar_string(input, ResolverContext::global()).unwrap_err();
         match err {
             RuneError::SyntaxError { code, .. } => {
                 assert_eq!(code, Some(212));
@@ -330,7 +1091,7 @@ mod tests {
     #[test]
     fn test_sys_missing_key() {
         let input = "$sys";
-        let err = expand_dollar_string(input).unwrap_err();
+        let err = expand_dollar_string(input, ResolverContext::global()).unwrap_err();
         match err {
             RuneError::SyntaxError { code, .. } => {
                 assert_eq!(code, Some(211));
@@ -347,7 +1108,8 @@ mod tests {
         }
 
         let input = "$env.RUNE_TEST_ENV";
-        let result = expand_dollar_string(input).expect("Failed to expand env var");
+        let result = expand_dollar_string(input, ResolverContext::global())
+            .expect("Failed to expand env var");
 
         match result {
             Value::String(s) => assert_eq!(s, "hello_world"),
@@ -358,7 +1120,7 @@ mod tests {
     #[test]
     fn test_env_missing_key() {
         let input = "$env";
-        let err = expand_dollar_string(input).unwrap_err();
+        let err = expand_dollar_string(input, ResolverContext::global()).unwrap_err();
         match err {
             RuneError::SyntaxError { code, .. } => {
                 assert_eq!(code, Some(209));
@@ -374,7 +1136,8 @@ mod tests {
         }
 
         let path = vec!["env".to_string(), "TEST_VAR".to_string()];
-        let result = parse_dollar_reference(path).expect("Failed to parse $env reference");
+        let result = parse_dollar_reference(path, ResolverContext::global())
+            .expect("Failed to parse $env reference");
 
         match result {
             Value::String(s) => assert_eq!(s, "test_value"),
@@ -385,11 +1148,241 @@ mod tests {
     #[test]
     fn test_parse_dollar_reference_sys() {
         let path = vec!["sys".to_string(), "hostname".to_string()];
-        let result = parse_dollar_reference(path).expect("Failed to parse $sys reference");
+        let result = parse_dollar_reference(path, ResolverContext::global())
+            .expect("Failed to parse $sys reference");
 
         match result {
             Value::String(s) => assert!(!s.is_empty(), "Hostname should not be empty"),
             _ => panic!("Expected Value::String for $sys.hostname"),
         }
     }
+
+    #[test]
+    fn test_env_default_used_when_unset() {
+        unsafe {
+            std::env::remove_var("RUNE_TEST_UNSET_PORT");
+        }
+
+        let input = "$env.RUNE_TEST_UNSET_PORT:-8080";
+        let result = expand_dollar_string(input, ResolverContext::global())
+            .expect("Failed to expand env default");
+        assert_eq!(result, Value::String("8080".into()));
+
+        let input = "http://$env.RUNE_TEST_UNSET_HOST:-localhost:8080 ok";
+        let result = expand_dollar_string(input, ResolverContext::global())
+            .expect("Failed to expand env default");
+        assert_eq!(result, Value::String("http://localhost:8080 ok".into()));
+    }
+
+    #[test]
+    fn test_env_list_splits_on_path_separator() {
+        let joined = env::join_paths(["/usr/local/bin", "/usr/bin", "/bin"]).unwrap();
+        unsafe {
+            std::env::set_var("RUNE_TEST_LIST_PATH", &joined);
+            std::env::set_var("RUNE_TEST_LIST_EMPTY", "");
+            std::env::remove_var("RUNE_TEST_LIST_UNSET");
+        }
+
+        let list = |name: &str| {
+            parse_dollar_reference(
+                vec!["env_list".to_string(), name.to_string()],
+                ResolverContext::global(),
+            )
+            .unwrap()
+        };
+
+        assert_eq!(
+            list("RUNE_TEST_LIST_PATH"),
+            Value::Array(vec![
+                Value::String("/usr/local/bin".into()),
+                Value::String("/usr/bin".into()),
+                Value::String("/bin".into()),
+            ])
+        );
+        assert_eq!(list("RUNE_TEST_LIST_EMPTY"), Value::Array(vec![]));
+        assert_eq!(list("RUNE_TEST_LIST_UNSET"), Value::Array(vec![]));
+    }
+
+    #[test]
+    fn test_env_default_ignored_when_set() {
+        unsafe {
+            std::env::set_var("RUNE_TEST_SET_PORT", "9090");
+        }
+
+        let input = "$env.RUNE_TEST_SET_PORT:-8080";
+        let result = expand_dollar_string(input, ResolverContext::global())
+            .expect("Failed to expand env default");
+        assert_eq!(result, Value::String("9090".into()));
+
+        let path = vec!["env".to_string(), "RUNE_TEST_SET_PORT".to_string()];
+        let result = resolve_env_or(&path, Value::Int(8080), ResolverContext::global())
+            .expect("Failed to resolve");
+        assert_eq!(result, Value::String("9090".into()));
+    }
+
+    #[test]
+    fn test_sys_byte_metrics_are_byte_formatted() {
+        let units = [" B", " KB", " MB", " GB", " TB"];
+        for key in [
+            "swap_total",
+            "swap_free",
+            "swap_used",
+            "disk_total",
+            "disk_free",
+            "disk_used",
+        ] {
+            let path = vec!["sys".to_string(), key.to_string()];
+            let value =
+                resolve_sys(&path, ResolverContext::global()).expect("disk metric should resolve");
+            assert!(
+                units.iter().any(|unit| value.ends_with(unit)),
+                "$sys.{} = {:?} should be byte-formatted",
+                key,
+                value
+            );
+        }
+    }
+
+    #[test]
+    fn test_sys_disk_unknown_mount() {
+        let path = vec![
+            "sys".to_string(),
+            "disk_free".to_string(),
+            "/definitely/not/mounted".to_string(),
+        ];
+        let err = resolve_sys(&path, ResolverContext::global()).unwrap_err();
+        assert!(matches!(
+            err,
+            RuneError::SyntaxError {
+                code: Some(213),
+                ..
+            }
+        ));
+    }
+
+    #[cfg(unix)]
+    #[test]
+    fn test_sys_load_average_parses_as_numbers() {
+        for key in ["load1", "load5", "load15"] {
+            let value =
+                expand_dollar_string(&format!("$sys.{}", key), ResolverContext::global()).unwrap();
+            let Value::String(s) = value else {
+                panic!("Expected Value::String for $sys.{}", key);
+            };
+            assert!(s.parse::<f64>().is_ok(), "$sys.{} = {:?}", key, s);
+        }
+
+        let Value::String(all) =
+            expand_dollar_string("$sys.load_average", ResolverContext::global()).unwrap()
+        else {
+            panic!("Expected Value::String for $sys.load_average");
+        };
+        let parts: Vec<f64> = all.split(' ').map(|p| p.parse().unwrap()).collect();
+        assert_eq!(parts.len(), 3);
+    }
+
+    #[test]
+    fn test_sys_aliases_and_sub_paths_resolve() {
+        let mut keys = vec![
+            "total_swap",
+            "used_swap",
+            "username",
+            "boot_time",
+            "process_count",
+        ];
+        if cfg!(unix) {
+            keys.extend([
+                "load_average.one",
+                "load_average.five",
+                "load_average.fifteen",
+            ]);
+        }
+        for key in keys {
+            let path: Vec<String> = format!("sys.{}", key)
+                .split('.')
+                .map(String::from)
+                .collect();
+            let value = resolve_sys(&path, ResolverContext::global()).unwrap();
+            assert!(!value.is_empty(), "$sys.{} is empty", key);
+        }
+
+        assert_eq!(sys("total_swap"), sys("swap_total"));
+        if cfg!(unix) {
+            let err = resolve_sys(
+                &["sys".into(), "load_average".into(), "hourly".into()],
+                ResolverContext::global(),
+            )
+            .unwrap_err();
+            assert!(matches!(
+                err,
+                RuneError::SyntaxError {
+                    code: Some(226),
+                    ..
+                }
+            ));
+        }
+    }
+
+    #[test]
+    fn test_sys_ip_is_an_address_or_a_clear_error() {
+        match expand_dollar_string("$sys.ip", ResolverContext::global()) {
+            Ok(Value::String(s)) => {
+                let addr: std::net::IpAddr = s.parse().expect("$sys.ip should be an IP");
+                assert!(addr.is_ipv4() && !addr.is_loopback());
+            }
+            // Hosts without a non-loopback interface (e.g. sandboxes) report an error.
+            Err(RuneError::SyntaxError { code, .. }) => assert_eq!(code, Some(213)),
+            other => panic!("Unexpected result for $sys.ip: {:?}", other),
+        }
+
+        let err = expand_dollar_string("$sys.ip.rune-no-such-iface0", ResolverContext::global())
+            .unwrap_err();
+        assert!(matches!(
+            err,
+            RuneError::SyntaxError {
+                code: Some(213),
+                ..
+            }
+        ));
+    }
+
+    #[test]
+    fn test_sys_process_count_and_cpu_usage() {
+        let Value::String(count) =
+            expand_dollar_string("$sys.process_count", ResolverContext::global()).unwrap()
+        else {
+            panic!("Expected Value::String for $sys.process_count");
+        };
+        assert!(count.parse::<usize>().unwrap() > 0);
+
+        let Value::String(usage) =
+            expand_dollar_string("$sys.cpu_usage", ResolverContext::global()).unwrap()
+        else {
+            panic!("Expected Value::String for $sys.cpu_usage");
+        };
+        let usage: f64 = usage.parse().unwrap();
+        assert!((0.0..=100.0).contains(&usage), "cpu_usage = {}", usage);
+    }
+
+    #[test]
+    fn test_sys_boot_time_is_an_iso_timestamp() {
+        let Value::String(boot) =
+            expand_dollar_string("$sys.boot_time", ResolverContext::global()).unwrap()
+        else {
+            panic!("Expected Value::String for $sys.boot_time");
+        };
+
+        // YYYY-MM-DDTHH:MM:SSZ
+        assert_eq!(boot.len(), 20, "boot_time = {:?}", boot);
+        assert!(boot.ends_with('Z') && &boot[10..11] == "T");
+        let year: u32 = boot[..4].parse().unwrap();
+        assert!(year >= 1970);
+    }
+
+    #[test]
+    fn test_format_unix_timestamp() {
+        assert_eq!(format_unix_timestamp(0), "1970-01-01T00:00:00Z");
+        assert_eq!(format_unix_timestamp(1_704_067_200), "2024-01-01T00:00:00Z");
+        assert_eq!(format_unix_timestamp(951_827_696), "2000-02-29T12:34:56Z");
+    }
 }
@@ -1,8 +1,8 @@
 // Author: Dustin Pilgrim
 // License: MIT
 
-use crate::RuneError;
 use crate::ast::Value;
+use crate::{RuneError, RuneErrorCode};
 
 #[derive(Debug, Clone, PartialEq)]
 pub struct SchemaDocument {
@@ -43,13 +43,63 @@ pub enum SchemaType {
     Object,
 }
 
+/// Expected `@metadata` keys and their types, checked by `RuneConfig::validate_metadata`.
+///
+/// # Examples
+/// ```
+/// use rune_cfg::{MetadataSchema, SchemaType};
+///
+/// let schema = MetadataSchema::new()
+///     .required("version", SchemaType::String)
+///     .optional("port", SchemaType::Number);
+/// assert_eq!(schema.fields.len(), 2);
+/// ```
+#[derive(Debug, Clone, PartialEq, Default)]
+pub struct MetadataSchema {
+    pub fields: Vec<MetadataField>,
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct MetadataField {
+    pub name: String,
+    pub kind: SchemaType,
+    pub required: bool,
+}
+
+impl MetadataSchema {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Declare a metadata key that must be present and match `kind`.
+    pub fn required(mut self, name: impl Into<String>, kind: SchemaType) -> Self {
+        self.fields.push(MetadataField {
+            name: name.into(),
+            kind,
+            required: true,
+        });
+        self
+    }
+
+    /// Declare a metadata key that must match `kind` when present.
+    pub fn optional(mut self, name: impl Into<String>, kind: SchemaType) -> Self {
+        self.fields.push(MetadataField {
+            name: name.into(),
+            kind,
+            required: false,
+        });
+        self
+    }
+}
+
 impl SchemaDocument {
     pub fn from_file<P: AsRef<std::path::Path>>(path: P) -> Result<Self, RuneError> {
         let content = std::fs::read_to_string(&path).map_err(|e| RuneError::FileError {
             message: format!("Failed to read schema file: {}", e),
             path: path.as_ref().to_string_lossy().to_string(),
             hint: Some("Check that the schema file exists and is readable".into()),
-            code: Some(601),
+            code: Some(RuneErrorCode::SchemaFileRead.code()),
+            source: Some(e.into()),
         })?;
         Self::from_str(&content)
     }
@@ -310,8 +360,11 @@ fn parse_literal_value(raw: &str) -> Value {
     if raw == "null" || raw == "None" {
         return Value::Null;
     }
+    if let Ok(n) = raw.parse::<i64>() {
+        return Value::Int(n);
+    }
     if let Ok(n) = raw.parse::<f64>() {
-        return Value::Number(n);
+        return Value::Float(n);
     }
     if let Some(s) = parse_quoted(raw) {
         return Value::String(s);
@@ -417,8 +470,9 @@ fn schema_error(message: impl Into<String>, line: usize, hint: impl Into<String>
         message: message.into(),
         line,
         column: 0,
+        span: None,
         hint: Some(hint.into()),
-        code: Some(600),
+        code: Some(RuneErrorCode::InvalidSchema.code()),
     }
 }
 
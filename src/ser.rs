@@ -0,0 +1,167 @@
+// Author: Dustin Pilgrim
+// License: MIT
+
+//! serde support: [`Value`] and [`Document`] are [`Serialize`], so a parsed config can
+//! be written out in any serde format.
+//!
+//! Values are written as they were parsed, without resolving anything:
+//! - Regex → `{"regex": "pattern"}`
+//! - Bytes → base64 string
+//! - DateTime → RFC 3339 string
+//! - References → dotted string path, `$`-prefixed for `$namespace` ones
+//! - Interpolated strings → their text, with `$` references as written
+//! - Conditionals → `{"conditional": {"condition", "then", "else"}}`
+//! - Objects → array of `{"key", "value"}` and `{"if": ...}` entries, which keeps
+//!   their order and any `if` blocks
+//!
+//! A document is a map of `metadata`, `constants` and `globals`, each left out when
+//! empty, and `items`. Resolved values are written through [`Resolved`] instead.
+
+use base64::Engine;
+use base64::engine::general_purpose::STANDARD as BASE64;
+use serde::ser::{Serialize, SerializeMap, Serializer};
+
+use crate::ast::{ConditionalValue, Document, IfBlock, ObjectItem, Value, interpolated_text};
+
+impl Serialize for Value {
+    fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        match self {
+            Value::String(s) => serializer.serialize_str(s),
+            Value::Int(n) => serializer.serialize_i64(*n),
+            Value::Float(n) => serializer.serialize_f64(*n),
+            Value::Bool(b) => serializer.serialize_bool(*b),
+            Value::Array(values) => serializer.collect_seq(values),
+            Value::Interpolated(parts) => serializer.serialize_str(&interpolated_text(parts)),
+            Value::Object(items) => serializer.collect_seq(items),
+            Value::Reference(path) => serializer.serialize_str(&path.join(".")),
+            Value::Namespaced(path) => serializer.serialize_str(&format!("${}", path.join("."))),
+            Value::Regex(re) => {
+                let mut map = serializer.serialize_map(Some(1))?;
+                map.serialize_entry("regex", re.as_str())?;
+                map.end()
+            }
+            Value::Bytes(bytes) => serializer.serialize_str(&BASE64.encode(bytes)),
+            #[cfg(feature = "chrono")]
+            Value::DateTime(timestamp) => serializer.serialize_str(&timestamp.to_rfc3339()),
+            Value::Conditional(conditional) => {
+                let mut map = serializer.serialize_map(Some(1))?;
+                map.serialize_entry("conditional", &**conditional)?;
+                map.end()
+            }
+            Value::Null => serializer.serialize_unit(),
+        }
+    }
+}
+
+impl Serialize for ConditionalValue {
+    fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        let mut map = serializer.serialize_map(Some(3))?;
+        map.serialize_entry("condition", &format!("{:?}", self.condition))?;
+        map.serialize_entry("then", &self.then_value)?;
+        map.serialize_entry("else", &self.else_value)?;
+        map.end()
+    }
+}
+
+impl Serialize for ObjectItem {
+    fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        match self {
+            ObjectItem::Assign(key, value) => {
+                let mut map = serializer.serialize_map(Some(2))?;
+                map.serialize_entry("key", key)?;
+                map.serialize_entry("value", value)?;
+                map.end()
+            }
+            ObjectItem::IfBlock(block) => {
+                let mut map = serializer.serialize_map(Some(1))?;
+                map.serialize_entry("if", block)?;
+                map.end()
+            }
+        }
+    }
+}
+
+impl Serialize for IfBlock {
+    fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        let mut map = serializer.serialize_map(Some(3))?;
+        map.serialize_entry("condition", &format!("{:?}", self.condition))?;
+        map.serialize_entry("then", &self.then_items)?;
+        map.serialize_entry("else", &self.else_items)?;
+        map.end()
+    }
+}
+
+/// A `(key, value)` list written as a map, in order.
+struct Entries<'a>(&'a [(String, Value)]);
+
+impl Serialize for Entries<'_> {
+    fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        serializer.collect_map(self.0.iter().map(|(k, v)| (k, v)))
+    }
+}
+
+impl Serialize for Document {
+    fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        let mut map = serializer.serialize_map(None)?;
+        for (name, entries) in [
+            ("metadata", &self.metadata),
+            ("constants", &self.constants),
+            ("globals", &self.globals),
+        ] {
+            if !entries.is_empty() {
+                map.serialize_entry(name, &Entries(entries))?;
+            }
+        }
+        map.serialize_entry("items", &Entries(&self.items))?;
+        map.end()
+    }
+}
+
+/// A resolved value, whose objects hold only assignments, written with objects as
+/// plain maps in key order. Everything else is written as by `Value`.
+pub(crate) struct Resolved<'a>(pub &'a Value);
+
+impl Serialize for Resolved<'_> {
+    fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        match self.0 {
+            Value::Object(items) => {
+                serializer.collect_map(items.iter().filter_map(|item| match item {
+                    ObjectItem::Assign(key, value) => Some((key, Resolved(value))),
+                    ObjectItem::IfBlock(_) => None,
+                }))
+            }
+            Value::Array(values) => serializer.collect_seq(values.iter().map(Resolved)),
+            other => other.serialize(serializer),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::parser::Parser;
+
+    #[test]
+    fn test_serialize_document_to_yaml() {
+        let mut parser = Parser::new(
+            "@description \"Demo\"\nname \"demo\"\napp:\n  port 8080\n  host name\n  tags [\"a\" \"b\"]\n  pattern r\"^x+$\"\n  proxy null\nend\n",
+        )
+        .unwrap();
+        let doc = parser.parse_document().unwrap();
+
+        let yaml = serde_yaml::to_string(&doc).unwrap();
+        let parsed: serde_yaml::Value = serde_yaml::from_str(&yaml).unwrap();
+
+        assert_eq!(parsed["metadata"]["description"], "Demo");
+        assert_eq!(parsed["globals"]["name"], "demo");
+        assert!(parsed.get("constants").is_none());
+
+        let app = &parsed["items"]["app"];
+        assert_eq!(app[0]["key"], "port");
+        assert_eq!(app[0]["value"], 8080);
+        // References are written as their path, not resolved.
+        assert_eq!(app[1]["value"], "name");
+        assert_eq!(app[2]["value"][1], "b");
+        assert_eq!(app[3]["value"]["regex"], "^x+$");
+        assert!(app[4]["value"].is_null());
+    }
+}
@@ -1,6 +1,10 @@
 // Author: Dustin Pilgrim
 // License: MIT
 
+use std::path::{Path, PathBuf};
+
+use crate::{RuneError, RuneErrorCode};
+
 pub fn format_uptime(seconds: u64) -> String {
     if seconds < 60 {
         format!("{} sec{}", seconds, if seconds != 1 { "s" } else { "" })
@@ -40,3 +44,117 @@ pub fn format_bytes(bytes: u64) -> String {
         format!("{} B", bytes)
     }
 }
+
+/// Format Unix seconds as an ISO-8601 UTC timestamp, e.g. `2024-01-01T00:00:00Z`.
+pub fn format_unix_timestamp(seconds: u64) -> String {
+    let days = (seconds / 86_400) as i64;
+    let secs_of_day = seconds % 86_400;
+
+    // Civil-from-days (Howard Hinnant), valid for the whole proleptic Gregorian range.
+    let z = days + 719_468;
+    let era = z.div_euclid(146_097);
+    let doe = z.rem_euclid(146_097);
+    let yoe = (doe - doe / 1_460 + doe / 36_524 - doe / 146_096) / 365;
+    let doy = doe - (365 * yoe + yoe / 4 - yoe / 100);
+    let mp = (5 * doy + 2) / 153;
+    let day = doy - (153 * mp + 2) / 5 + 1;
+    let month = if mp < 10 { mp + 3 } else { mp - 9 };
+    let year = yoe + era * 400 + i64::from(month <= 2);
+
+    format!(
+        "{:04}-{:02}-{:02}T{:02}:{:02}:{:02}Z",
+        year,
+        month,
+        day,
+        secs_of_day / 3_600,
+        (secs_of_day % 3_600) / 60,
+        secs_of_day % 60
+    )
+}
+
+/// Split a dotted path into segments, honouring quoted segments.
+///
+/// `servers."east.1".host` yields `["servers", "east.1", "host"]`, so keys that
+/// literally contain a dot stay addressable. Both `"` and `'` quotes are accepted.
+pub fn split_path(path: &str) -> Vec<String> {
+    let mut segments = Vec::new();
+    let mut current = String::new();
+    let mut quote: Option<char> = None;
+
+    for ch in path.chars() {
+        match quote {
+            Some(q) if ch == q => quote = None,
+            Some(_) => current.push(ch),
+            None if (ch == '"' || ch == '\'') && current.is_empty() => quote = Some(ch),
+            None if ch == '.' => segments.push(std::mem::take(&mut current)),
+            None => current.push(ch),
+        }
+    }
+    segments.push(current);
+
+    segments
+}
+
+/// Append `key` to a dotted `prefix`, quoting it when it contains a dot so the
+/// result splits back the same way with [`split_path`].
+pub fn join_path(prefix: &str, key: &str) -> String {
+    let segment = if key.contains('.') {
+        let quote = if key.contains('"') { '\'' } else { '"' };
+        format!("{quote}{key}{quote}")
+    } else {
+        key.to_string()
+    };
+
+    if prefix.is_empty() {
+        segment
+    } else {
+        format!("{}.{}", prefix, segment)
+    }
+}
+
+/// Expand "~/" and resolve relative paths against base_dir.
+pub fn resolve_path(raw_path: &str, base_dir: &Path) -> Result<PathBuf, RuneError> {
+    let mut p = if let Some(rest) = raw_path.strip_prefix("~/") {
+        let home = home_dir_fallback().ok_or_else(|| RuneError::FileError {
+            message: "Could not determine home directory for ~ expansion".into(),
+            path: raw_path.to_string(),
+            hint: Some("Set HOME (or USERPROFILE on Windows) or use an absolute path".into()),
+            code: Some(RuneErrorCode::FileUnavailable.code()),
+            source: None,
+        })?;
+        home.join(rest)
+    } else {
+        PathBuf::from(raw_path)
+    };
+
+    if p.is_relative() {
+        p = base_dir.join(p);
+    }
+    Ok(p)
+}
+
+/// Best-effort home directory lookup without external crates.
+fn home_dir_fallback() -> Option<PathBuf> {
+    // Unix-like: HOME
+    if let Some(home) = std::env::var_os("HOME") {
+        if !home.is_empty() {
+            return Some(PathBuf::from(home));
+        }
+    }
+
+    // Windows: USERPROFILE, or HOMEDRIVE + HOMEPATH
+    if let Some(up) = std::env::var_os("USERPROFILE") {
+        if !up.is_empty() {
+            return Some(PathBuf::from(up));
+        }
+    }
+
+    let drive = std::env::var_os("HOMEDRIVE");
+    let path = std::env::var_os("HOMEPATH");
+    match (drive, path) {
+        (Some(d), Some(p)) if !d.is_empty() && !p.is_empty() => {
+            Some(PathBuf::from(d).join(PathBuf::from(p)))
+        }
+        _ => None,
+    }
+}